@@ -0,0 +1,46 @@
+//! 比较完整解码加 image 缩放与缩略图快速路径的耗时：cargo bench --bench thumbnail_decode
+use std::path::PathBuf;
+use criterion::{Criterion, criterion_group, criterion_main};
+use image::{DynamicImage, ImageFormat, RgbImage};
+use image::imageops::FilterType;
+use Wallpaper_Explorer::utils::{load_for_thumbnail, open_oriented, resize_thumbnail};
+
+/// 缩略图的目标尺寸
+const TARGET: (u32, u32) = (200, 150);
+
+/// 生成 8000×6000 的测试 JPEG
+fn create_source() -> PathBuf {
+    let path: PathBuf = std::env::temp_dir()
+        .join(format!("wallpaper-explorer-bench-{}.jpg", std::process::id()));
+    let source: RgbImage = RgbImage::from_fn(8000, 6000, |x, y| image::Rgb([(x % 256) as u8, (y % 256) as u8, ((x + y) % 256) as u8]));
+    source.save_with_format(&path, ImageFormat::Jpeg).unwrap();
+    path
+}
+
+fn bench_thumbnail_decode(c: &mut Criterion) {
+    let path: PathBuf = create_source();
+    let mut group = c.benchmark_group("thumbnail_decode");
+    group.sample_size(10);
+    
+    group.bench_function("open_and_resize", |b| {
+        b.iter(|| open_oriented(&path).unwrap().resize(TARGET.0, TARGET.1, FilterType::Lanczos3))
+    });
+    group.bench_function("fast_path", |b| {
+        b.iter(|| resize_thumbnail(&load_for_thumbnail(&path, TARGET.0).unwrap(), TARGET.0, TARGET.1))
+    });
+    
+    // 已解码的大图只比较缩放本身
+    let decoded: DynamicImage = open_oriented(&path).unwrap();
+    group.bench_function("image_resize", |b| {
+        b.iter(|| decoded.resize_exact(TARGET.0, TARGET.1, FilterType::Lanczos3))
+    });
+    group.bench_function("fast_image_resize", |b| {
+        b.iter(|| resize_thumbnail(&decoded, TARGET.0, TARGET.1))
+    });
+    
+    group.finish();
+    let _ = std::fs::remove_file(&path);
+}
+
+criterion_group!(benches, bench_thumbnail_decode);
+criterion_main!(benches);
\ No newline at end of file
@@ -1,41 +1,373 @@
-use crate::Result;
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use slint::{ComponentHandle, Image, ModelRc, Timer, TimerMode, VecModel};
+
+use crate::{Result, WallpaperError};
+use crate::components::AnimationPlayer;
 use crate::config::Config;
-use crate::services::WallpaperService;
-use crate::ui::MainWindow;
+use crate::models::Wallpaper;
+use crate::services::{BatchReport, CollectionService, RebuildProgress, SmartCollectionService, WallpaperService};
+use crate::ui::{MainWindow, WallpaperThumbnail, get_frame_image, load_thumbnail_image};
+use crate::ui::main_window::MainWindow as Window;
+use crate::utils::decode_frames;
+
+/// 设置页面刷新缩略图重建进度的间隔
+const REBUILD_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 取回后台图像质量分析结果的间隔
+const QUALITY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 预览中推进动图播放的间隔，不长于动图的最短帧延迟
+const PREVIEW_TICK_INTERVAL: Duration = Duration::from_millis(10);
 
 pub struct App {
-    #[allow(dead_code)]
     config: Config,
-    #[allow(dead_code)]
-    wallpaper_service: WallpaperService,
+    /// 与界面回调共享
+    wallpaper_service: Rc<RefCell<WallpaperService>>,
+    /// 成员随壁纸库变化重新计算，后台质量分析的结果也会影响成员
+    smart_collection_service: Rc<RefCell<SmartCollectionService>>,
+    collection_service: CollectionService,
     main_window: MainWindow,
+    /// 预览窗口中打开的壁纸ID及其播放状态
+    preview: Rc<RefCell<Option<(String, AnimationPlayer)>>>,
+    /// 缩略图缓存重建期间定时刷新设置页面的进度
+    rebuild_timer: Timer,
+    /// 推进预览中的动图播放
+    preview_timer: Timer,
+    /// 定时取回后台图像质量分析的结果
+    quality_timer: Timer,
 }
 
 impl App {
     pub fn new() -> Result<Self> {
         let config: Config = Config::load()?;
         let wallpaper_service: WallpaperService = WallpaperService::new(&config)?;
+        let smart_collection_service: SmartCollectionService = SmartCollectionService::new(&config);
+        let collection_service: CollectionService = CollectionService::new(&config)?;
         let main_window: MainWindow = MainWindow::new()?;
         
         Ok(Self {
             config,
-            wallpaper_service,
+            wallpaper_service: Rc::new(RefCell::new(wallpaper_service)),
+            smart_collection_service: Rc::new(RefCell::new(smart_collection_service)),
+            collection_service,
             main_window,
+            preview: Rc::new(RefCell::new(None)),
+            rebuild_timer: Timer::default(),
+            preview_timer: Timer::default(),
+            quality_timer: Timer::default(),
         })
     }
     
-    pub fn run(self) -> Result<()> {
+    pub fn run(mut self) -> Result<()> {
         // 设置事件处理器
         self.setup_event_handlers()?;
         
+        // 扫描失败时仍显示界面，可在设置中修复后重新扫描
+        if let Err(e) = self.scan_wallpapers() {
+            log::error!("扫描壁纸失败: {}", e);
+        }
+        
         // 运行主循环
         self.main_window.run()?;
         
         Ok(())
     }
     
+    /// 扫描壁纸目录，并把在程序外被重命名或移动的壁纸同步到相册
+    pub fn scan_wallpapers(&mut self) -> Result<()> {
+        {
+            let mut wallpaper_service = self.wallpaper_service.borrow_mut();
+            wallpaper_service.scan_wallpapers()?;
+            self.collection_service.replace_ids(wallpaper_service.get_scan_renames())?;
+        }
+        self.refresh_smart_collections();
+        self.update_grid();
+        Ok(())
+    }
+    
+    /// 移动选中的壁纸，相册中的ID随之更新
+    pub fn move_wallpapers(&mut self, ids: &[String], destination: &Path) -> Result<BatchReport> {
+        let report: BatchReport = self.wallpaper_service.borrow_mut().move_wallpapers(ids, destination)?;
+        self.collection_service.replace_ids(&report.renamed)?;
+        self.refresh_smart_collections();
+        self.update_grid();
+        Ok(report)
+    }
+    
+    /// 删除选中的壁纸，并从所有相册中移除
+    pub fn delete_wallpapers(&mut self, ids: &[String]) -> Result<BatchReport> {
+        let report: BatchReport = self.wallpaper_service.borrow_mut().delete_wallpapers(ids);
+        self.collection_service.remove_from_all(&report.succeeded)?;
+        self.refresh_smart_collections();
+        self.update_grid();
+        Ok(report)
+    }
+    
+    pub fn add_tag_to_wallpapers(&mut self, ids: &[String], tag: &str) -> Result<BatchReport> {
+        let report: BatchReport = self.wallpaper_service.borrow_mut().add_tag_to_wallpapers(ids, tag)?;
+        self.refresh_smart_collections();
+        Ok(report)
+    }
+    
+    pub fn remove_tag_from_wallpapers(&mut self, ids: &[String], tag: &str) -> Result<BatchReport> {
+        let report: BatchReport = self.wallpaper_service.borrow_mut().remove_tag_from_wallpapers(ids, tag)?;
+        self.refresh_smart_collections();
+        Ok(report)
+    }
+    
+    pub fn set_rating(&mut self, ids: &[String], rating: u8) -> Result<BatchReport> {
+        let report: BatchReport = self.wallpaper_service.borrow_mut().set_rating(ids, rating)?;
+        self.refresh_smart_collections();
+        Ok(report)
+    }
+    
+    /// 新建智能收藏并保存到配置，返回其ID
+    pub fn create_smart_collection(&mut self, name: &str, query: &str) -> Result<String> {
+        let id: String = self.smart_collection_service.borrow_mut().create(name, query)?.id.clone();
+        self.save_smart_collections()?;
+        Ok(id)
+    }
+    
+    pub fn rename_smart_collection(&mut self, id: &str, name: &str) -> Result<()> {
+        self.smart_collection_service.borrow_mut().rename(id, name)?;
+        self.save_smart_collections()
+    }
+    
+    pub fn update_smart_collection_query(&mut self, id: &str, query: &str) -> Result<()> {
+        self.smart_collection_service.borrow_mut().update_query(id, query)?;
+        self.save_smart_collections()
+    }
+    
+    pub fn delete_smart_collection(&mut self, id: &str) -> Result<()> {
+        self.smart_collection_service.borrow_mut().delete(id)?;
+        self.save_smart_collections()
+    }
+    
+    /// 保存智能收藏，并立即计算新建或修改后的查询的成员
+    fn save_smart_collections(&mut self) -> Result<()> {
+        self.smart_collection_service.borrow().persist(&mut self.config)?;
+        self.refresh_smart_collections();
+        Ok(())
+    }
+    
+    /// 在网格中显示壁纸库的缩略图，透明缩略图叠加棋盘格，动图叠加播放标记
+    fn update_grid(&self) {
+        let service = self.wallpaper_service.borrow();
+        let thumbnails: Vec<WallpaperThumbnail> = service
+            .get_display_wallpapers()
+            .into_iter()
+            .map(|wallpaper| WallpaperThumbnail {
+                id: wallpaper.id.as_str().into(),
+                name: wallpaper.filename.as_str().into(),
+                image: load_grid_thumbnail(wallpaper),
+            })
+            .collect();
+        self.main_window.inner().set_thumbnails(ModelRc::new(VecModel::from(thumbnails)));
+    }
+    
+    fn refresh_smart_collections(&self) {
+        self.wallpaper_service
+            .borrow()
+            .refresh_smart_collections(&mut self.smart_collection_service.borrow_mut());
+    }
+    
     fn setup_event_handlers(&self) -> Result<()> {
-        // TODO: 实现事件处理器绑定
+        self.setup_preview();
+        self.setup_cache_rebuild();
+        self.setup_quality_analysis();
         Ok(())
     }
+    
+    /// 点击网格中的壁纸打开预览：动图自动播放，暂停后可逐帧选择并保存为静态壁纸文件
+    fn setup_preview(&self) {
+        let window = self.main_window.inner();
+        
+        let service: Rc<RefCell<WallpaperService>> = Rc::clone(&self.wallpaper_service);
+        let preview: Rc<RefCell<Option<(String, AnimationPlayer)>>> = Rc::clone(&self.preview);
+        let weak = window.as_weak();
+        window.on_open_wallpaper(move |id| {
+            let Some(window) = weak.upgrade() else {
+                return;
+            };
+            match open_preview(&service.borrow(), &id) {
+                Ok(player) => {
+                    window.set_preview_animated(player.get_frame_count() > 1);
+                    show_preview_frame(&window, &player);
+                    *preview.borrow_mut() = Some((id.to_string(), player));
+                    window.set_previewing(true);
+                }
+                Err(e) => log::warn!("打开预览失败 {}: {}", id, e),
+            }
+        });
+        
+        let preview: Rc<RefCell<Option<(String, AnimationPlayer)>>> = Rc::clone(&self.preview);
+        let weak = window.as_weak();
+        window.on_close_preview(move || {
+            *preview.borrow_mut() = None;
+            if let Some(window) = weak.upgrade() {
+                window.set_previewing(false);
+            }
+        });
+        
+        let preview: Rc<RefCell<Option<(String, AnimationPlayer)>>> = Rc::clone(&self.preview);
+        let weak = window.as_weak();
+        window.on_toggle_preview_playback(move || {
+            if let (Some(window), Some((_, player))) = (weak.upgrade(), preview.borrow_mut().as_mut()) {
+                player.toggle();
+                show_preview_frame(&window, player);
+            }
+        });
+        
+        let preview: Rc<RefCell<Option<(String, AnimationPlayer)>>> = Rc::clone(&self.preview);
+        let weak = window.as_weak();
+        window.on_step_preview_frame(move |forward| {
+            if let (Some(window), Some((_, player))) = (weak.upgrade(), preview.borrow_mut().as_mut()) {
+                player.step(forward);
+                show_preview_frame(&window, player);
+            }
+        });
+        
+        let service: Rc<RefCell<WallpaperService>> = Rc::clone(&self.wallpaper_service);
+        let preview: Rc<RefCell<Option<(String, AnimationPlayer)>>> = Rc::clone(&self.preview);
+        let weak = window.as_weak();
+        window.on_save_preview_frame(move || {
+            let Some(window) = weak.upgrade() else {
+                return;
+            };
+            match prepare_preview_frame(&service.borrow(), &preview.borrow()) {
+                Ok(path) => window.set_preview_status(format!("已保存当前帧: {}", path.display()).into()),
+                Err(e) => window.set_preview_status(format!("保存当前帧失败: {}", e).into()),
+            }
+        });
+        
+        let preview: Rc<RefCell<Option<(String, AnimationPlayer)>>> = Rc::clone(&self.preview);
+        let weak = window.as_weak();
+        let mut last_tick: Instant = Instant::now();
+        self.preview_timer.start(TimerMode::Repeated, PREVIEW_TICK_INTERVAL, move || {
+            let now: Instant = Instant::now();
+            let elapsed: Duration = now - last_tick;
+            last_tick = now;
+            
+            if let (Some(window), Some((_, player))) = (weak.upgrade(), preview.borrow_mut().as_mut()) {
+                if player.advance(elapsed) {
+                    show_preview_frame(&window, player);
+                }
+            }
+        });
+    }
+    
+    /// 扫描后图像质量在后台分析，定时将结果应用到壁纸库，并重新计算按质量筛选的智能收藏
+    fn setup_quality_analysis(&self) {
+        let service: Rc<RefCell<WallpaperService>> = Rc::clone(&self.wallpaper_service);
+        let smart_collections: Rc<RefCell<SmartCollectionService>> = Rc::clone(&self.smart_collection_service);
+        self.quality_timer.start(TimerMode::Repeated, QUALITY_POLL_INTERVAL, move || {
+            let result: Result<usize> = service.borrow_mut().poll_quality_analysis();
+            match result {
+                Ok(0) => {}
+                Ok(_) => {
+                    service.borrow().refresh_smart_collections(&mut smart_collections.borrow_mut());
+                }
+                Err(e) => log::warn!("应用图像质量分析结果失败: {}", e),
+            }
+        });
+    }
+    
+    /// 设置页面的缩略图缓存重建：在后台重建，定时刷新进度，结束后合并重建得到的缓存记录
+    fn setup_cache_rebuild(&self) {
+        let window = self.main_window.inner();
+        
+        let service: Rc<RefCell<WallpaperService>> = Rc::clone(&self.wallpaper_service);
+        let weak = window.as_weak();
+        window.on_rebuild_cache(move || {
+            let Some(window) = weak.upgrade() else {
+                return;
+            };
+            match service.borrow_mut().start_thumbnail_rebuild() {
+                Ok(()) => {
+                    window.set_rebuilding_cache(true);
+                    window.set_cache_status("正在准备重建缩略图...".into());
+                }
+                Err(e) => window.set_cache_status(format!("无法重建缩略图缓存: {}", e).into()),
+            }
+        });
+        
+        let service: Rc<RefCell<WallpaperService>> = Rc::clone(&self.wallpaper_service);
+        window.on_cancel_cache_rebuild(move || service.borrow().cancel_thumbnail_rebuild());
+        
+        let service: Rc<RefCell<WallpaperService>> = Rc::clone(&self.wallpaper_service);
+        let weak = window.as_weak();
+        self.rebuild_timer.start(TimerMode::Repeated, REBUILD_POLL_INTERVAL, move || {
+            let Some(window) = weak.upgrade() else {
+                return;
+            };
+            let mut service = service.borrow_mut();
+            let Some(progress) = service.get_thumbnail_rebuild_progress() else {
+                return;
+            };
+            window.set_cache_status(format!("重建缩略图: {}/{}", progress.completed, progress.total).into());
+            
+            match service.finish_thumbnail_rebuild() {
+                Ok(None) => {}
+                Ok(Some(progress)) => {
+                    window.set_rebuilding_cache(false);
+                    window.set_cache_status(format_rebuild_result(&progress).into());
+                }
+                Err(e) => {
+                    window.set_rebuilding_cache(false);
+                    window.set_cache_status(format!("缩略图重建失败: {}", e).into());
+                }
+            }
+        });
+    }
+}
+
+/// 在预览窗口中打开壁纸，动图解码所有帧，播放器创建后自动播放
+fn open_preview(service: &WallpaperService, id: &str) -> Result<AnimationPlayer> {
+    let path: PathBuf = service
+        .get_wallpaper_by_id(id)
+        .map(|wallpaper: &Wallpaper| wallpaper.path.clone())
+        .ok_or_else(|| WallpaperError::Service(format!("找不到壁纸: {}", id)))?;
+    Ok(AnimationPlayer::new(decode_frames(&path)?))
+}
+
+/// 将预览中当前显示的帧保存为静态壁纸文件，暂停并逐帧选择后调用
+fn prepare_preview_frame(service: &WallpaperService, preview: &Option<(String, AnimationPlayer)>) -> Result<PathBuf> {
+    let (id, player) = preview
+        .as_ref()
+        .ok_or_else(|| WallpaperError::Service("没有打开的预览".to_string()))?;
+    service.prepare_wallpaper_frame(id, player.get_current_index())
+}
+
+fn show_preview_frame(window: &Window, player: &AnimationPlayer) {
+    if let Some(frame) = player.get_current_frame() {
+        window.set_preview_frame(get_frame_image(frame));
+    }
+    window.set_preview_playing(player.is_playing());
+    if player.get_frame_count() > 1 {
+        window.set_preview_status(format!("第 {}/{} 帧", player.get_current_index() + 1, player.get_frame_count()).into());
+    } else {
+        window.set_preview_status("".into());
+    }
+}
+
+/// 缩略图缺失或无法读取时显示空白格子
+fn load_grid_thumbnail(wallpaper: &Wallpaper) -> Image {
+    let Some(thumbnail_path) = &wallpaper.thumbnail_path else {
+        return Image::default();
+    };
+    load_thumbnail_image(thumbnail_path, wallpaper.is_animated()).unwrap_or_else(|e| {
+        log::warn!("读取缩略图失败 {:?}: {}", thumbnail_path, e);
+        Image::default()
+    })
+}
+
+fn format_rebuild_result(progress: &RebuildProgress) -> String {
+    if progress.completed < progress.total {
+        format!("已取消重建：完成 {}/{}", progress.completed, progress.total)
+    } else {
+        format!("重建完成：{} 个，失败 {} 个", progress.completed - progress.failed, progress.failed)
+    }
 } 
\ No newline at end of file
@@ -0,0 +1,96 @@
+use std::io::Write;
+use crate::{Result, WallpaperError};
+use crate::config::Config;
+use crate::services::{CacheStats, RebuildProgress, RepairReport, ThumbnailService, WallpaperService};
+use crate::utils::format_file_size;
+
+/// 不启动界面、直接在命令行中运行的缩略图缓存维护命令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheCommand {
+    /// `--verify-cache`：校验缓存并输出统计
+    Verify,
+    /// `--repair-cache`：重新生成损坏的缩略图
+    Repair,
+    /// `--rebuild-cache`：为壁纸目录中的所有文件重建整个缓存
+    Rebuild,
+}
+
+impl CacheCommand {
+    /// 解析命令行参数（不含程序名），没有缓存命令时返回 `None`
+    ///
+    /// 只处理 `--*-cache` 形式的参数，桌面环境附加的其他参数（如 `%U`、`-psn_*`）一律忽略
+    pub fn parse(args: &[String]) -> Result<Option<Self>> {
+        let mut commands: Vec<Self> = Vec::new();
+        for argument in args.iter().filter(|argument| argument.starts_with("--") && argument.ends_with("-cache")) {
+            commands.push(match argument.as_str() {
+                "--verify-cache" => Self::Verify,
+                "--repair-cache" => Self::Repair,
+                "--rebuild-cache" => Self::Rebuild,
+                _ => return Err(WallpaperError::Config(format!(
+                    "未知的缓存命令: {}（可用: --verify-cache, --repair-cache, --rebuild-cache）",
+                    argument
+                ))),
+            });
+        }
+        
+        match commands.as_slice() {
+            [] => Ok(None),
+            [command] => Ok(Some(*command)),
+            _ => Err(WallpaperError::Config("只能指定一个缓存命令".to_string())),
+        }
+    }
+    
+    pub fn run(self) -> Result<()> {
+        let config: Config = Config::load()?;
+        match self {
+            Self::Verify => {
+                let stats: CacheStats = ThumbnailService::new(&config)?.verify_cache()?;
+                println!("缩略图: {} 个，共 {}", stats.count, format_file_size(stats.total_bytes));
+                println!("原图已不存在: {} 个", stats.orphaned);
+                println!("临时文件: {} 个", stats.temporary_files);
+                println!("损坏: {} 个", stats.broken.len());
+                for filename in &stats.broken {
+                    println!("  {}", filename);
+                }
+            }
+            Self::Repair => {
+                let report: RepairReport = ThumbnailService::new(&config)?.repair_cache()?;
+                println!(
+                    "重新生成 {} 个，删除 {} 个无法恢复的缩略图和 {} 个临时文件",
+                    report.regenerated, report.removed, report.temporary_removed
+                );
+            }
+            Self::Rebuild => {
+                let mut service: WallpaperService = WallpaperService::new(&config)?;
+                let progress: RebuildProgress = service.rebuild_thumbnail_cache(|progress| {
+                    print!("\r重建缩略图: {}/{}", progress.completed, progress.total);
+                    let _ = std::io::stdout().flush();
+                    true
+                })?;
+                println!();
+                println!("完成 {} 个，失败 {} 个", progress.completed - progress.failed, progress.failed);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cache_command() {
+        let parse = |args: &[&str]| CacheCommand::parse(&args.iter().map(|arg| arg.to_string()).collect::<Vec<String>>());
+        assert_eq!(parse(&[]).unwrap(), None);
+        assert_eq!(parse(&["--verify-cache"]).unwrap(), Some(CacheCommand::Verify));
+        assert_eq!(parse(&["--rebuild-cache"]).unwrap(), Some(CacheCommand::Rebuild));
+        assert!(parse(&["--purge-cache"]).is_err());
+        assert!(parse(&["--verify-cache", "--repair-cache"]).is_err());
+        
+        // 桌面环境和系统附加的参数不影响缓存命令的解析
+        assert_eq!(parse(&["%U"]).unwrap(), None);
+        assert_eq!(parse(&["-psn_0_12345", "--unknown"]).unwrap(), None);
+        assert_eq!(parse(&["%U", "--repair-cache"]).unwrap(), Some(CacheCommand::Repair));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,148 @@
+use std::time::Duration;
+use crate::utils::AnimationFrame;
+
+/// 延迟为 0 的帧按此时长显示，避免一次推进跳过所有帧
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+
+/// 预览窗口的动图播放状态：当前帧、播放/暂停和当前帧已显示的时长
+///
+/// 由界面定时器调用 `advance` 推进，返回值表示是否需要重绘；播放到最后一帧后从头循环
+#[derive(Debug, Clone)]
+pub struct AnimationPlayer {
+    frames: Vec<AnimationFrame>,
+    current: usize,
+    playing: bool,
+    elapsed: Duration,
+}
+
+impl AnimationPlayer {
+    pub fn new(frames: Vec<AnimationFrame>) -> Self {
+        let playing: bool = frames.len() > 1;
+        Self {
+            frames,
+            current: 0,
+            playing,
+            elapsed: Duration::ZERO,
+        }
+    }
+    
+    pub fn get_frame_count(&self) -> usize {
+        self.frames.len()
+    }
+    
+    pub fn get_current_index(&self) -> usize {
+        self.current
+    }
+    
+    pub fn get_current_frame(&self) -> Option<&AnimationFrame> {
+        self.frames.get(self.current)
+    }
+    
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+    
+    pub fn play(&mut self) {
+        self.playing = self.frames.len() > 1;
+    }
+    
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+    
+    pub fn toggle(&mut self) {
+        if self.playing {
+            self.pause();
+        } else {
+            self.play();
+        }
+    }
+    
+    /// 跳转到指定帧（用于选择设为静态壁纸的帧），超出范围时停在最后一帧
+    pub fn seek(&mut self, index: usize) {
+        self.current = index.min(self.frames.len().saturating_sub(1));
+        self.elapsed = Duration::ZERO;
+    }
+    
+    /// 逐帧前进或后退，暂停时使用
+    pub fn step(&mut self, forward: bool) {
+        if self.frames.is_empty() {
+            return;
+        }
+        let count: usize = self.frames.len();
+        self.current = if forward { (self.current + 1) % count } else { (self.current + count - 1) % count };
+        self.elapsed = Duration::ZERO;
+    }
+    
+    /// 经过 `elapsed` 时长后推进播放，返回当前帧是否变化
+    pub fn advance(&mut self, elapsed: Duration) -> bool {
+        if !self.playing || self.frames.len() < 2 {
+            return false;
+        }
+        
+        let previous: usize = self.current;
+        self.elapsed += elapsed;
+        loop {
+            let delay: Duration = self.frames[self.current].delay.max(MIN_FRAME_DELAY);
+            if self.elapsed < delay {
+                break;
+            }
+            self.elapsed -= delay;
+            self.current = (self.current + 1) % self.frames.len();
+        }
+        self.current != previous
+    }
+    
+    /// 距离下一帧的时长，供界面设置定时器；暂停或静态图片时为 `None`
+    pub fn get_time_to_next_frame(&self) -> Option<Duration> {
+        if !self.playing || self.frames.len() < 2 {
+            return None;
+        }
+        Some(self.frames[self.current].delay.max(MIN_FRAME_DELAY).saturating_sub(self.elapsed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::RgbaImage;
+
+    fn create_frames(delays_ms: &[u64]) -> Vec<AnimationFrame> {
+        delays_ms
+            .iter()
+            .map(|delay| AnimationFrame {
+                image: RgbaImage::new(4, 4),
+                delay: Duration::from_millis(*delay),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_animation_playback() {
+        let mut player: AnimationPlayer = AnimationPlayer::new(create_frames(&[100, 50, 200]));
+        assert!(player.is_playing());
+        
+        assert!(!player.advance(Duration::from_millis(60)));
+        assert!(player.advance(Duration::from_millis(60)));
+        assert_eq!(player.get_current_index(), 1);
+        assert_eq!(player.get_time_to_next_frame(), Some(Duration::from_millis(30)));
+        
+        // 一次推进跨越多帧后循环回到开头
+        assert!(player.advance(Duration::from_millis(230)));
+        assert_eq!(player.get_current_index(), 0);
+        
+        player.pause();
+        assert!(!player.advance(Duration::from_secs(1)));
+        assert_eq!(player.get_time_to_next_frame(), None);
+        player.step(false);
+        assert_eq!(player.get_current_index(), 2);
+        player.seek(10);
+        assert_eq!(player.get_current_index(), 2);
+        
+        // 静态图片不播放
+        let mut player: AnimationPlayer = AnimationPlayer::new(create_frames(&[0]));
+        player.play();
+        assert!(!player.is_playing());
+        assert!(player.get_current_frame().is_some());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,481 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use crate::models::Wallpaper;
+use crate::utils::get_relative_path_display;
+
+const SCORE_MATCH: i32 = 16;
+const SCORE_GAP_START: i32 = -3;
+const SCORE_GAP_EXTENSION: i32 = -1;
+const BONUS_BOUNDARY: i32 = 8;
+const BONUS_CAMEL_CASE: i32 = 7;
+const BONUS_CONSECUTIVE: i32 = 4;
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+/// 文件名命中比路径命中更有价值
+const BONUS_FILENAME: i32 = 12;
+
+/// 命中的字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchField {
+    Filename,
+    Path,
+}
+
+/// 一条模糊匹配结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    /// 壁纸在建立索引时列表中的位置
+    pub index: usize,
+    pub score: i32,
+    pub field: MatchField,
+    /// 命中的字符区间（按字符而非字节计数），用于界面高亮
+    pub ranges: Vec<Range<usize>>,
+}
+
+struct IndexEntry {
+    id: String,
+    filename: String,
+    relative_path: String,
+    filename_chars: Vec<char>,
+    filename_lower: Vec<char>,
+    filename_mask: u64,
+    path_chars: Vec<char>,
+    path_lower: Vec<char>,
+    path_mask: u64,
+}
+
+/// 预先建立的模糊搜索索引
+///
+/// 建立索引时预先计算小写字符序列和字符位掩码，查询时先用位掩码排除
+/// 不可能命中的条目，保证在数万条记录上每次按键都能即时返回
+pub struct FuzzyIndex {
+    entries: Vec<IndexEntry>,
+}
+
+impl FuzzyIndex {
+    /// 为壁纸列表建立索引，相对路径基于所属的壁纸目录计算
+    pub fn build<'a>(wallpapers: impl IntoIterator<Item = &'a Wallpaper>, base_directories: &[PathBuf]) -> Self {
+        let entries: Vec<IndexEntry> = wallpapers
+            .into_iter()
+            .map(|wallpaper| {
+                let base: &Path = base_directories
+                    .iter()
+                    .find(|directory| wallpaper.path.starts_with(directory))
+                    .map(|directory| directory.as_path())
+                    .unwrap_or_else(|| Path::new(""));
+                let relative_path: String = get_relative_path_display(&wallpaper.path, base);
+                
+                let filename_chars: Vec<char> = wallpaper.filename.chars().collect();
+                let filename_lower: Vec<char> = lowercase_chars(&filename_chars);
+                let path_chars: Vec<char> = relative_path.chars().collect();
+                let path_lower: Vec<char> = lowercase_chars(&path_chars);
+                
+                IndexEntry {
+                    id: wallpaper.id.clone(),
+                    filename: wallpaper.filename.clone(),
+                    filename_mask: char_mask(&filename_lower),
+                    filename_chars,
+                    filename_lower,
+                    path_mask: char_mask(&path_lower),
+                    relative_path,
+                    path_chars,
+                    path_lower,
+                }
+            })
+            .collect();
+        
+        Self { entries }
+    }
+    
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    
+    pub fn get_id(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(|entry| entry.id.as_str())
+    }
+    
+    pub fn get_filename(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(|entry| entry.filename.as_str())
+    }
+    
+    pub fn get_relative_path(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(|entry| entry.relative_path.as_str())
+    }
+    
+    /// 搜索并按得分从高到低返回最多 `limit` 条结果
+    pub fn search(&self, query: &str, limit: usize) -> Vec<FuzzyMatch> {
+        let pattern: Vec<char> = to_pattern(query);
+        let mut matches: Vec<FuzzyMatch> = self.match_candidates(&pattern, 0..self.entries.len());
+        self.rank(&mut matches, limit);
+        self.fill_ranges(&pattern, &mut matches);
+        matches
+    }
+    
+    /// 只计算得分，命中区间留给排名后的前若干条结果再计算
+    fn match_candidates<I>(&self, pattern: &[char], candidates: I) -> Vec<FuzzyMatch>
+    where
+        I: IntoIterator<Item = usize>,
+    {
+        if pattern.is_empty() {
+            return Vec::new();
+        }
+        
+        let pattern_mask: u64 = char_mask(pattern);
+        
+        candidates
+            .into_iter()
+            .filter_map(|index| {
+                let entry: &IndexEntry = &self.entries[index];
+                
+                if entry.filename_mask & pattern_mask == pattern_mask {
+                    if let Some(score) = score_match(&entry.filename_chars, &entry.filename_lower, pattern, None) {
+                        return Some(FuzzyMatch {
+                            index,
+                            score: score + BONUS_FILENAME,
+                            field: MatchField::Filename,
+                            ranges: Vec::new(),
+                        });
+                    }
+                }
+                
+                if entry.path_mask & pattern_mask == pattern_mask {
+                    if let Some(score) = score_match(&entry.path_chars, &entry.path_lower, pattern, None) {
+                        return Some(FuzzyMatch {
+                            index,
+                            score,
+                            field: MatchField::Path,
+                            ranges: Vec::new(),
+                        });
+                    }
+                }
+                
+                None
+            })
+            .collect()
+    }
+    
+    fn fill_ranges(&self, pattern: &[char], matches: &mut [FuzzyMatch]) {
+        let mut positions: Vec<usize> = Vec::with_capacity(pattern.len());
+        
+        for fuzzy_match in matches.iter_mut() {
+            let entry: &IndexEntry = &self.entries[fuzzy_match.index];
+            let (original, lower) = match fuzzy_match.field {
+                MatchField::Filename => (&entry.filename_chars, &entry.filename_lower),
+                MatchField::Path => (&entry.path_chars, &entry.path_lower),
+            };
+            
+            positions.clear();
+            score_match(original, lower, pattern, Some(&mut positions));
+            fuzzy_match.ranges = positions_to_ranges(&positions);
+        }
+    }
+    
+    fn rank(&self, matches: &mut Vec<FuzzyMatch>, limit: usize) {
+        let compare = |a: &FuzzyMatch, b: &FuzzyMatch| -> Ordering {
+            b.score
+                .cmp(&a.score)
+                .then_with(|| self.text_length(a).cmp(&self.text_length(b)))
+                .then_with(|| a.index.cmp(&b.index))
+        };
+        
+        if limit == 0 {
+            matches.clear();
+            return;
+        }
+        if matches.len() > limit {
+            matches.select_nth_unstable_by(limit - 1, compare);
+            matches.truncate(limit);
+        }
+        matches.sort_unstable_by(compare);
+    }
+    
+    fn text_length(&self, fuzzy_match: &FuzzyMatch) -> usize {
+        let entry: &IndexEntry = &self.entries[fuzzy_match.index];
+        match fuzzy_match.field {
+            MatchField::Filename => entry.filename_chars.len(),
+            MatchField::Path => entry.path_chars.len(),
+        }
+    }
+}
+
+/// 逐键输入时使用的搜索器
+///
+/// 当新的查询是上一次查询的延长（继续输入）时，只在上一次命中的条目中继续查找
+pub struct FuzzySearcher {
+    index: FuzzyIndex,
+    last_query: String,
+    last_candidates: Vec<usize>,
+}
+
+impl FuzzySearcher {
+    pub fn new(index: FuzzyIndex) -> Self {
+        Self {
+            index,
+            last_query: String::new(),
+            last_candidates: Vec::new(),
+        }
+    }
+    
+    pub fn index(&self) -> &FuzzyIndex {
+        &self.index
+    }
+    
+    pub fn search(&mut self, query: &str, limit: usize) -> Vec<FuzzyMatch> {
+        let narrowing: bool = !self.last_query.is_empty() && query.starts_with(self.last_query.as_str());
+        let pattern: Vec<char> = to_pattern(query);
+        
+        let mut matches: Vec<FuzzyMatch> = if narrowing {
+            let candidates: Vec<usize> = std::mem::take(&mut self.last_candidates);
+            self.index.match_candidates(&pattern, candidates)
+        } else {
+            self.index.match_candidates(&pattern, 0..self.index.len())
+        };
+        
+        self.last_query = query.to_string();
+        self.last_candidates = matches.iter().map(|fuzzy_match| fuzzy_match.index).collect();
+        
+        self.index.rank(&mut matches, limit);
+        self.index.fill_ranges(&pattern, &mut matches);
+        matches
+    }
+    
+    /// 壁纸列表变化后需要重建索引
+    pub fn reset(&mut self, index: FuzzyIndex) {
+        self.index = index;
+        self.last_query.clear();
+        self.last_candidates.clear();
+    }
+}
+
+fn to_pattern(query: &str) -> Vec<char> {
+    query
+        .chars()
+        .flat_map(|ch| ch.to_lowercase())
+        .collect()
+}
+
+fn lowercase_chars(chars: &[char]) -> Vec<char> {
+    // 保持与原字符一一对应，以便命中位置可以直接用于高亮
+    chars
+        .iter()
+        .map(|ch| ch.to_lowercase().next().unwrap_or(*ch))
+        .collect()
+}
+
+fn char_mask(chars: &[char]) -> u64 {
+    chars.iter().fold(0u64, |mask, ch| {
+        let bit: u32 = match ch {
+            'a'..='z' => *ch as u32 - 'a' as u32,
+            '0'..='9' => 26 + (*ch as u32 - '0' as u32),
+            ch if ch.is_ascii() => 36 + (*ch as u32 % 27),
+            _ => 63,
+        };
+        mask | (1u64 << bit)
+    })
+}
+
+fn is_separator(ch: char) -> bool {
+    matches!(ch, '/' | '\\' | '_' | '-' | '.' | ' ')
+}
+
+fn bonus_at(chars: &[char], position: usize) -> i32 {
+    if position == 0 {
+        return BONUS_BOUNDARY;
+    }
+    
+    let previous: char = chars[position - 1];
+    let current: char = chars[position];
+    
+    if is_separator(previous) && !is_separator(current) {
+        BONUS_BOUNDARY
+    } else if (previous.is_lowercase() && current.is_uppercase())
+        || (!previous.is_ascii_digit() && current.is_ascii_digit())
+    {
+        BONUS_CAMEL_CASE
+    } else {
+        0
+    }
+}
+
+/// 子序列匹配并打分，返回得分和命中的字符位置
+///
+/// 先向前找到最早完整命中的结束位置，再向后收缩出最短的起始位置，
+/// 最后在该窗口内计算得分（与 fzf v1 算法一致）。传入 `positions` 时同时记录命中位置
+fn score_match(
+    original: &[char],
+    lower: &[char],
+    pattern: &[char],
+    mut positions: Option<&mut Vec<usize>>,
+) -> Option<i32> {
+    if pattern.len() > lower.len() {
+        return None;
+    }
+    
+    let mut pattern_index: usize = 0;
+    let mut end: Option<usize> = None;
+    for (position, ch) in lower.iter().enumerate() {
+        if *ch == pattern[pattern_index] {
+            pattern_index += 1;
+            if pattern_index == pattern.len() {
+                end = Some(position);
+                break;
+            }
+        }
+    }
+    let end: usize = end?;
+    
+    let mut pattern_index: usize = pattern.len() - 1;
+    let mut start: usize = 0;
+    for position in (0..=end).rev() {
+        if lower[position] == pattern[pattern_index] {
+            if pattern_index == 0 {
+                start = position;
+                break;
+            }
+            pattern_index -= 1;
+        }
+    }
+    
+    let mut score: i32 = 0;
+    let mut pattern_index: usize = 0;
+    let mut in_gap: bool = false;
+    let mut consecutive: i32 = 0;
+    let mut run_bonus: i32 = 0;
+    
+    for (position, ch) in lower.iter().enumerate().take(end + 1).skip(start) {
+        if pattern_index < pattern.len() && *ch == pattern[pattern_index] {
+            let mut bonus: i32 = bonus_at(original, position);
+            
+            if consecutive > 0 {
+                // 连续命中继承本段第一个字符的边界加分
+                bonus = bonus.max(run_bonus).max(BONUS_CONSECUTIVE);
+            } else {
+                run_bonus = bonus;
+            }
+            if pattern_index == 0 {
+                bonus *= BONUS_FIRST_CHAR_MULTIPLIER;
+            }
+            
+            score += SCORE_MATCH + bonus;
+            if let Some(positions) = positions.as_mut() {
+                positions.push(position);
+            }
+            pattern_index += 1;
+            consecutive += 1;
+            in_gap = false;
+        } else {
+            score += if in_gap { SCORE_GAP_EXTENSION } else { SCORE_GAP_START };
+            in_gap = true;
+            consecutive = 0;
+        }
+    }
+    
+    Some(score)
+}
+
+fn positions_to_ranges(positions: &[usize]) -> Vec<Range<usize>> {
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    
+    for &position in positions {
+        match ranges.last_mut() {
+            Some(range) if range.end == position => range.end += 1,
+            _ => ranges.push(position..position + 1),
+        }
+    }
+    
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_index(paths: &[&str]) -> FuzzyIndex {
+        let wallpapers: Vec<Wallpaper> = paths.iter().map(|path| Wallpaper::for_test(path)).collect();
+        FuzzyIndex::build(&wallpapers, &[PathBuf::from("/walls")])
+    }
+
+    #[test]
+    fn test_ranking_prefers_boundaries_and_filenames() {
+        let index: FuzzyIndex = build_index(&[
+            "/walls/misc/jasmbler.jpg",
+            "/walls/nature/sunset_beach.jpg",
+            "/walls/city/SunsetBoulevard.png",
+            "/walls/sunset/dunes.jpg",
+        ]);
+        
+        let results: Vec<FuzzyMatch> = index.search("sb", 10);
+        let names: Vec<&str> = results
+            .iter()
+            .map(|result| index.get_filename(result.index).unwrap())
+            .collect();
+        assert_eq!(names, vec!["sunset_beach.jpg", "SunsetBoulevard.png", "jasmbler.jpg"]);
+        
+        let results: Vec<FuzzyMatch> = index.search("sundun", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].field, MatchField::Path);
+        assert_eq!(index.get_relative_path(results[0].index), Some("sunset/dunes.jpg"));
+        assert_eq!(results[0].ranges, vec![0..3, 7..10]);
+    }
+
+    #[test]
+    fn test_ranges_are_character_based() {
+        let index: FuzzyIndex = build_index(&["/walls/夕阳_海滩.jpg", "/walls/other.png"]);
+        
+        let results: Vec<FuzzyMatch> = index.search("海滩", 10);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].field, MatchField::Filename);
+        assert_eq!(results[0].ranges, vec![3..5]);
+    }
+
+    #[test]
+    fn test_incremental_search() {
+        let index: FuzzyIndex = build_index(&[
+            "/walls/mountain.jpg",
+            "/walls/moon.png",
+            "/walls/forest.jpg",
+        ]);
+        let mut searcher: FuzzySearcher = FuzzySearcher::new(index);
+        
+        assert_eq!(searcher.search("mo", 10).len(), 2);
+        assert_eq!(searcher.search("mou", 10).len(), 1);
+        assert_eq!(searcher.search("f", 10).len(), 1);
+        assert!(searcher.search("", 10).is_empty());
+        assert_eq!(searcher.search("jpg", 1).len(), 1);
+    }
+    
+    /// 逐个输入查询字符，每次查询都不能超过 `limit`
+    fn assert_typing_latency(count: usize, limit: std::time::Duration) {
+        let paths: Vec<String> = (0..count)
+            .map(|i| format!("/walls/collection_{}/wallpaper_{:05}_{}.jpg", i % 97, i, i % 13))
+            .collect();
+        let wallpapers: Vec<Wallpaper> = paths.iter().map(|path| Wallpaper::for_test(path)).collect();
+        let mut searcher: FuzzySearcher = FuzzySearcher::new(FuzzyIndex::build(&wallpapers, &[PathBuf::from("/walls")]));
+        
+        for query in ["w", "wa", "wal", "wall", "wall1", "wall12"] {
+            let started: std::time::Instant = std::time::Instant::now();
+            searcher.search(query, 100);
+            let elapsed: std::time::Duration = started.elapsed();
+            assert!(elapsed < limit, "查询 {} 耗时 {:?}，超过 {:?}", query, elapsed, limit);
+        }
+    }
+    
+    /// 冒烟测试：调试构建下也能运行的规模，上限放宽以免 CI 机器抖动导致误报
+    #[test]
+    fn test_search_latency_smoke() {
+        assert_typing_latency(10_000, std::time::Duration::from_millis(500));
+    }
+    
+    /// 5 万张壁纸时每次按键的查询在一帧（16 毫秒）内完成，只在发布构建下有意义
+    #[cfg(not(debug_assertions))]
+    #[test]
+    #[ignore = "性能测试：cargo test --release -- --ignored test_search_latency_on_large_index"]
+    fn test_search_latency_on_large_index() {
+        assert_typing_latency(50_000, std::time::Duration::from_millis(16));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,123 @@
+use crate::utils::select_thumbnail_tier;
+
+/// 网格单元格边长（逻辑像素）的范围
+pub const MIN_CELL_SIZE: f32 = 96.0;
+pub const MAX_CELL_SIZE: f32 = 512.0;
+
+const DEFAULT_CELL_SIZE: f32 = 200.0;
+
+/// Ctrl+滚轮每滚动一格的缩放倍数
+const ZOOM_STEP: f32 = 1.1;
+
+/// 缩小时所需像素低于较小档位的此比例才切换档位，避免在档位边界附近来回重新加载
+const TIER_DOWNGRADE_RATIO: f32 = 0.75;
+
+/// 网格缩放状态：单元格大小、显示缩放比例和当前使用的缩略图档位
+///
+/// 放大超出当前档位时立即切换到更大的档位；缩小时继续使用较大的档位，
+/// 直到明显小于较小档位才切换，因此连续缩放不会每一步都重新生成缩略图
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridZoom {
+    cell_size: f32,
+    scale_factor: f32,
+    tier: u32,
+}
+
+impl Default for GridZoom {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE, 1.0)
+    }
+}
+
+impl GridZoom {
+    pub fn new(cell_size: f32, scale_factor: f32) -> Self {
+        let mut zoom: GridZoom = Self {
+            cell_size: cell_size.clamp(MIN_CELL_SIZE, MAX_CELL_SIZE),
+            scale_factor: scale_factor.max(1.0),
+            tier: 0,
+        };
+        zoom.tier = select_thumbnail_tier(zoom.get_required_pixels());
+        zoom
+    }
+    
+    pub fn get_cell_size(&self) -> f32 {
+        self.cell_size
+    }
+    
+    pub fn get_scale_factor(&self) -> f32 {
+        self.scale_factor
+    }
+    
+    /// 当前应使用的缩略图档位
+    pub fn get_tier(&self) -> u32 {
+        self.tier
+    }
+    
+    /// Ctrl+滚轮缩放，`steps` 为滚动格数，正数放大；返回缩略图档位是否变化
+    pub fn zoom_by(&mut self, steps: f32) -> bool {
+        self.set_cell_size(self.cell_size * ZOOM_STEP.powf(steps))
+    }
+    
+    pub fn set_cell_size(&mut self, cell_size: f32) -> bool {
+        self.cell_size = cell_size.clamp(MIN_CELL_SIZE, MAX_CELL_SIZE);
+        self.update_tier()
+    }
+    
+    /// 窗口移动到缩放比例不同的显示器时调用
+    pub fn set_scale_factor(&mut self, scale_factor: f32) -> bool {
+        self.scale_factor = scale_factor.max(1.0);
+        self.update_tier()
+    }
+    
+    /// 给定可用宽度下的网格列数
+    pub fn get_columns(&self, available_width: f32, spacing: f32) -> usize {
+        ((available_width + spacing) / (self.cell_size + spacing)).floor().max(1.0) as usize
+    }
+    
+    /// 单元格在屏幕上的物理像素边长
+    fn get_required_pixels(&self) -> u32 {
+        (self.cell_size * self.scale_factor).ceil() as u32
+    }
+    
+    fn update_tier(&mut self) -> bool {
+        let required: u32 = self.get_required_pixels();
+        let tier: u32 = if required > self.tier {
+            select_thumbnail_tier(required)
+        } else {
+            select_thumbnail_tier((required as f32 / TIER_DOWNGRADE_RATIO).ceil() as u32).min(self.tier)
+        };
+        
+        let changed: bool = tier != self.tier;
+        self.tier = tier;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tier_hysteresis() {
+        let mut zoom: GridZoom = GridZoom::new(200.0, 1.0);
+        assert_eq!(zoom.get_tier(), 256);
+        
+        // 放大超过 256 时切换到 512，缩回 256 附近时不切换
+        assert!(zoom.set_cell_size(300.0));
+        assert_eq!(zoom.get_tier(), 512);
+        assert!(!zoom.set_cell_size(250.0));
+        assert!(!zoom.zoom_by(-1.0));
+        assert_eq!(zoom.get_tier(), 512);
+        assert!(zoom.set_cell_size(180.0));
+        assert_eq!(zoom.get_tier(), 256);
+        
+        // HiDPI 显示器需要两倍像素
+        assert!(zoom.set_scale_factor(2.0));
+        assert_eq!(zoom.get_tier(), 512);
+        assert!(zoom.set_cell_size(MAX_CELL_SIZE * 2.0));
+        assert_eq!((zoom.get_cell_size(), zoom.get_tier()), (MAX_CELL_SIZE, 1024));
+        
+        assert_eq!(GridZoom::new(200.0, 1.0).get_columns(850.0, 10.0), 4);
+        assert_eq!(GridZoom::new(200.0, 1.0).get_columns(50.0, 10.0), 1);
+    }
+}
\ No newline at end of file
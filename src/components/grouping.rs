@@ -0,0 +1,235 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use crate::components::sort::natural_cmp;
+use crate::models::Wallpaper;
+use crate::utils::{get_aspect_ratio, is_jpeg_alias, normalize_format};
+
+/// 网格分组方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupBy {
+    #[default]
+    None,
+    /// 所在文件夹
+    Folder,
+    /// 修改时间所在月份，最近的月份在前
+    Month,
+    /// 分辨率档位，例如 4K、2K
+    Resolution,
+    /// 常见宽高比，例如 16:9、21:9
+    AspectRatio,
+    Format,
+    /// 按标签分组。网格中每张壁纸只占一个位置，有多个标签时只归入按自然排序最靠前的标签，
+    /// 其他标签下的壁纸可用 `tag:` 查询查看；没有标签的壁纸归入最后的“未加标签”分组
+    Tag,
+}
+
+/// 分辨率档位：(名称, 最小宽度)，按长边计算，从高到低排列
+const RESOLUTION_CLASSES: &[(&str, u32)] = &[
+    ("8K", 7680),
+    ("5K", 5120),
+    ("4K", 3840),
+    ("2K", 2560),
+    ("1080p", 1920),
+    ("720p", 1280),
+];
+
+/// 常见宽高比：(名称, 比值)
+const ASPECT_RATIO_BUCKETS: &[(&str, f32)] = &[
+    ("32:9", 32.0 / 9.0),
+    ("21:9", 21.0 / 9.0),
+    ("16:9", 16.0 / 9.0),
+    ("16:10", 16.0 / 10.0),
+    ("3:2", 3.0 / 2.0),
+    ("4:3", 4.0 / 3.0),
+    ("1:1", 1.0),
+    ("9:16", 9.0 / 16.0),
+];
+
+/// 归入某个宽高比档位允许的相对误差
+const ASPECT_RATIO_BUCKET_TOLERANCE: f32 = 0.03;
+
+/// 网格中的一个分组
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridSection {
+    /// 分组标识，折叠状态按此记录
+    pub key: String,
+    /// 分组标题
+    pub title: String,
+    /// 分组中第一张壁纸在网格中的下标
+    pub start: usize,
+    pub count: usize,
+    pub collapsed: bool,
+}
+
+impl GridSection {
+    pub fn contains(&self, index: usize) -> bool {
+        index >= self.start && index < self.start + self.count
+    }
+}
+
+/// 壁纸所属的分组：`rank` 决定分组之间的顺序，`rank` 相同时按 `key` 自然排序
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupKey {
+    pub rank: u32,
+    pub key: String,
+    pub title: String,
+}
+
+impl GroupKey {
+    fn new(rank: u32, key: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            rank,
+            key: key.into(),
+            title: title.into(),
+        }
+    }
+    
+    pub fn cmp_order(&self, other: &Self) -> Ordering {
+        self.rank.cmp(&other.rank).then_with(|| natural_cmp(&self.key, &other.key))
+    }
+}
+
+pub fn group_key(wallpaper: &Wallpaper, group_by: GroupBy) -> GroupKey {
+    match group_by {
+        GroupBy::None => GroupKey::new(0, "", ""),
+        GroupBy::Folder => {
+            let folder: String = wallpaper.path
+                .parent()
+                .map(|parent| parent.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let title: String = wallpaper.path
+                .parent()
+                .and_then(|parent| parent.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| folder.clone());
+            GroupKey::new(0, folder, title)
+        }
+        GroupBy::Month => {
+            let (year, month) = (wallpaper.modified_at.year(), wallpaper.modified_at.month());
+            let months: u32 = (year.max(0) as u32) * 12 + month;
+            GroupKey::new(u32::MAX - months, format!("{:04}-{:02}", year, month), format!("{}年{}月", year, month))
+        }
+        GroupBy::Resolution => {
+            let long_side: u32 = wallpaper.size.0.max(wallpaper.size.1);
+            match RESOLUTION_CLASSES.iter().position(|(_, min_width)| long_side >= *min_width) {
+                Some(rank) => GroupKey::new(rank as u32, RESOLUTION_CLASSES[rank].0, RESOLUTION_CLASSES[rank].0),
+                None => GroupKey::new(RESOLUTION_CLASSES.len() as u32, "low", "低分辨率"),
+            }
+        }
+        GroupBy::AspectRatio => {
+            let ratio: f32 = get_aspect_ratio(wallpaper.size.0, wallpaper.size.1);
+            let bucket: Option<usize> = ASPECT_RATIO_BUCKETS
+                .iter()
+                .position(|(_, bucket)| (ratio / bucket - 1.0).abs() <= ASPECT_RATIO_BUCKET_TOLERANCE);
+            match bucket {
+                Some(rank) => GroupKey::new(rank as u32, ASPECT_RATIO_BUCKETS[rank].0, ASPECT_RATIO_BUCKETS[rank].0),
+                None => GroupKey::new(ASPECT_RATIO_BUCKETS.len() as u32, "other", "其他比例"),
+            }
+        }
+        GroupBy::Format => {
+            let format: String = normalize_format(&wallpaper.format);
+            GroupKey::new(0, format.clone(), format.to_uppercase())
+        }
+        GroupBy::Tag => match wallpaper.tags.iter().min_by(|a, b| natural_cmp(a, b)) {
+            Some(tag) => GroupKey::new(0, tag.clone(), tag.clone()),
+            None => GroupKey::new(1, "", "未加标签"),
+        },
+    }
+}
+
+/// 将同目录下同名（不区分大小写）的 RAW 与 JPEG 归为一组，互相记录对方的 ID
+///
+/// 网格中只显示 JPEG，RAW 通过 `sibling_id` 访问；没有 JPEG 的 RAW 单独显示
+pub fn link_raw_siblings(wallpapers: &mut [Wallpaper]) {
+    let mut jpegs: HashMap<(PathBuf, String), usize> = HashMap::new();
+    for (index, wallpaper) in wallpapers.iter_mut().enumerate() {
+        wallpaper.sibling_id = None;
+        if is_jpeg_alias(&wallpaper.format) {
+            jpegs.insert(sibling_key(wallpaper), index);
+        }
+    }
+    
+    for index in 0..wallpapers.len() {
+        if !wallpapers[index].is_raw() {
+            continue;
+        }
+        let Some(&jpeg) = jpegs.get(&sibling_key(&wallpapers[index])) else {
+            continue;
+        };
+        // 同名 JPEG 已与另一个 RAW（例如 .CR2 和 .DNG）配对时保留先出现的一对
+        if wallpapers[jpeg].sibling_id.is_some() {
+            continue;
+        }
+        wallpapers[jpeg].sibling_id = Some(wallpapers[index].id.clone());
+        wallpapers[index].sibling_id = Some(wallpapers[jpeg].id.clone());
+    }
+}
+
+/// 是否因与 JPEG 归为一组而不在网格中单独显示
+pub fn is_grouped_raw(wallpaper: &Wallpaper) -> bool {
+    wallpaper.is_raw() && wallpaper.sibling_id.is_some()
+}
+
+fn sibling_key(wallpaper: &Wallpaper) -> (PathBuf, String) {
+    let directory: PathBuf = wallpaper.path.parent().map(PathBuf::from).unwrap_or_default();
+    let stem: String = wallpaper.path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    (directory, stem)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn create_test_wallpaper(path: &str, size: (u32, u32)) -> Wallpaper {
+        Wallpaper {
+            modified_at: chrono::Utc.with_ymd_and_hms(2024, 2, 10, 0, 0, 0).unwrap(),
+            ..Wallpaper::for_test(path)
+        }
+        .with_dimensions(size.0, size.1)
+    }
+
+    #[test]
+    fn test_group_keys() {
+        let wallpaper: Wallpaper = create_test_wallpaper("/walls/Nature/lake.jpeg", (3840, 1600));
+        assert_eq!(group_key(&wallpaper, GroupBy::Folder).title, "Nature");
+        assert_eq!(group_key(&wallpaper, GroupBy::Month).title, "2024年2月");
+        assert_eq!(group_key(&wallpaper, GroupBy::Resolution).key, "4K");
+        assert_eq!(group_key(&wallpaper, GroupBy::AspectRatio).key, "21:9");
+        assert_eq!(group_key(&wallpaper, GroupBy::Format).title, "JPG");
+        assert_eq!(group_key(&wallpaper, GroupBy::Tag).title, "未加标签");
+        
+        let phone: Wallpaper = create_test_wallpaper("/walls/phone.png", (1080, 2400));
+        assert_eq!(group_key(&phone, GroupBy::Resolution).key, "1080p");
+        assert_eq!(group_key(&phone, GroupBy::AspectRatio).key, "other");
+    }
+
+    #[test]
+    fn test_raw_siblings() {
+        let mut wallpapers: Vec<Wallpaper> = vec![
+            create_test_wallpaper("/photos/IMG_0001.CR2", (6000, 4000)),
+            create_test_wallpaper("/photos/img_0001.jpg", (6000, 4000)),
+            create_test_wallpaper("/photos/IMG_0002.NEF", (6000, 4000)),
+            create_test_wallpaper("/photos/other/IMG_0002.jpg", (6000, 4000)),
+            create_test_wallpaper("/photos/IMG_0001.dng", (6000, 4000)),
+        ];
+        link_raw_siblings(&mut wallpapers);
+        
+        assert_eq!(wallpapers[0].sibling_id.as_deref(), Some("/photos/img_0001.jpg"));
+        assert_eq!(wallpapers[1].sibling_id.as_deref(), Some("/photos/IMG_0001.CR2"));
+        assert!(is_grouped_raw(&wallpapers[0]));
+        assert!(!is_grouped_raw(&wallpapers[1]));
+        
+        // 不同目录的同名文件、第二个同名 RAW 不归组
+        assert_eq!(wallpapers[2].sibling_id, None);
+        assert_eq!(wallpapers[3].sibling_id, None);
+        assert_eq!(wallpapers[4].sibling_id, None);
+    }
+}
\ No newline at end of file
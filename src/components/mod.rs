@@ -1,6 +1,22 @@
 // 这里可以添加自定义的Rust组件
 // 例如：复杂的业务逻辑组件、数据处理组件等
 
+pub mod animation_player;
+pub mod fuzzy_search;
+pub mod grid_zoom;
+pub mod grouping;
+pub mod query;
+pub mod rotation;
+pub mod sort;
+pub mod tag_rules;
 pub mod wallpaper_grid;
 
-pub use wallpaper_grid::WallpaperGrid; 
\ No newline at end of file
+pub use animation_player::AnimationPlayer;
+pub use fuzzy_search::{FuzzyIndex, FuzzyMatch, FuzzySearcher, MatchField};
+pub use grid_zoom::GridZoom;
+pub use grouping::{GridSection, GroupBy};
+pub use query::{Query, QueryParseError};
+pub use rotation::RotationScheduler;
+pub use sort::{SortKey, SortOrder, SortSpec};
+pub use tag_rules::{RuleMatch, TagRuleEngine};
+pub use wallpaper_grid::{GridRow, WallpaperGrid}; 
\ No newline at end of file
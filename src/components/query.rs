@@ -0,0 +1,1049 @@
+use std::fmt;
+use chrono::NaiveDate;
+use crate::models::Wallpaper;
+use crate::models::tag::is_tag_or_descendant;
+use crate::models::usage::MAX_RATING;
+use crate::utils::{
+    ASPECT_RATIO_TOLERANCE, get_aspect_ratio, is_jpeg_alias, is_landscape, is_portrait, is_square, parse_color,
+};
+
+/// 查询语法错误，`position` 为出错位置（从 0 开始的字符序号）
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl QueryParseError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        Self {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for QueryParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "第 {} 个字符处: {}", self.position + 1, self.message)
+    }
+}
+
+impl std::error::Error for QueryParseError {}
+
+/// 比较运算符
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn compare<T: PartialOrd>(self, left: T, right: T) -> bool {
+        match self {
+            CompareOp::Eq => left == right,
+            CompareOp::Lt => left < right,
+            CompareOp::Le => left <= right,
+            CompareOp::Gt => left > right,
+            CompareOp::Ge => left >= right,
+        }
+    }
+    
+    fn symbol(self) -> &'static str {
+        match self {
+            CompareOp::Eq => "=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+}
+
+/// 文本类字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextField {
+    Name,
+    Path,
+    Format,
+    Tag,
+    Camera,
+    Lens,
+    Artist,
+    Copyright,
+}
+
+/// 数值类字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NumberField {
+    Width,
+    Height,
+    AspectRatio,
+    FileSize,
+    /// 平均亮度，0-100
+    Brightness,
+    /// 星级 0-5，0 表示未评分
+    Rating,
+    /// 估计的放大前宽度，没有质量指标时为实际宽度
+    EffectiveWidth,
+    EffectiveHeight,
+    /// 综合质量分，0-100
+    Quality,
+}
+
+/// 日期类字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateField {
+    Modified,
+    Created,
+    /// EXIF 拍摄时间
+    Captured,
+}
+
+/// 布尔属性，对应 `is:xxx` 语法
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flag {
+    Landscape,
+    Portrait,
+    Square,
+    /// 带有 GPS 坐标
+    Geotagged,
+    /// 深色壁纸，适合深色主题
+    Dark,
+    Light,
+    Favorite,
+    /// GIF/WebP 动图
+    Animated,
+    /// 由较低分辨率放大而来
+    Upscaled,
+}
+
+/// 针对单个壁纸字段的判断条件
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// `contains` 为真时进行包含匹配（`name:~sunset`），否则进行相等匹配，均不区分大小写
+    Text { field: TextField, value: String, contains: bool },
+    Number { field: NumberField, op: CompareOp, value: f64 },
+    Date { field: DateField, op: CompareOp, value: NaiveDate },
+    /// 调色板中有与目标颜色相近的颜色（Lab 空间距离）
+    Color { rgb: [u8; 3] },
+    Flag(Flag),
+}
+
+impl Predicate {
+    pub fn matches(&self, wallpaper: &Wallpaper) -> bool {
+        match self {
+            Predicate::Text { field, value, contains } => {
+                let matches_text = |text: &str| -> bool {
+                    let text: String = text.to_lowercase();
+                    if *contains {
+                        text.contains(value.as_str())
+                    } else {
+                        text == *value
+                    }
+                };
+                
+                match field {
+                    TextField::Name => {
+                        let stem: &str = wallpaper.filename
+                            .rsplit_once('.')
+                            .map(|(stem, _)| stem)
+                            .unwrap_or(&wallpaper.filename);
+                        matches_text(&wallpaper.filename) || matches_text(stem)
+                    }
+                    TextField::Path => matches_text(&wallpaper.path.to_string_lossy()),
+                    TextField::Format => {
+                        matches_text(&wallpaper.format)
+                            || (!*contains && is_jpeg_alias(value) && is_jpeg_alias(&wallpaper.format))
+                    }
+                    // 层级标签：`tag:nature` 同时匹配 `nature/mountains`
+                    TextField::Tag => wallpaper.tags.iter().any(|tag| {
+                        matches_text(tag) || (!*contains && is_tag_or_descendant(&tag.to_lowercase(), value))
+                    }),
+                    TextField::Camera => wallpaper.metadata.get_camera().is_some_and(|camera| matches_text(&camera)),
+                    TextField::Lens => wallpaper.metadata.lens.as_deref().is_some_and(matches_text),
+                    TextField::Artist => wallpaper.metadata.artist.as_deref().is_some_and(matches_text),
+                    TextField::Copyright => wallpaper.metadata.copyright.as_deref().is_some_and(matches_text),
+                }
+            }
+            Predicate::Number { field, op, value } => {
+                let actual: f64 = match field {
+                    NumberField::Width => wallpaper.size.0 as f64,
+                    NumberField::Height => wallpaper.size.1 as f64,
+                    NumberField::AspectRatio => {
+                        let ratio: f64 = get_aspect_ratio(wallpaper.size.0, wallpaper.size.1) as f64;
+                        // 宽高比存在舍入误差，例如 1920x1080 与 16:9
+                        if *op == CompareOp::Eq {
+                            return (ratio - value).abs() < ASPECT_RATIO_TOLERANCE as f64;
+                        }
+                        ratio
+                    }
+                    NumberField::FileSize => wallpaper.file_size as f64,
+                    NumberField::Rating => wallpaper.rating as f64,
+                    NumberField::Brightness => match &wallpaper.colors {
+                        Some(colors) => colors.brightness as f64 * 100.0,
+                        None => return false,
+                    },
+                    NumberField::EffectiveWidth => wallpaper.get_effective_size().0 as f64,
+                    NumberField::EffectiveHeight => wallpaper.get_effective_size().1 as f64,
+                    NumberField::Quality => match &wallpaper.quality {
+                        Some(quality) => quality.score as f64,
+                        None => return false,
+                    },
+                };
+                op.compare(actual, *value)
+            }
+            Predicate::Date { field, op, value } => {
+                let actual: Option<NaiveDate> = match field {
+                    DateField::Modified => Some(wallpaper.modified_at.date_naive()),
+                    DateField::Created => Some(wallpaper.created_at.date_naive()),
+                    DateField::Captured => wallpaper.metadata.captured_at.map(|captured| captured.date()),
+                };
+                actual.is_some_and(|actual| op.compare(actual, *value))
+            }
+            Predicate::Color { rgb } => wallpaper.colors
+                .as_ref()
+                .and_then(|colors| colors.distance_to(*rgb))
+                .is_some_and(|distance| distance <= COLOR_MATCH_DISTANCE),
+            Predicate::Flag(flag) => {
+                let (width, height) = wallpaper.size;
+                match flag {
+                    Flag::Landscape => is_landscape(width, height),
+                    Flag::Portrait => is_portrait(width, height),
+                    Flag::Square => is_square(width, height),
+                    Flag::Geotagged => wallpaper.metadata.gps.is_some(),
+                    Flag::Dark => wallpaper.colors.as_ref().is_some_and(|colors| colors.is_dark()),
+                    Flag::Light => wallpaper.colors.as_ref().is_some_and(|colors| !colors.is_dark()),
+                    Flag::Favorite => wallpaper.favorite,
+                    Flag::Animated => wallpaper.is_animated(),
+                    Flag::Upscaled => wallpaper.is_upscaled(),
+                }
+            }
+        }
+    }
+}
+
+/// `color:` 查询允许的最大 Lab 距离（CIE76 ΔE）
+const COLOR_MATCH_DISTANCE: f32 = 25.0;
+
+/// 可组合的壁纸查询条件
+///
+/// 既可以通过 [`Query::parse`] 从搜索栏输入解析，也可以在代码中用
+/// [`Query::and`]、[`Query::or`]、[`Query::negate`] 组合
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    All,
+    Term(Predicate),
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+impl Query {
+    /// 解析查询字符串，例如 `tag:nature width>=3840 (format:png OR format:webp) -name:~draft`
+    ///
+    /// 相邻的条件之间默认为 AND 关系；支持 `AND`、`OR`、`NOT`、`-` 前缀和括号
+    pub fn parse(input: &str) -> std::result::Result<Query, QueryParseError> {
+        let tokens: Vec<Token> = tokenize(input)?;
+        let mut parser: Parser = Parser {
+            tokens,
+            position: 0,
+            input_length: input.chars().count(),
+        };
+        
+        if parser.tokens.is_empty() {
+            return Ok(Query::All);
+        }
+        
+        let query: Query = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(QueryParseError::new(token.position, "多余的 `)`"));
+        }
+        Ok(query)
+    }
+    
+    pub fn matches(&self, wallpaper: &Wallpaper) -> bool {
+        match self {
+            Query::All => true,
+            Query::Term(predicate) => predicate.matches(wallpaper),
+            Query::Not(query) => !query.matches(wallpaper),
+            Query::And(queries) => queries.iter().all(|query| query.matches(wallpaper)),
+            Query::Or(queries) => queries.iter().any(|query| query.matches(wallpaper)),
+        }
+    }
+    
+    /// 从壁纸列表中筛选出满足条件的壁纸
+    pub fn filter<'a>(&self, wallpapers: &'a [Wallpaper]) -> Vec<&'a Wallpaper> {
+        wallpapers
+            .iter()
+            .filter(|wallpaper| self.matches(wallpaper))
+            .collect()
+    }
+    
+    pub fn and(self, other: Query) -> Query {
+        match (self, other) {
+            (Query::All, query) | (query, Query::All) => query,
+            (Query::And(mut left), Query::And(right)) => {
+                left.extend(right);
+                Query::And(left)
+            }
+            (Query::And(mut left), right) => {
+                left.push(right);
+                Query::And(left)
+            }
+            (left, right) => Query::And(vec![left, right]),
+        }
+    }
+    
+    pub fn or(self, other: Query) -> Query {
+        match (self, other) {
+            (Query::All, _) | (_, Query::All) => Query::All,
+            (Query::Or(mut left), Query::Or(right)) => {
+                left.extend(right);
+                Query::Or(left)
+            }
+            (Query::Or(mut left), right) => {
+                left.push(right);
+                Query::Or(left)
+            }
+            (left, right) => Query::Or(vec![left, right]),
+        }
+    }
+    
+    pub fn negate(self) -> Query {
+        match self {
+            Query::Not(query) => *query,
+            query => Query::Not(Box::new(query)),
+        }
+    }
+    
+    /// 用给定的解析函数改写所有精确匹配的标签条件，用于将别名解析为标签名
+    pub fn resolve_tags<F>(&mut self, resolve: &F)
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        match self {
+            Query::Term(Predicate::Text { field: TextField::Tag, value, contains: false }) => {
+                if let Some(resolved) = resolve(value) {
+                    *value = resolved;
+                }
+            }
+            Query::Not(query) => query.resolve_tags(resolve),
+            Query::And(queries) | Query::Or(queries) => {
+                for query in queries {
+                    query.resolve_tags(resolve);
+                }
+            }
+            Query::All | Query::Term(_) => {}
+        }
+    }
+    
+    /// 指定文件格式（`jpg` 与 `jpeg` 视为同一格式）
+    pub fn format(format: &str) -> Query {
+        Query::Term(Predicate::Text {
+            field: TextField::Format,
+            value: format.to_lowercase(),
+            contains: false,
+        })
+    }
+    
+    /// 包含指定标签或其子标签
+    pub fn tag(tag: &str) -> Query {
+        Query::Term(Predicate::Text {
+            field: TextField::Tag,
+            value: tag.to_lowercase(),
+            contains: false,
+        })
+    }
+    
+    /// 宽高均不小于给定值
+    pub fn min_size(min_width: u32, min_height: u32) -> Query {
+        Query::Term(Predicate::Number {
+            field: NumberField::Width,
+            op: CompareOp::Ge,
+            value: min_width as f64,
+        })
+        .and(Query::Term(Predicate::Number {
+            field: NumberField::Height,
+            op: CompareOp::Ge,
+            value: min_height as f64,
+        }))
+    }
+    
+    /// 宽高比位于闭区间 `[min_ratio, max_ratio]` 内
+    pub fn aspect_ratio_between(min_ratio: f32, max_ratio: f32) -> Query {
+        Query::Term(Predicate::Number {
+            field: NumberField::AspectRatio,
+            op: CompareOp::Ge,
+            value: min_ratio as f64,
+        })
+        .and(Query::Term(Predicate::Number {
+            field: NumberField::AspectRatio,
+            op: CompareOp::Le,
+            value: max_ratio as f64,
+        }))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    LeftParen,
+    RightParen,
+    And,
+    Or,
+    Not,
+    /// 一个查询项，`quoted` 表示整个项由引号包围
+    Term { text: String, quoted: bool },
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+fn tokenize(input: &str) -> std::result::Result<Vec<Token>, QueryParseError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut index: usize = 0;
+    
+    while index < chars.len() {
+        let ch: char = chars[index];
+        
+        if ch.is_whitespace() {
+            index += 1;
+            continue;
+        }
+        
+        match ch {
+            '(' => {
+                tokens.push(Token { kind: TokenKind::LeftParen, position: index });
+                index += 1;
+            }
+            ')' => {
+                tokens.push(Token { kind: TokenKind::RightParen, position: index });
+                index += 1;
+            }
+            '-' if chars.get(index + 1).is_some_and(|next| !next.is_whitespace()) => {
+                tokens.push(Token { kind: TokenKind::Not, position: index });
+                index += 1;
+            }
+            _ => {
+                let start: usize = index;
+                let mut text: String = String::new();
+                let mut quoted: bool = false;
+                
+                while index < chars.len() {
+                    let ch: char = chars[index];
+                    if ch.is_whitespace() || ch == '(' || ch == ')' {
+                        break;
+                    }
+                    
+                    if ch == '"' {
+                        let quote_start: usize = index;
+                        quoted = text.is_empty();
+                        index += 1;
+                        loop {
+                            match chars.get(index) {
+                                Some('"') => {
+                                    index += 1;
+                                    break;
+                                }
+                                Some('\\') if chars.get(index + 1) == Some(&'"') => {
+                                    text.push('"');
+                                    index += 2;
+                                }
+                                Some(&c) => {
+                                    text.push(c);
+                                    index += 1;
+                                }
+                                None => {
+                                    return Err(QueryParseError::new(quote_start, "引号未闭合"));
+                                }
+                            }
+                        }
+                        continue;
+                    }
+                    
+                    text.push(ch);
+                    index += 1;
+                }
+                
+                let kind: TokenKind = match text.as_str() {
+                    "AND" if !quoted => TokenKind::And,
+                    "OR" if !quoted => TokenKind::Or,
+                    "NOT" if !quoted => TokenKind::Not,
+                    _ => TokenKind::Term { text, quoted },
+                };
+                tokens.push(Token { kind, position: start });
+            }
+        }
+    }
+    
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    input_length: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+    
+    fn next(&mut self) -> Option<Token> {
+        let token: Option<Token> = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+    
+    fn parse_or(&mut self) -> std::result::Result<Query, QueryParseError> {
+        let mut query: Query = self.parse_and()?;
+        
+        while let Some(Token { kind: TokenKind::Or, .. }) = self.peek() {
+            self.next();
+            let right: Query = self.parse_and()?;
+            query = query.or(right);
+        }
+        
+        Ok(query)
+    }
+    
+    fn parse_and(&mut self) -> std::result::Result<Query, QueryParseError> {
+        let mut query: Query = self.parse_unary()?;
+        
+        loop {
+            match self.peek().map(|token| &token.kind) {
+                Some(TokenKind::And) => {
+                    self.next();
+                }
+                // 相邻的条件之间隐式为 AND
+                Some(TokenKind::Term { .. }) | Some(TokenKind::Not) | Some(TokenKind::LeftParen) => {}
+                _ => break,
+            }
+            let right: Query = self.parse_unary()?;
+            query = query.and(right);
+        }
+        
+        Ok(query)
+    }
+    
+    fn parse_unary(&mut self) -> std::result::Result<Query, QueryParseError> {
+        if let Some(Token { kind: TokenKind::Not, .. }) = self.peek() {
+            self.next();
+            return Ok(self.parse_unary()?.negate());
+        }
+        self.parse_primary()
+    }
+    
+    fn parse_primary(&mut self) -> std::result::Result<Query, QueryParseError> {
+        let token: Token = match self.next() {
+            Some(token) => token,
+            None => return Err(QueryParseError::new(self.input_length, "查询意外结束，缺少查询条件")),
+        };
+        
+        match token.kind {
+            TokenKind::LeftParen => {
+                if let Some(Token { kind: TokenKind::RightParen, position }) = self.peek() {
+                    return Err(QueryParseError::new(*position, "括号内缺少查询条件"));
+                }
+                let query: Query = self.parse_or()?;
+                match self.next() {
+                    Some(Token { kind: TokenKind::RightParen, .. }) => Ok(query),
+                    _ => Err(QueryParseError::new(token.position, "括号未闭合")),
+                }
+            }
+            TokenKind::RightParen => Err(QueryParseError::new(token.position, "多余的 `)`")),
+            TokenKind::And | TokenKind::Or => {
+                Err(QueryParseError::new(token.position, "AND/OR 前缺少查询条件"))
+            }
+            TokenKind::Not => unreachable!("NOT 已在 parse_unary 中处理"),
+            TokenKind::Term { text, quoted } => parse_term(&text, quoted, token.position),
+        }
+    }
+}
+
+/// 解析单个查询项，例如 `width>=3840`、`modified:<2024-01-01`、`name:~sunset`
+fn parse_term(text: &str, quoted: bool, position: usize) -> std::result::Result<Query, QueryParseError> {
+    let field_length: usize = text
+        .chars()
+        .take_while(|ch| ch.is_ascii_alphabetic() || *ch == '_')
+        .count();
+    let rest: &str = &text[field_length..];
+    
+    let operator: Option<(&str, usize)> = ["<=", ">=", ":", "=", "<", ">"]
+        .iter()
+        .find(|op| rest.starts_with(**op))
+        .map(|op| (*op, op.len()));
+    
+    // 没有字段前缀的普通文本按文件名包含匹配
+    let (op_text, op_length) = match operator {
+        Some(operator) if field_length > 0 && !quoted => operator,
+        _ => {
+            return Ok(Query::Term(Predicate::Text {
+                field: TextField::Name,
+                value: text.to_lowercase(),
+                contains: true,
+            }));
+        }
+    };
+    
+    let field: &str = &text[..field_length];
+    let mut value: &str = &rest[op_length..];
+    let mut value_position: usize = position + field_length + op_length;
+    
+    // `field:` 后还可以跟比较运算符或 `~`，例如 `modified:<2024-01-01`
+    let mut op: CompareOp = parse_operator(op_text);
+    let mut contains: bool = false;
+    if op_text == ":" {
+        if let Some(stripped) = value.strip_prefix('~') {
+            contains = true;
+            value = stripped;
+            value_position += 1;
+        } else if let Some(inner) = ["<=", ">=", "=", "<", ">"].iter().find(|inner| value.starts_with(**inner)) {
+            op = parse_operator(inner);
+            value = &value[inner.len()..];
+            value_position += inner.len();
+        }
+    }
+    
+    if value.is_empty() {
+        return Err(QueryParseError::new(value_position, format!("字段 `{}` 缺少取值", field)));
+    }
+    
+    let field_name: String = field.to_lowercase();
+    let predicate: Predicate = match field_name.as_str() {
+        "name" | "path" | "format" | "tag" | "camera" | "lens" | "artist" | "copyright" => {
+            if op != CompareOp::Eq {
+                return Err(unsupported_operator(&field_name, op, position + field_length));
+            }
+            let field: TextField = match field_name.as_str() {
+                "name" => TextField::Name,
+                "path" => TextField::Path,
+                "format" => TextField::Format,
+                "camera" => TextField::Camera,
+                "lens" => TextField::Lens,
+                "artist" => TextField::Artist,
+                "copyright" => TextField::Copyright,
+                _ => TextField::Tag,
+            };
+            Predicate::Text {
+                field,
+                value: value.to_lowercase(),
+                contains,
+            }
+        }
+        "width" | "height" | "effective_width" | "ewidth" | "effective_height" | "eheight" => {
+            reject_contains(contains, &field_name, value_position)?;
+            let number: u32 = value.parse().map_err(|_| {
+                QueryParseError::new(value_position, format!("`{}` 不是有效的像素值", value))
+            })?;
+            Predicate::Number {
+                field: match field_name.as_str() {
+                    "width" => NumberField::Width,
+                    "height" => NumberField::Height,
+                    "effective_width" | "ewidth" => NumberField::EffectiveWidth,
+                    _ => NumberField::EffectiveHeight,
+                },
+                op,
+                value: number as f64,
+            }
+        }
+        "rating" => {
+            reject_contains(contains, &field_name, value_position)?;
+            let number: u8 = value
+                .parse()
+                .ok()
+                .filter(|number: &u8| *number <= MAX_RATING)
+                .ok_or_else(|| QueryParseError::new(value_position, format!("`{}` 不是有效的评分，应为 0-{}", value, MAX_RATING)))?;
+            Predicate::Number {
+                field: NumberField::Rating,
+                op,
+                value: number as f64,
+            }
+        }
+        "brightness" => {
+            reject_contains(contains, &field_name, value_position)?;
+            let number: f64 = value
+                .parse()
+                .ok()
+                .filter(|number: &f64| (0.0..=100.0).contains(number))
+                .ok_or_else(|| QueryParseError::new(value_position, format!("`{}` 不是有效的亮度，应为 0-100", value)))?;
+            Predicate::Number {
+                field: NumberField::Brightness,
+                op,
+                value: number,
+            }
+        }
+        "quality" => {
+            reject_contains(contains, &field_name, value_position)?;
+            let number: f64 = value
+                .parse()
+                .ok()
+                .filter(|number: &f64| (0.0..=100.0).contains(number))
+                .ok_or_else(|| QueryParseError::new(value_position, format!("`{}` 不是有效的质量分，应为 0-100", value)))?;
+            Predicate::Number {
+                field: NumberField::Quality,
+                op,
+                value: number,
+            }
+        }
+        "color" => {
+            if op != CompareOp::Eq || contains {
+                return Err(unsupported_operator(&field_name, op, position + field_length));
+            }
+            let rgb: [u8; 3] = parse_color(value).ok_or_else(|| {
+                QueryParseError::new(value_position, format!("`{}` 不是有效的颜色，应为 #rrggbb 或颜色名称", value))
+            })?;
+            Predicate::Color { rgb }
+        }
+        "ratio" => {
+            reject_contains(contains, &field_name, value_position)?;
+            Predicate::Number {
+                field: NumberField::AspectRatio,
+                op,
+                value: parse_ratio(value, value_position)?,
+            }
+        }
+        "size" => {
+            reject_contains(contains, &field_name, value_position)?;
+            Predicate::Number {
+                field: NumberField::FileSize,
+                op,
+                value: parse_file_size(value, value_position)?,
+            }
+        }
+        "modified" | "created" | "taken" => {
+            reject_contains(contains, &field_name, value_position)?;
+            let date: NaiveDate = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+                QueryParseError::new(value_position, format!("`{}` 不是有效的日期，应为 YYYY-MM-DD", value))
+            })?;
+            Predicate::Date {
+                field: match field_name.as_str() {
+                    "modified" => DateField::Modified,
+                    "created" => DateField::Created,
+                    _ => DateField::Captured,
+                },
+                op,
+                value: date,
+            }
+        }
+        "is" => {
+            if op != CompareOp::Eq || contains {
+                return Err(unsupported_operator(&field_name, op, position + field_length));
+            }
+            let flag: Flag = match value.to_lowercase().as_str() {
+                "landscape" => Flag::Landscape,
+                "portrait" => Flag::Portrait,
+                "square" => Flag::Square,
+                "geotagged" => Flag::Geotagged,
+                "dark" => Flag::Dark,
+                "light" => Flag::Light,
+                "favorite" | "fav" => Flag::Favorite,
+                "animated" | "anim" => Flag::Animated,
+                "upscaled" => Flag::Upscaled,
+                _ => {
+                    return Err(QueryParseError::new(
+                        value_position,
+                        format!("未知属性 `{}`，可用: landscape, portrait, square, geotagged, dark, light, favorite, animated, upscaled", value),
+                    ));
+                }
+            };
+            Predicate::Flag(flag)
+        }
+        _ => {
+            return Err(QueryParseError::new(
+                position,
+                format!(
+                    "未知字段 `{}`，可用: name, path, format, tag, camera, lens, artist, copyright, width, height, effective_width, effective_height, ratio, size, rating, brightness, quality, color, modified, created, taken, is",
+                    field
+                ),
+            ));
+        }
+    };
+    
+    Ok(Query::Term(predicate))
+}
+
+fn parse_operator(op: &str) -> CompareOp {
+    match op {
+        "<" => CompareOp::Lt,
+        "<=" => CompareOp::Le,
+        ">" => CompareOp::Gt,
+        ">=" => CompareOp::Ge,
+        _ => CompareOp::Eq,
+    }
+}
+
+fn unsupported_operator(field: &str, op: CompareOp, position: usize) -> QueryParseError {
+    QueryParseError::new(
+        position,
+        format!("字段 `{}` 不支持运算符 `{}`", field, op.symbol()),
+    )
+}
+
+fn reject_contains(contains: bool, field: &str, position: usize) -> std::result::Result<(), QueryParseError> {
+    if contains {
+        Err(QueryParseError::new(position - 1, format!("字段 `{}` 不支持 `~` 包含匹配", field)))
+    } else {
+        Ok(())
+    }
+}
+
+/// 解析宽高比，支持 `16:9`、`16/9` 和 `1.78` 三种写法
+fn parse_ratio(value: &str, position: usize) -> std::result::Result<f64, QueryParseError> {
+    let invalid = || QueryParseError::new(position, format!("`{}` 不是有效的宽高比", value));
+    
+    let ratio: f64 = match value.split_once([':', '/']) {
+        Some((width, height)) => {
+            let width: f64 = width.parse().map_err(|_| invalid())?;
+            let height: f64 = height.parse().map_err(|_| invalid())?;
+            if height == 0.0 {
+                return Err(invalid());
+            }
+            width / height
+        }
+        None => value.parse().map_err(|_| invalid())?,
+    };
+    
+    Ok(ratio)
+}
+
+/// 解析文件大小，支持 `B`、`KB`、`MB`、`GB` 单位（按 1024 进制）
+fn parse_file_size(value: &str, position: usize) -> std::result::Result<f64, QueryParseError> {
+    let upper: String = value.to_uppercase();
+    let digits_length: usize = upper
+        .chars()
+        .take_while(|ch| ch.is_ascii_digit() || *ch == '.')
+        .count();
+    let (number, unit) = upper.split_at(digits_length);
+    
+    let multiplier: f64 = match unit {
+        "" | "B" => 1.0,
+        "K" | "KB" => 1024.0,
+        "M" | "MB" => 1024.0 * 1024.0,
+        "G" | "GB" => 1024.0 * 1024.0 * 1024.0,
+        _ => {
+            return Err(QueryParseError::new(
+                position + digits_length,
+                format!("未知的大小单位 `{}`", unit),
+            ));
+        }
+    };
+    
+    let number: f64 = number.parse().map_err(|_| {
+        QueryParseError::new(position, format!("`{}` 不是有效的文件大小", value))
+    })?;
+    
+    Ok(number * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use crate::models::{AnimationInfo, ImageQuality};
+
+    fn create_test_wallpaper(filename: &str, size: (u32, u32), tags: &[&str]) -> Wallpaper {
+        Wallpaper {
+            id: filename.to_string(),
+            file_size: 2 * 1024 * 1024,
+            created_at: chrono::Utc.with_ymd_and_hms(2023, 6, 1, 12, 0, 0).unwrap(),
+            modified_at: chrono::Utc.with_ymd_and_hms(2023, 12, 24, 12, 0, 0).unwrap(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Wallpaper::for_test(&format!("/pictures/wallpapers/{}", filename))
+        }
+        .with_dimensions(size.0, size.1)
+    }
+    
+    fn matching(query: &str, wallpapers: &[Wallpaper]) -> Vec<String> {
+        Query::parse(query)
+            .unwrap()
+            .filter(wallpapers)
+            .into_iter()
+            .map(|wallpaper| wallpaper.filename.clone())
+            .collect()
+    }
+    
+    fn sample_wallpapers() -> Vec<Wallpaper> {
+        vec![
+            create_test_wallpaper("Sunset_Beach.png", (3840, 2160), &["nature", "sea"]),
+            create_test_wallpaper("mountains.jpeg", (1920, 1080), &["Nature"]),
+            create_test_wallpaper("city.webp", (1080, 1920), &["urban/night"]),
+            create_test_wallpaper("square.jpg", (1000, 1000), &[]),
+        ]
+    }
+
+    #[test]
+    fn test_field_terms() {
+        let wallpapers: Vec<Wallpaper> = sample_wallpapers();
+        
+        assert_eq!(
+            matching("tag:nature width>=3840 format:png ratio:16:9 modified:<2024-01-01 name:~sunset", &wallpapers),
+            vec!["Sunset_Beach.png"]
+        );
+        assert_eq!(matching("format:jpg", &wallpapers), vec!["mountains.jpeg", "square.jpg"]);
+        assert_eq!(matching("is:portrait", &wallpapers), vec!["city.webp"]);
+        assert_eq!(matching("size:>1MB created:2023-06-01", &wallpapers).len(), 4);
+        assert_eq!(matching("name:mountains", &wallpapers), vec!["mountains.jpeg"]);
+        assert_eq!(matching("\"city\"", &wallpapers), vec!["city.webp"]);
+        assert_eq!(matching("tag:urban", &wallpapers), vec!["city.webp"]);
+        assert!(matching("tag:urban/day", &wallpapers).is_empty());
+        assert!(matching("", &wallpapers).len() == 4);
+    }
+
+    #[test]
+    fn test_boolean_operators() {
+        let wallpapers: Vec<Wallpaper> = sample_wallpapers();
+        
+        assert_eq!(
+            matching("tag:nature OR tag:urban", &wallpapers),
+            vec!["Sunset_Beach.png", "mountains.jpeg", "city.webp"]
+        );
+        assert_eq!(matching("tag:nature AND NOT format:png", &wallpapers), vec!["mountains.jpeg"]);
+        assert_eq!(
+            matching("-(tag:nature OR is:square)", &wallpapers),
+            vec!["city.webp"]
+        );
+        assert_eq!(
+            matching("(is:landscape OR is:square) width<2000", &wallpapers),
+            vec!["mountains.jpeg", "square.jpg"]
+        );
+    }
+
+    #[test]
+    fn test_composable_predicates() {
+        let wallpapers: Vec<Wallpaper> = sample_wallpapers();
+        
+        let query: Query = Query::min_size(1920, 1080)
+            .and(Query::aspect_ratio_between(1.7, 1.8))
+            .and(Query::format("png").negate());
+        let names: Vec<&str> = query.filter(&wallpapers)
+            .into_iter()
+            .map(|wallpaper| wallpaper.filename.as_str())
+            .collect();
+        assert_eq!(names, vec!["mountains.jpeg"]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error: QueryParseError = Query::parse("tag:nature colour:red").unwrap_err();
+        assert_eq!(error.position, 11);
+        
+        let error: QueryParseError = Query::parse("width>=abc").unwrap_err();
+        assert_eq!(error.position, 7);
+        
+        let error: QueryParseError = Query::parse("(tag:a OR tag:b").unwrap_err();
+        assert_eq!(error.position, 0);
+        
+        let error: QueryParseError = Query::parse("tag:a )").unwrap_err();
+        assert_eq!(error.position, 6);
+        
+        let error: QueryParseError = Query::parse("tag:a OR").unwrap_err();
+        assert_eq!(error.position, 8);
+        
+        let error: QueryParseError = Query::parse("name:\"sunset").unwrap_err();
+        assert_eq!(error.position, 5);
+        
+        let error: QueryParseError = Query::parse("modified:<2024-13-01").unwrap_err();
+        assert_eq!(error.position, 10);
+        
+        let error: QueryParseError = Query::parse("tag>=5").unwrap_err();
+        assert!(error.message.contains(">="));
+    }
+
+    #[test]
+    fn test_metadata_fields() {
+        let mut wallpapers: Vec<Wallpaper> = sample_wallpapers();
+        wallpapers[1].metadata = crate::models::ImageMetadata {
+            camera_make: Some("FUJIFILM".to_string()),
+            camera_model: Some("X-T5".to_string()),
+            artist: Some("Jane Doe".to_string()),
+            captured_at: NaiveDate::from_ymd_opt(2022, 5, 3).unwrap().and_hms_opt(8, 0, 0),
+            gps: Some(crate::models::image_metadata::GpsCoordinates {
+                latitude: 35.0,
+                longitude: 135.7,
+                altitude: None,
+            }),
+            ..Default::default()
+        };
+        
+        assert_eq!(matching("camera:~fujifilm artist:\"jane doe\"", &wallpapers), vec!["mountains.jpeg"]);
+        assert_eq!(matching("taken:<2023-01-01", &wallpapers), vec!["mountains.jpeg"]);
+        assert_eq!(matching("is:geotagged", &wallpapers), vec!["mountains.jpeg"]);
+        assert_eq!(matching("-copyright:~cc", &wallpapers).len(), 4);
+    }
+
+    #[test]
+    fn test_color_fields() {
+        use crate::utils::{ColorPalette, PaletteColor};
+        
+        let palette = |rgb: [u8; 3], brightness: f32| -> Option<ColorPalette> {
+            Some(ColorPalette {
+                colors: vec![PaletteColor { rgb, weight: 1.0 }],
+                brightness,
+            })
+        };
+        let mut wallpapers: Vec<Wallpaper> = sample_wallpapers();
+        wallpapers[0].colors = palette([240, 130, 40], 0.7);
+        wallpapers[2].colors = palette([20, 30, 90], 0.15);
+        
+        assert_eq!(matching("color:orange", &wallpapers), vec!["Sunset_Beach.png"]);
+        assert_eq!(matching("color:#1a2060", &wallpapers), vec!["city.webp"]);
+        assert_eq!(matching("is:dark", &wallpapers), vec!["city.webp"]);
+        assert_eq!(matching("is:light", &wallpapers), vec!["Sunset_Beach.png"]);
+        assert_eq!(matching("brightness:>50", &wallpapers), vec!["Sunset_Beach.png"]);
+        
+        assert!(Query::parse("color:magentaish").is_err());
+        assert!(Query::parse("brightness>120").is_err());
+    }
+
+    #[test]
+    fn test_rating_and_favorite() {
+        let mut wallpapers: Vec<Wallpaper> = sample_wallpapers();
+        wallpapers[0].rating = 5;
+        wallpapers[1].rating = 3;
+        wallpapers[1].favorite = true;
+        
+        assert_eq!(matching("rating>=4", &wallpapers), vec!["Sunset_Beach.png"]);
+        assert_eq!(matching("rating:0", &wallpapers), vec!["city.webp", "square.jpg"]);
+        assert_eq!(matching("is:favorite", &wallpapers), vec!["mountains.jpeg"]);
+        
+        wallpapers[2].animation = Some(AnimationInfo { frame_count: 12, duration_ms: 1200 });
+        assert_eq!(matching("is:animated", &wallpapers), vec!["city.webp"]);
+        assert!(Query::parse("rating:6").is_err());
+    }
+
+    #[test]
+    fn test_quality_fields() {
+        let mut wallpapers: Vec<Wallpaper> = sample_wallpapers();
+        // 4K 壁纸实际由 720p 放大而来
+        wallpapers[0].quality = Some(ImageQuality {
+            sharpness: 15.0,
+            jpeg_quality: None,
+            blockiness: 0.0,
+            effective_size: (1280, 720),
+            score: 35,
+        });
+        wallpapers[1].quality = Some(ImageQuality {
+            sharpness: 400.0,
+            jpeg_quality: Some(92),
+            blockiness: 0.05,
+            effective_size: (1920, 1080),
+            score: 78,
+        });
+        
+        assert_eq!(matching("is:upscaled", &wallpapers), vec!["Sunset_Beach.png"]);
+        assert_eq!(matching("width>=1920 effective_width>=1920", &wallpapers), vec!["mountains.jpeg"]);
+        // 没有质量指标的壁纸按实际尺寸计算有效分辨率，但不参与质量分比较
+        assert_eq!(matching("eheight>=1080", &wallpapers), vec!["mountains.jpeg", "city.webp"]);
+        assert_eq!(matching("quality<50", &wallpapers), vec!["Sunset_Beach.png"]);
+        assert!(Query::parse("quality>101").is_err());
+    }
+}
\ No newline at end of file
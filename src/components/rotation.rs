@@ -0,0 +1,166 @@
+use crate::models::Wallpaper;
+use crate::utils::splitmix64;
+
+/// 各星级的权重，下标为星级，未评分（0）按 3 星计算
+const RATING_WEIGHTS: [f64; 6] = [4.0, 1.0, 2.0, 4.0, 8.0, 16.0];
+
+/// 收藏的壁纸额外乘以的权重
+const FAVORITE_WEIGHT_MULTIPLIER: f64 = 2.0;
+
+/// 壁纸轮换调度器：从候选壁纸中随机挑选下一张
+///
+/// 启用评分权重时，高星级和收藏的壁纸被选中的概率更高；
+/// 最近使用过的壁纸会被跳过，避免短时间内重复
+pub struct RotationScheduler {
+    /// splitmix64 随机数状态
+    state: u64,
+    weight_by_rating: bool,
+    avoid_recent: usize,
+}
+
+impl Default for RotationScheduler {
+    fn default() -> Self {
+        let seed: u64 = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        Self::new(seed)
+    }
+}
+
+impl RotationScheduler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed,
+            weight_by_rating: true,
+            avoid_recent: 1,
+        }
+    }
+    
+    pub fn with_rating_weights(mut self, weight_by_rating: bool) -> Self {
+        self.weight_by_rating = weight_by_rating;
+        self
+    }
+    
+    /// 跳过最近使用过的若干张壁纸，候选不足时会逐步放宽
+    pub fn with_avoid_recent(mut self, count: usize) -> Self {
+        self.avoid_recent = count;
+        self
+    }
+    
+    pub fn get_weight(&self, wallpaper: &Wallpaper) -> f64 {
+        if !self.weight_by_rating {
+            return 1.0;
+        }
+        
+        let weight: f64 = RATING_WEIGHTS[wallpaper.rating.min(5) as usize];
+        if wallpaper.favorite {
+            weight * FAVORITE_WEIGHT_MULTIPLIER
+        } else {
+            weight
+        }
+    }
+    
+    /// 挑选下一张壁纸，`recent` 为最近使用过的壁纸ID，最近的在前
+    pub fn pick<'a>(&mut self, candidates: &[&'a Wallpaper], recent: &[&str]) -> Option<&'a Wallpaper> {
+        let mut avoid: usize = self.avoid_recent.min(recent.len());
+        loop {
+            let eligible: Vec<&'a Wallpaper> = candidates
+                .iter()
+                .copied()
+                .filter(|wallpaper| !recent[..avoid].contains(&wallpaper.id.as_str()))
+                .collect();
+            
+            if !eligible.is_empty() {
+                return Some(self.pick_weighted(&eligible));
+            }
+            if avoid == 0 {
+                return None;
+            }
+            avoid -= 1;
+        }
+    }
+    
+    fn pick_weighted<'a>(&mut self, eligible: &[&'a Wallpaper]) -> &'a Wallpaper {
+        let total: f64 = eligible.iter().map(|wallpaper| self.get_weight(wallpaper)).sum();
+        let mut target: f64 = self.next_f64() * total;
+        
+        for wallpaper in eligible {
+            target -= self.get_weight(wallpaper);
+            if target < 0.0 {
+                return wallpaper;
+            }
+        }
+        eligible[eligible.len() - 1]
+    }
+    
+    /// 返回 [0, 1) 区间的随机数，`splitmix64` 内部已加上步长，这里只需推进计数
+    fn next_f64(&mut self) -> f64 {
+        let value: u64 = splitmix64(self.state);
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        (value >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_wallpaper(id: &str, rating: u8, favorite: bool) -> Wallpaper {
+        Wallpaper {
+            id: id.to_string(),
+            rating,
+            favorite,
+            ..Wallpaper::for_test(&format!("{}.jpg", id))
+        }
+    }
+
+    #[test]
+    fn test_weighted_pick() {
+        let wallpapers: Vec<Wallpaper> = vec![
+            create_test_wallpaper("low", 1, false),
+            create_test_wallpaper("high", 5, true),
+        ];
+        let candidates: Vec<&Wallpaper> = wallpapers.iter().collect();
+        
+        let mut scheduler: RotationScheduler = RotationScheduler::new(42).with_avoid_recent(0);
+        let high: usize = (0..1000)
+            .filter(|_| scheduler.pick(&candidates, &[]).unwrap().id == "high")
+            .count();
+        // 权重为 1 比 32
+        assert!(high > 940, "high picked {} times", high);
+        
+        let mut scheduler: RotationScheduler = RotationScheduler::new(42).with_rating_weights(false);
+        let high: usize = (0..1000)
+            .filter(|_| scheduler.pick(&candidates, &[]).unwrap().id == "high")
+            .count();
+        assert!((400..600).contains(&high), "high picked {} times", high);
+    }
+
+    #[test]
+    fn test_avoid_recent() {
+        let wallpapers: Vec<Wallpaper> = vec![
+            create_test_wallpaper("a", 5, false),
+            create_test_wallpaper("b", 1, false),
+        ];
+        let candidates: Vec<&Wallpaper> = wallpapers.iter().collect();
+        let mut scheduler: RotationScheduler = RotationScheduler::new(7).with_avoid_recent(2);
+        
+        for _ in 0..20 {
+            assert_eq!(scheduler.pick(&candidates, &["a"]).unwrap().id, "b");
+        }
+        // 所有候选都在最近使用列表中时，只跳过最近的一张
+        assert_eq!(scheduler.pick(&candidates, &["b", "a"]).unwrap().id, "a");
+        assert!(scheduler.pick(&[], &[]).is_none());
+    }
+
+    #[test]
+    fn test_random_sequence() {
+        // SplitMix64 以 0 为种子的参考输出，每步只加一次步长
+        let mut scheduler: RotationScheduler = RotationScheduler::new(0);
+        for expected in [0xe220a8397b1dcdafu64, 0x6e789e6aa1b965f4, 0x06c45d188009454f] {
+            let value: u64 = (scheduler.next_f64() * (1u64 << 53) as f64) as u64;
+            assert_eq!(value, expected >> 11);
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,241 @@
+use std::cmp::Ordering;
+use chrono::NaiveDateTime;
+use icu_collator::{Collator, CollatorOptions, Numeric, Strength};
+use icu_locid::locale;
+use serde::{Deserialize, Serialize};
+use crate::models::Wallpaper;
+use crate::utils::{get_aspect_ratio, splitmix64, stable_hash};
+
+/// 排序字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "key", rename_all = "snake_case")]
+pub enum SortKey {
+    /// 文件名，自然排序（`img2` 在 `img10` 之前）
+    Name,
+    Modified,
+    Created,
+    /// EXIF 拍摄时间，没有拍摄时间的壁纸使用文件修改时间
+    CapturedAt,
+    FileSize,
+    /// 像素面积
+    PixelArea,
+    AspectRatio,
+    Rating,
+    /// 有效分辨率的像素面积，没有质量指标时按实际尺寸
+    EffectiveResolution,
+    /// 综合质量分，没有质量指标的壁纸排在最低
+    Quality,
+    /// 主色色相；接近灰色的壁纸排在彩色之后并按亮度从亮到暗，没有调色板的壁纸排在最后
+    Hue,
+    /// 按种子打乱，种子相同时顺序相同
+    Random { seed: u64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// 单个排序条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortCriterion {
+    #[serde(flatten)]
+    pub key: SortKey,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+/// 多字段排序规则，前面的字段相同时再比较后面的字段，
+/// 例如先按评分降序、评分相同再按修改时间降序
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SortSpec {
+    pub criteria: Vec<SortCriterion>,
+}
+
+impl SortSpec {
+    pub fn by(key: SortKey, order: SortOrder) -> Self {
+        Self::default().then(key, order)
+    }
+    
+    pub fn then(mut self, key: SortKey, order: SortOrder) -> Self {
+        self.criteria.push(SortCriterion { key, order });
+        self
+    }
+    
+    pub fn compare(&self, a: &Wallpaper, b: &Wallpaper) -> Ordering {
+        self.criteria
+            .iter()
+            .map(|criterion| {
+                let ordering: Ordering = compare_by_key(criterion.key, a, b);
+                match criterion.order {
+                    SortOrder::Ascending => ordering,
+                    SortOrder::Descending => ordering.reverse(),
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+    
+    /// 稳定排序，所有字段都相同的壁纸保持原有顺序
+    pub fn sort(&self, wallpapers: &mut [Wallpaper]) {
+        wallpapers.sort_by(|a, b| self.compare(a, b));
+    }
+}
+
+fn compare_by_key(key: SortKey, a: &Wallpaper, b: &Wallpaper) -> Ordering {
+    match key {
+        SortKey::Name => natural_cmp(&a.filename, &b.filename),
+        SortKey::Modified => a.modified_at.cmp(&b.modified_at),
+        SortKey::Created => a.created_at.cmp(&b.created_at),
+        SortKey::CapturedAt => captured_at(a).cmp(&captured_at(b)),
+        SortKey::FileSize => a.file_size.cmp(&b.file_size),
+        SortKey::PixelArea => pixel_area(a).cmp(&pixel_area(b)),
+        SortKey::AspectRatio => get_aspect_ratio(a.size.0, a.size.1).total_cmp(&get_aspect_ratio(b.size.0, b.size.1)),
+        SortKey::Rating => a.rating.cmp(&b.rating),
+        SortKey::EffectiveResolution => effective_area(a).cmp(&effective_area(b)),
+        SortKey::Quality => a.quality.map(|quality| quality.score).cmp(&b.quality.map(|quality| quality.score)),
+        SortKey::Hue => {
+            let ((group_a, value_a), (group_b, value_b)) = (hue_rank(a), hue_rank(b));
+            group_a.cmp(&group_b).then(value_a.total_cmp(&value_b))
+        }
+        SortKey::Random { seed } => random_rank(seed, a).cmp(&random_rank(seed, b)),
+    }
+}
+
+fn captured_at(wallpaper: &Wallpaper) -> NaiveDateTime {
+    wallpaper.metadata.captured_at.unwrap_or_else(|| wallpaper.modified_at.naive_utc())
+}
+
+/// (分组, 排序值)：0 为彩色，按色相；1 为灰色，按亮度从亮到暗；2 为未计算调色板
+fn hue_rank(wallpaper: &Wallpaper) -> (u8, f32) {
+    match &wallpaper.colors {
+        Some(colors) => match colors.get_hue() {
+            Some(hue) => (0, hue),
+            None => (1, -colors.brightness),
+        },
+        None => (2, 0.0),
+    }
+}
+
+fn pixel_area(wallpaper: &Wallpaper) -> u64 {
+    wallpaper.size.0 as u64 * wallpaper.size.1 as u64
+}
+
+fn effective_area(wallpaper: &Wallpaper) -> u64 {
+    let (width, height) = wallpaper.get_effective_size();
+    width as u64 * height as u64
+}
+
+/// 由种子和壁纸 ID 决定的随机序号，与壁纸在列表中的位置无关
+fn random_rank(seed: u64, wallpaper: &Wallpaper) -> u64 {
+    splitmix64(seed ^ stable_hash(wallpaper.id.as_bytes()))
+}
+
+/// 文件名排序使用的中文排序规则：汉字按拼音排序，排在拉丁字母之前
+///
+/// 数字按数值比较；只比较到第二级，不区分大小写、全角半角和平假名片假名
+fn create_collator() -> Collator {
+    let mut options: CollatorOptions = CollatorOptions::new();
+    options.strength = Some(Strength::Secondary);
+    options.numeric = Some(Numeric::On);
+    Collator::try_new(&locale!("zh").into(), options).expect("内置的中文排序规则数据")
+}
+
+// 排序规则数据不能跨线程共享，每个线程创建一份
+thread_local! {
+    static COLLATOR: Collator = create_collator();
+}
+
+/// 自然排序比较：按中文排序规则比较，数字按数值比较（`img2` 在 `img10` 之前）
+///
+/// 比较结果相同时按原始字符串区分，保证顺序确定
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    COLLATOR.with(|collator| collator.compare(a, b)).then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_natural_cmp() {
+        let mut names: Vec<&str> = vec!["img10.jpg", "IMG2.jpg", "img1.jpg", "img02.jpg", "Beach.png", "apple.png"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, vec!["apple.png", "Beach.png", "img1.jpg", "IMG2.jpg", "img02.jpg", "img10.jpg"]);
+        
+        // 全角数字与半角数字等价，片假名与平假名等价
+        assert_eq!(natural_cmp("壁纸２.png", "壁纸10.png"), Ordering::Less);
+        assert_eq!(natural_cmp("サクラ1", "さくら2"), Ordering::Less);
+        // 汉字按拼音排序
+        let mut names: Vec<&str> = vec!["山.jpg", "川.jpg", "北京.jpg", "阿尔卑斯.jpg"];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(names, vec!["阿尔卑斯.jpg", "北京.jpg", "川.jpg", "山.jpg"]);
+    }
+
+    fn sorted_ids(spec: &SortSpec, mut wallpapers: Vec<Wallpaper>) -> Vec<String> {
+        spec.sort(&mut wallpapers);
+        wallpapers.into_iter().map(|wallpaper| wallpaper.id).collect()
+    }
+
+    fn create_test_wallpapers(count: usize) -> Vec<Wallpaper> {
+        (0..count)
+            .map(|i| Wallpaper {
+                id: i.to_string(),
+                ..Wallpaper::for_test(&format!("{}.jpg", i))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_by_captured_at() {
+        let mut wallpapers: Vec<Wallpaper> = create_test_wallpapers(3);
+        wallpapers[0].metadata.captured_at = chrono::NaiveDate::from_ymd_opt(2021, 1, 1)
+            .and_then(|date| date.and_hms_opt(0, 0, 0));
+        wallpapers[2].metadata.captured_at = chrono::NaiveDate::from_ymd_opt(2019, 1, 1)
+            .and_then(|date| date.and_hms_opt(0, 0, 0));
+        
+        // 没有拍摄时间的 1 号按修改时间（当前时间）排在最后
+        let spec: SortSpec = SortSpec::by(SortKey::CapturedAt, SortOrder::Ascending);
+        assert_eq!(sorted_ids(&spec, wallpapers.clone()), ["2", "0", "1"]);
+        let spec: SortSpec = SortSpec::by(SortKey::CapturedAt, SortOrder::Descending);
+        assert_eq!(sorted_ids(&spec, wallpapers), ["1", "0", "2"]);
+    }
+
+    #[test]
+    fn test_sort_by_hue() {
+        use crate::utils::{ColorPalette, PaletteColor};
+        
+        let palette = |rgb: [u8; 3], brightness: f32| -> Option<ColorPalette> {
+            Some(ColorPalette {
+                colors: vec![PaletteColor { rgb, weight: 1.0 }],
+                brightness,
+            })
+        };
+        let mut wallpapers: Vec<Wallpaper> = create_test_wallpapers(6);
+        wallpapers[0].colors = palette([30, 30, 30], 0.1);
+        wallpapers[1].colors = palette([20, 60, 200], 0.3);
+        wallpapers[3].colors = palette([220, 30, 30], 0.5);
+        wallpapers[4].colors = palette([200, 200, 200], 0.8);
+        wallpapers[5].colors = palette([220, 30, 30], 0.5);
+        wallpapers[5].rating = 4;
+        
+        let spec: SortSpec = SortSpec::by(SortKey::Hue, SortOrder::Ascending);
+        assert_eq!(sorted_ids(&spec, wallpapers.clone()), ["3", "5", "1", "4", "0", "2"]);
+        
+        // 色相相同的红色壁纸按第二个字段（评分降序）排列
+        let spec: SortSpec = SortSpec::by(SortKey::Hue, SortOrder::Ascending).then(SortKey::Rating, SortOrder::Descending);
+        assert_eq!(sorted_ids(&spec, wallpapers), ["5", "3", "1", "4", "0", "2"]);
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let spec: SortSpec = SortSpec::by(SortKey::Rating, SortOrder::Descending)
+            .then(SortKey::Hue, SortOrder::Descending)
+            .then(SortKey::Random { seed: 7 }, SortOrder::Ascending);
+        let text: String = toml::to_string(&spec).unwrap();
+        assert_eq!(toml::from_str::<SortSpec>(&text).unwrap(), spec);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,229 @@
+use globset::{GlobBuilder, GlobMatcher};
+use crate::{Result, WallpaperError};
+use crate::models::tag_rule::{Orientation, RuleCondition, TagRule};
+use crate::models::Wallpaper;
+use crate::utils::{
+    ASPECT_RATIO_TOLERANCE, classify_color, get_aspect_ratio, is_jpeg_alias, is_landscape, is_portrait, is_square,
+};
+
+/// 规则命中结果：哪条规则为壁纸添加了哪个标签
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleMatch {
+    pub rule_id: String,
+    pub tag: String,
+}
+
+enum CompiledCondition {
+    Path(GlobMatcher),
+    Other(RuleCondition),
+}
+
+impl CompiledCondition {
+    fn compile(rule: &TagRule, condition: &RuleCondition) -> Result<Self> {
+        match condition {
+            RuleCondition::PathPattern { pattern } => {
+                let glob = GlobBuilder::new(pattern)
+                    .case_insensitive(true)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| {
+                        WallpaperError::Config(format!("规则 {} 的路径模式无效: {}", rule.name, e))
+                    })?;
+                Ok(CompiledCondition::Path(glob.compile_matcher()))
+            }
+            RuleCondition::AspectRatio { min, max } if min > max => Err(WallpaperError::Config(
+                format!("规则 {} 的宽高比区间无效: {} > {}", rule.name, min, max),
+            )),
+            condition => Ok(CompiledCondition::Other(condition.clone())),
+        }
+    }
+    
+    fn matches(&self, wallpaper: &Wallpaper) -> bool {
+        let (width, height) = wallpaper.size;
+        
+        match self {
+            CompiledCondition::Path(matcher) => matcher.is_match(&wallpaper.path),
+            CompiledCondition::Other(condition) => match condition {
+                RuleCondition::PathPattern { .. } => false,
+                RuleCondition::Resolution { min_width, min_height } => {
+                    width >= *min_width && height >= *min_height
+                }
+                RuleCondition::Orientation { orientation } => match orientation {
+                    Orientation::Landscape => is_landscape(width, height),
+                    Orientation::Portrait => is_portrait(width, height),
+                    Orientation::Square => is_square(width, height),
+                },
+                RuleCondition::AspectRatio { min, max } => {
+                    let ratio: f32 = get_aspect_ratio(width, height);
+                    ratio > 0.0
+                        && ratio >= min - ASPECT_RATIO_TOLERANCE
+                        && ratio <= max + ASPECT_RATIO_TOLERANCE
+                }
+                RuleCondition::DominantColor { color } => wallpaper.colors
+                    .as_ref()
+                    .and_then(|colors| colors.get_dominant())
+                    .is_some_and(|rgb| classify_color(rgb) == *color),
+                RuleCondition::Format { formats } => formats.iter().any(|format| {
+                    format.eq_ignore_ascii_case(&wallpaper.format)
+                        || (is_jpeg_alias(format) && is_jpeg_alias(&wallpaper.format))
+                }),
+            },
+        }
+    }
+}
+
+struct CompiledRule {
+    id: String,
+    tag: String,
+    conditions: Vec<CompiledCondition>,
+}
+
+/// 自动标签规则引擎，预先编译规则中的路径通配符
+pub struct TagRuleEngine {
+    rules: Vec<CompiledRule>,
+}
+
+impl TagRuleEngine {
+    /// 编译所有启用的规则，无效的规则会被记录并跳过
+    pub fn new(rules: &[TagRule]) -> Self {
+        let compiled: Vec<CompiledRule> = rules
+            .iter()
+            .filter(|rule| rule.enabled)
+            .filter_map(|rule| match Self::compile(rule) {
+                Ok(compiled) => Some(compiled),
+                Err(e) => {
+                    log::warn!("跳过无效的自动标签规则: {}", e);
+                    None
+                }
+            })
+            .collect();
+        
+        Self { rules: compiled }
+    }
+    
+    /// 检查规则是否有效
+    pub fn validate(rule: &TagRule) -> Result<()> {
+        Self::compile(rule).map(|_| ())
+    }
+    
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+    
+    /// 返回壁纸命中的所有规则及其标签，同一标签只记录第一条命中的规则
+    pub fn evaluate(&self, wallpaper: &Wallpaper) -> Vec<RuleMatch> {
+        let mut matches: Vec<RuleMatch> = Vec::new();
+        
+        for rule in &self.rules {
+            if matches.iter().any(|existing| existing.tag == rule.tag) {
+                continue;
+            }
+            if rule.conditions.iter().all(|condition| condition.matches(wallpaper)) {
+                matches.push(RuleMatch {
+                    rule_id: rule.id.clone(),
+                    tag: rule.tag.clone(),
+                });
+            }
+        }
+        
+        matches
+    }
+    
+    fn compile(rule: &TagRule) -> Result<CompiledRule> {
+        if rule.tag.trim().is_empty() {
+            return Err(WallpaperError::Config(format!("规则 {} 缺少标签", rule.name)));
+        }
+        // 没有条件的规则会匹配所有壁纸，视为配置错误
+        if rule.conditions.is_empty() {
+            return Err(WallpaperError::Config(format!("规则 {} 没有任何条件", rule.name)));
+        }
+        
+        let conditions: Vec<CompiledCondition> = rule.conditions
+            .iter()
+            .map(|condition| CompiledCondition::compile(rule, condition))
+            .collect::<Result<_>>()?;
+        
+        Ok(CompiledRule {
+            id: rule.id.clone(),
+            tag: rule.tag.clone(),
+            conditions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{ColorName, ColorPalette, PaletteColor};
+
+    fn create_test_wallpaper(path: &str, size: (u32, u32)) -> Wallpaper {
+        Wallpaper::for_test(path).with_dimensions(size.0, size.1)
+    }
+    
+    fn tags(engine: &TagRuleEngine, wallpaper: &Wallpaper) -> Vec<String> {
+        engine.evaluate(wallpaper).into_iter().map(|found| found.tag).collect()
+    }
+
+    #[test]
+    fn test_rule_conditions() {
+        let rules: Vec<TagRule> = vec![
+            TagRule::new("动漫".to_string(), "anime".to_string(), vec![
+                RuleCondition::PathPattern { pattern: "**/anime/**".to_string() },
+            ]),
+            TagRule::new("4K".to_string(), "4k".to_string(), vec![
+                RuleCondition::Resolution { min_width: 3840, min_height: 0 },
+            ]),
+            TagRule::new("竖屏".to_string(), "portrait".to_string(), vec![
+                RuleCondition::Orientation { orientation: Orientation::Portrait },
+            ]),
+            TagRule::new("带鱼屏".to_string(), "ultrawide".to_string(), vec![
+                RuleCondition::AspectRatio { min: 2.3, max: 2.4 },
+            ]),
+            TagRule::new("蓝色 PNG".to_string(), "blue".to_string(), vec![
+                RuleCondition::DominantColor { color: ColorName::Blue },
+                RuleCondition::Format { formats: vec!["png".to_string()] },
+            ]),
+        ];
+        let engine: TagRuleEngine = TagRuleEngine::new(&rules);
+        assert_eq!(engine.len(), 5);
+        
+        let blue: ColorPalette = ColorPalette {
+            colors: vec![PaletteColor { rgb: [20, 60, 200], weight: 1.0 }],
+            brightness: 0.5,
+        };
+        let anime: Wallpaper = create_test_wallpaper("/home/user/Pictures/Anime/sky.png", (3840, 2160))
+            .with_colors(blue.clone());
+        assert_eq!(tags(&engine, &anime), vec!["anime", "4k", "blue"]);
+        assert_eq!(engine.evaluate(&anime)[0].rule_id, rules[0].id);
+        
+        let phone: Wallpaper = create_test_wallpaper("/home/user/Pictures/anime.jpg", (1080, 1920)).with_colors(blue);
+        assert_eq!(tags(&engine, &phone), vec!["portrait"]);
+        
+        let ultrawide: Wallpaper = create_test_wallpaper("/walls/wide.jpeg", (3440, 1440));
+        assert_eq!(tags(&engine, &ultrawide), vec!["ultrawide"]);
+    }
+
+    #[test]
+    fn test_invalid_rules_are_skipped() {
+        let mut disabled: TagRule = TagRule::new("停用".to_string(), "x".to_string(), vec![
+            RuleCondition::Format { formats: vec!["jpg".to_string()] },
+        ]);
+        disabled.enabled = false;
+        let rules: Vec<TagRule> = vec![
+            TagRule::new("坏模式".to_string(), "bad".to_string(), vec![
+                RuleCondition::PathPattern { pattern: "**/[anime".to_string() },
+            ]),
+            TagRule::new("无条件".to_string(), "all".to_string(), Vec::new()),
+            disabled,
+        ];
+        
+        assert!(TagRuleEngine::validate(&rules[0]).is_err());
+        assert!(TagRuleEngine::validate(&rules[1]).is_err());
+        assert!(TagRuleEngine::validate(&rules[2]).is_ok());
+        assert!(TagRuleEngine::new(&rules).is_empty());
+    }
+}
\ No newline at end of file
@@ -1,24 +1,73 @@
+use std::collections::HashSet;
+use crate::components::Query;
+use crate::components::grouping::{group_key, GridSection, GroupBy, GroupKey};
+use crate::components::sort::{SortKey, SortOrder, SortSpec};
 use crate::models::Wallpaper;
 
 /// 壁纸网格组件，处理壁纸的网格布局逻辑
 pub struct WallpaperGrid {
     wallpapers: Vec<Wallpaper>,
     columns: usize,
+    /// 焦点位置（键盘光标 / 主选中项）
     selected_index: Option<usize>,
+    /// 范围选择的锚点（Shift 扩展选择的起点）
+    anchor_index: Option<usize>,
+    /// 已选中壁纸的 ID 集合，排序或刷新后依然有效
+    selected_ids: HashSet<String>,
+    /// 框选开始前的选中状态，用于在拖动过程中反复计算框选结果
+    rubber_band_base: Option<HashSet<String>>,
+    group_by: GroupBy,
+    /// 分组列表，不分组时为空
+    sections: Vec<GridSection>,
+    /// 已折叠分组的标识，重新分组或刷新后依然有效
+    collapsed_keys: HashSet<String>,
+    /// 按显示顺序排列的网格行，壁纸、列数、分组或折叠状态变化时重新计算
+    layout_rows: Vec<GridRow>,
+    /// 每张壁纸的 (行, 列) 位置，所在分组被折叠时为 `None`
+    positions: Vec<Option<(usize, usize)>>,
+}
+
+/// 网格中的一行：分组标题或一行壁纸
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridRow {
+    /// 分组标题，值为分组下标
+    Header(usize),
+    /// 一行壁纸，`start..end` 为壁纸下标范围
+    Items { section: usize, start: usize, end: usize },
 }
 
 impl WallpaperGrid {
     pub fn new(columns: usize) -> Self {
         Self {
             wallpapers: Vec::new(),
-            columns,
+            columns: columns.max(1),
             selected_index: None,
+            anchor_index: None,
+            selected_ids: HashSet::new(),
+            rubber_band_base: None,
+            group_by: GroupBy::None,
+            sections: Vec::new(),
+            collapsed_keys: HashSet::new(),
+            layout_rows: Vec::new(),
+            positions: Vec::new(),
         }
     }
     
+    /// 设置壁纸列表，仍然存在的壁纸会保留选中状态
     pub fn set_wallpapers(&mut self, wallpapers: Vec<Wallpaper>) {
+        let focus_id: Option<String> = self.id_at(self.selected_index);
+        let anchor_id: Option<String> = self.id_at(self.anchor_index);
+        
         self.wallpapers = wallpapers;
-        self.selected_index = None;
+        
+        let existing_ids: HashSet<&str> = self.wallpapers
+            .iter()
+            .map(|wallpaper| wallpaper.id.as_str())
+            .collect();
+        self.selected_ids.retain(|id| existing_ids.contains(id.as_str()));
+        self.rubber_band_base = None;
+        self.rebuild_sections();
+        self.restore_cursor(focus_id, anchor_id);
     }
     
     pub fn get_wallpapers(&self) -> &[Wallpaper] {
@@ -27,29 +76,197 @@ impl WallpaperGrid {
     
     pub fn set_columns(&mut self, columns: usize) {
         self.columns = columns.max(1);
+        self.rebuild_layout();
     }
     
     pub fn get_columns(&self) -> usize {
         self.columns
     }
     
+    /// 网格的总行数，分组时包括分组标题行
     pub fn get_rows(&self) -> usize {
-        if self.wallpapers.is_empty() {
-            0
+        self.layout_rows.len()
+    }
+    
+    /// 设置分组方式，壁纸按分组重新排列，分组内保持原有顺序
+    pub fn set_group_by(&mut self, group_by: GroupBy) {
+        self.group_by = group_by;
+        self.reorder(|_| {});
+    }
+    
+    pub fn get_group_by(&self) -> GroupBy {
+        self.group_by
+    }
+    
+    /// 当前分组列表，不分组时为空
+    pub fn get_sections(&self) -> &[GridSection] {
+        &self.sections
+    }
+    
+    /// 折叠或展开分组，焦点所在的壁纸被折叠时移动到分组后第一张可见的壁纸
+    pub fn set_section_collapsed(&mut self, key: &str, collapsed: bool) {
+        if collapsed {
+            self.collapsed_keys.insert(key.to_string());
         } else {
-            (self.wallpapers.len() + self.columns - 1) / self.columns
+            self.collapsed_keys.remove(key);
+        }
+        
+        for section in self.sections.iter_mut().filter(|section| section.key == key) {
+            section.collapsed = collapsed;
+        }
+        self.rebuild_layout();
+        self.move_focus_to_visible();
+    }
+    
+    pub fn toggle_section(&mut self, key: &str) {
+        let collapsed: bool = self.collapsed_keys.contains(key);
+        self.set_section_collapsed(key, !collapsed);
+    }
+    
+    /// 按显示顺序返回网格的每一行，供界面绘制分组标题和壁纸
+    pub fn get_layout_rows(&self) -> &[GridRow] {
+        &self.layout_rows
+    }
+    
+    /// 壁纸在网格中的 (行, 列) 位置，壁纸所在分组被折叠时返回 `None`
+    pub fn get_position(&self, index: usize) -> Option<(usize, usize)> {
+        self.positions.get(index).copied().flatten()
+    }
+    
+    /// 网格 (行, 列) 位置上的壁纸下标，标题行和空位返回 `None`
+    pub fn get_index_at(&self, row: usize, column: usize) -> Option<usize> {
+        match self.layout_rows.get(row)? {
+            GridRow::Items { start, end, .. } if start + column < *end => Some(start + column),
+            _ => None,
         }
     }
     
+    /// 单选指定壁纸（普通点击），替换当前选择
     pub fn select_wallpaper(&mut self, index: usize) -> Option<&Wallpaper> {
         if index < self.wallpapers.len() {
+            self.selected_ids.clear();
+            self.selected_ids.insert(self.wallpapers[index].id.clone());
             self.selected_index = Some(index);
+            self.anchor_index = Some(index);
             Some(&self.wallpapers[index])
         } else {
             None
         }
     }
     
+    /// 切换指定壁纸的选中状态（Ctrl+点击）
+    pub fn toggle_selection(&mut self, index: usize) -> Option<&Wallpaper> {
+        let id: String = self.wallpapers.get(index)?.id.clone();
+        
+        if !self.selected_ids.remove(&id) {
+            self.selected_ids.insert(id);
+        }
+        self.selected_index = Some(index);
+        self.anchor_index = Some(index);
+        
+        Some(&self.wallpapers[index])
+    }
+    
+    /// 从锚点扩展选择到指定位置（Shift+点击）
+    pub fn extend_selection_to(&mut self, index: usize) -> Option<&Wallpaper> {
+        if index >= self.wallpapers.len() {
+            return None;
+        }
+        
+        let anchor: usize = self.anchor_index.unwrap_or(index);
+        let (start, end) = if anchor <= index { (anchor, index) } else { (index, anchor) };
+        
+        // 折叠分组中的壁纸不在范围选择内
+        self.selected_ids = (start..=end)
+            .filter(|index| self.is_visible(*index))
+            .map(|index| self.wallpapers[index].id.clone())
+            .collect();
+        self.selected_index = Some(index);
+        self.anchor_index = Some(anchor);
+        
+        Some(&self.wallpapers[index])
+    }
+    
+    /// 按方向移动焦点并扩展选择（Shift+方向键）
+    pub fn extend_selection(&mut self, direction: Direction) -> Option<&Wallpaper> {
+        if self.wallpapers.is_empty() {
+            return None;
+        }
+        
+        let current_index: usize = self.focus_or_first_visible()?;
+        let target_index: usize = self.neighbor_index(current_index, direction)
+            .unwrap_or(current_index);
+        
+        if self.anchor_index.is_none() {
+            self.anchor_index = Some(current_index);
+        }
+        self.extend_selection_to(target_index)
+    }
+    
+    /// 选中全部壁纸
+    pub fn select_all(&mut self) {
+        self.selected_ids = self.wallpapers
+            .iter()
+            .map(|wallpaper| wallpaper.id.clone())
+            .collect();
+        
+        if self.selected_index.is_none() && !self.wallpapers.is_empty() {
+            self.selected_index = Some(0);
+            self.anchor_index = Some(0);
+        }
+    }
+    
+    /// 反转选择
+    pub fn invert_selection(&mut self) {
+        self.selected_ids = self.wallpapers
+            .iter()
+            .filter(|wallpaper| !self.selected_ids.contains(&wallpaper.id))
+            .map(|wallpaper| wallpaper.id.clone())
+            .collect();
+    }
+    
+    /// 开始框选，`additive` 为真时（按住 Ctrl）框选结果会叠加到现有选择上
+    pub fn begin_rubber_band(&mut self, additive: bool) {
+        let base: HashSet<String> = if additive {
+            self.selected_ids.clone()
+        } else {
+            HashSet::new()
+        };
+        self.rubber_band_base = Some(base);
+    }
+    
+    /// 更新框选区域，`start` 和 `end` 为对角的 (行, 列) 网格坐标
+    pub fn update_rubber_band(&mut self, start: (usize, usize), end: (usize, usize)) {
+        let mut selected: HashSet<String> = self.rubber_band_base
+            .clone()
+            .unwrap_or_default();
+        
+        let (first_row, last_row) = (start.0.min(end.0), start.0.max(end.0));
+        let (first_column, last_column) = (start.1.min(end.1), start.1.max(end.1));
+        let last_column: usize = last_column.min(self.columns - 1);
+        
+        let mut last_index: Option<usize> = None;
+        for row in self.layout_rows.iter().take(last_row + 1).skip(first_row) {
+            if let GridRow::Items { start, end, .. } = *row {
+                for index in (start + first_column..=start + last_column).filter(|index| *index < end) {
+                    selected.insert(self.wallpapers[index].id.clone());
+                    last_index = Some(index);
+                }
+            }
+        }
+        
+        self.selected_ids = selected;
+        if last_index.is_some() {
+            self.selected_index = last_index;
+            self.anchor_index = last_index;
+        }
+    }
+    
+    /// 结束框选
+    pub fn end_rubber_band(&mut self) {
+        self.rubber_band_base = None;
+    }
+    
     pub fn get_selected_wallpaper(&self) -> Option<&Wallpaper> {
         self.selected_index
             .and_then(|index| self.wallpapers.get(index))
@@ -59,8 +276,38 @@ impl WallpaperGrid {
         self.selected_index
     }
     
+    /// 按网格顺序返回所有选中的壁纸
+    pub fn get_selected_wallpapers(&self) -> Vec<&Wallpaper> {
+        self.wallpapers
+            .iter()
+            .filter(|wallpaper| self.selected_ids.contains(&wallpaper.id))
+            .collect()
+    }
+    
+    /// 按网格顺序返回所有选中壁纸的 ID，供批量操作使用
+    pub fn get_selected_ids(&self) -> Vec<String> {
+        self.get_selected_wallpapers()
+            .into_iter()
+            .map(|wallpaper| wallpaper.id.clone())
+            .collect()
+    }
+    
+    pub fn get_selection_count(&self) -> usize {
+        self.selected_ids.len()
+    }
+    
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.wallpapers
+            .get(index)
+            .map(|wallpaper| self.selected_ids.contains(&wallpaper.id))
+            .unwrap_or(false)
+    }
+    
     pub fn clear_selection(&mut self) {
         self.selected_index = None;
+        self.anchor_index = None;
+        self.selected_ids.clear();
+        self.rubber_band_base = None;
     }
     
     pub fn move_selection(&mut self, direction: Direction) -> Option<&Wallpaper> {
@@ -68,82 +315,199 @@ impl WallpaperGrid {
             return None;
         }
         
-        let current_index: usize = self.selected_index.unwrap_or(0);
-        let new_index: Option<usize> = match direction {
-            Direction::Up => {
-                if current_index >= self.columns {
-                    Some(current_index - self.columns)
-                } else {
-                    None
-                }
-            }
-            Direction::Down => {
-                let new_idx: usize = current_index + self.columns;
-                if new_idx < self.wallpapers.len() {
-                    Some(new_idx)
-                } else {
-                    None
-                }
-            }
-            Direction::Left => {
-                if current_index > 0 {
-                    Some(current_index - 1)
-                } else {
-                    None
-                }
-            }
-            Direction::Right => {
-                let new_idx: usize = current_index + 1;
-                if new_idx < self.wallpapers.len() {
-                    Some(new_idx)
-                } else {
-                    None
-                }
-            }
-        };
+        let current_index: usize = self.focus_or_first_visible()?;
         
-        if let Some(index) = new_index {
+        if let Some(index) = self.neighbor_index(current_index, direction) {
             self.select_wallpaper(index)
         } else {
             self.get_selected_wallpaper()
         }
     }
     
-    pub fn filter_by_aspect_ratio(&self, min_ratio: f32, max_ratio: f32) -> Vec<&Wallpaper> {
-        self.wallpapers
-            .iter()
-            .filter(|wallpaper| {
-                let ratio: f32 = wallpaper.size.0 as f32 / wallpaper.size.1 as f32;
-                ratio >= min_ratio && ratio <= max_ratio
-            })
-            .collect()
+    /// 按方向查找相邻的壁纸，跳过分组标题和折叠的分组
+    ///
+    /// 上下移动时，同一分组内目标位置为空则不移动；跨越分组时列号超出目标行则移动到该行末尾
+    fn neighbor_index(&self, current_index: usize, direction: Direction) -> Option<usize> {
+        match direction {
+            Direction::Left => (0..current_index).rev().find(|index| self.is_visible(*index)),
+            Direction::Right => (current_index + 1..self.wallpapers.len()).find(|index| self.is_visible(*index)),
+            Direction::Up | Direction::Down => {
+                let rows: &[GridRow] = &self.layout_rows;
+                let (row, column) = self.get_position(current_index)?;
+                let current_section: usize = match rows[row] {
+                    GridRow::Items { section, .. } => section,
+                    GridRow::Header(section) => section,
+                };
+                
+                let target: Option<&GridRow> = if matches!(direction, Direction::Up) {
+                    rows[..row].iter().rev().find(|row| matches!(row, GridRow::Items { .. }))
+                } else {
+                    rows[row + 1..].iter().find(|row| matches!(row, GridRow::Items { .. }))
+                };
+                
+                match *target? {
+                    GridRow::Items { section, start, end } if section == current_section => {
+                        Some(start + column).filter(|index| *index < end)
+                    }
+                    GridRow::Items { start, end, .. } => Some((start + column).min(end - 1)),
+                    GridRow::Header(_) => None,
+                }
+            }
+        }
+    }
+    
+    /// 壁纸是否可见（所在分组未折叠）
+    fn is_visible(&self, index: usize) -> bool {
+        self.get_position(index).is_some()
+    }
+    
+    /// 焦点所在的壁纸不可见时移动到其后第一张可见的壁纸，后面没有时向前查找
+    fn move_focus_to_visible(&mut self) {
+        if let Some(index) = self.selected_index.filter(|index| !self.is_visible(*index)) {
+            self.selected_index = (index..self.wallpapers.len())
+                .chain((0..index).rev())
+                .find(|index| self.is_visible(*index));
+            self.anchor_index = self.selected_index;
+        }
+    }
+    
+    fn focus_or_first_visible(&self) -> Option<usize> {
+        self.selected_index
+            .filter(|index| self.is_visible(*index))
+            .or_else(|| (0..self.wallpapers.len()).find(|index| self.is_visible(*index)))
+    }
+    
+    /// 按查询条件筛选网格中的壁纸
+    pub fn filter(&self, query: &Query) -> Vec<&Wallpaper> {
+        query.filter(&self.wallpapers)
+    }
+    
+    /// 按多字段排序规则进行稳定排序，焦点和选中状态保持不变
+    pub fn sort_by(&mut self, spec: &SortSpec) {
+        self.reorder(|wallpapers| spec.sort(wallpapers));
     }
     
+    /// 按像素面积排序
     pub fn sort_by_size(&mut self, ascending: bool) {
-        self.wallpapers.sort_by(|a, b| {
-            let size_a: u64 = a.size.0 as u64 * a.size.1 as u64;
-            let size_b: u64 = b.size.0 as u64 * b.size.1 as u64;
-            
-            if ascending {
-                size_a.cmp(&size_b)
-            } else {
-                size_b.cmp(&size_a)
-            }
-        });
-        
-        self.selected_index = None;
+        self.sort_by(&SortSpec::by(SortKey::PixelArea, sort_order(ascending)));
     }
     
+    /// 按有效分辨率排序，放大而来的壁纸按放大前的分辨率计算
+    pub fn sort_by_effective_resolution(&mut self, ascending: bool) {
+        self.sort_by(&SortSpec::by(SortKey::EffectiveResolution, sort_order(ascending)));
+    }
+    
+    /// 按文件名自然排序
     pub fn sort_by_name(&mut self, ascending: bool) {
-        self.wallpapers.sort_by(|a, b| {
-            if ascending {
-                a.filename.cmp(&b.filename)
-            } else {
-                b.filename.cmp(&a.filename)
+        self.sort_by(&SortSpec::by(SortKey::Name, sort_order(ascending)));
+    }
+    
+    /// 重新排列壁纸，并按 ID 恢复焦点和锚点位置
+    fn reorder<F>(&mut self, reorder: F)
+    where
+        F: FnOnce(&mut Vec<Wallpaper>),
+    {
+        let focus_id: Option<String> = self.id_at(self.selected_index);
+        let anchor_id: Option<String> = self.id_at(self.anchor_index);
+        
+        reorder(&mut self.wallpapers);
+        self.rebuild_sections();
+        
+        self.restore_cursor(focus_id, anchor_id);
+    }
+    
+    /// 按分组稳定地重新排列壁纸并重新计算分组和布局
+    fn rebuild_sections(&mut self) {
+        self.sections.clear();
+        if self.group_by != GroupBy::None {
+            self.group_wallpapers();
+        }
+        self.rebuild_layout();
+    }
+    
+    fn group_wallpapers(&mut self) {
+        let group_by: GroupBy = self.group_by;
+        let mut keyed: Vec<(GroupKey, Wallpaper)> = self.wallpapers
+            .drain(..)
+            .map(|wallpaper| (group_key(&wallpaper, group_by), wallpaper))
+            .collect();
+        keyed.sort_by(|a, b| a.0.cmp_order(&b.0));
+        
+        for (index, (key, wallpaper)) in keyed.into_iter().enumerate() {
+            match self.sections.last_mut() {
+                Some(section) if section.key == key.key => section.count += 1,
+                _ => self.sections.push(GridSection {
+                    collapsed: self.collapsed_keys.contains(&key.key),
+                    key: key.key,
+                    title: key.title,
+                    start: index,
+                    count: 1,
+                }),
             }
-        });
+            self.wallpapers.push(wallpaper);
+        }
+    }
+    
+    /// 重新计算网格行和每张壁纸的位置
+    fn rebuild_layout(&mut self) {
+        let mut rows: Vec<GridRow> = Vec::new();
+        let columns: usize = self.columns;
+        let push_items = |rows: &mut Vec<GridRow>, section: usize, start: usize, end: usize| {
+            for row_start in (start..end).step_by(columns) {
+                rows.push(GridRow::Items {
+                    section,
+                    start: row_start,
+                    end: (row_start + columns).min(end),
+                });
+            }
+        };
         
-        self.selected_index = None;
+        if self.sections.is_empty() {
+            push_items(&mut rows, 0, 0, self.wallpapers.len());
+        }
+        for (index, section) in self.sections.iter().enumerate() {
+            rows.push(GridRow::Header(index));
+            if !section.collapsed {
+                push_items(&mut rows, index, section.start, section.start + section.count);
+            }
+        }
+        
+        self.positions = vec![None; self.wallpapers.len()];
+        for (row, grid_row) in rows.iter().enumerate() {
+            if let GridRow::Items { start, end, .. } = *grid_row {
+                for index in start..end {
+                    self.positions[index] = Some((row, index - start));
+                }
+            }
+        }
+        self.layout_rows = rows;
+    }
+    
+    fn id_at(&self, index: Option<usize>) -> Option<String> {
+        index
+            .and_then(|index| self.wallpapers.get(index))
+            .map(|wallpaper| wallpaper.id.clone())
+    }
+    
+    fn index_of(&self, id: &str) -> Option<usize> {
+        self.wallpapers.iter().position(|wallpaper| wallpaper.id == id)
+    }
+    
+    fn restore_cursor(&mut self, focus_id: Option<String>, anchor_id: Option<String>) {
+        self.selected_index = focus_id.and_then(|id| self.index_of(&id));
+        self.anchor_index = anchor_id
+            .and_then(|id| self.index_of(&id))
+            .or(self.selected_index);
+        // 刷新后焦点所在的壁纸可能被归入已折叠的分组
+        self.move_focus_to_visible();
+    }
+}
+
+fn sort_order(ascending: bool) -> SortOrder {
+    if ascending {
+        SortOrder::Ascending
+    } else {
+        SortOrder::Descending
     }
 }
 
@@ -158,21 +522,23 @@ pub enum Direction {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::ImageQuality;
 
     fn create_test_wallpaper(id: &str, filename: &str) -> Wallpaper {
         Wallpaper {
             id: id.to_string(),
-            path: std::path::PathBuf::from(filename),
-            filename: filename.to_string(),
-            size: (1920, 1080),
-            file_size: 1024,
-            format: "jpg".to_string(),
-            thumbnail_path: None,
-            created_at: chrono::Utc::now(),
-            modified_at: chrono::Utc::now(),
-            tags: Vec::new(),
+            ..Wallpaper::for_test(filename)
         }
     }
+    
+    fn create_test_grid(count: usize, columns: usize) -> WallpaperGrid {
+        let mut grid: WallpaperGrid = WallpaperGrid::new(columns);
+        let wallpapers: Vec<Wallpaper> = (0..count)
+            .map(|i| create_test_wallpaper(&i.to_string(), &format!("test{}.jpg", i)))
+            .collect();
+        grid.set_wallpapers(wallpapers);
+        grid
+    }
 
     #[test]
     fn test_wallpaper_grid_basic() {
@@ -208,4 +574,256 @@ mod tests {
         assert!(grid.select_wallpaper(5).is_none());
         assert_eq!(grid.get_selected_index(), Some(0));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_toggle_and_range_selection() {
+        let mut grid: WallpaperGrid = create_test_grid(6, 3);
+        
+        grid.select_wallpaper(1);
+        grid.toggle_selection(4);
+        assert_eq!(grid.get_selected_ids(), vec!["1", "4"]);
+        
+        grid.toggle_selection(1);
+        assert_eq!(grid.get_selected_ids(), vec!["4"]);
+        
+        // Shift+点击从最近一次点击的位置开始扩展
+        grid.extend_selection_to(3);
+        assert_eq!(grid.get_selected_ids(), vec!["1", "2", "3"]);
+        assert_eq!(grid.get_selected_index(), Some(3));
+        
+        grid.extend_selection(Direction::Up);
+        assert_eq!(grid.get_selected_ids(), vec!["0", "1"]);
+        assert_eq!(grid.get_selected_index(), Some(0));
+        
+        grid.select_wallpaper(0);
+        grid.extend_selection(Direction::Down);
+        assert_eq!(grid.get_selected_ids(), vec!["0", "1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_select_all_and_invert() {
+        let mut grid: WallpaperGrid = create_test_grid(4, 2);
+        
+        grid.select_all();
+        assert_eq!(grid.get_selection_count(), 4);
+        
+        grid.select_wallpaper(1);
+        grid.invert_selection();
+        assert_eq!(grid.get_selected_ids(), vec!["0", "2", "3"]);
+        
+        grid.clear_selection();
+        assert_eq!(grid.get_selection_count(), 0);
+        assert_eq!(grid.get_selected_index(), None);
+    }
+
+    #[test]
+    fn test_rubber_band_selection() {
+        let mut grid: WallpaperGrid = create_test_grid(7, 3);
+        
+        grid.select_wallpaper(6);
+        grid.begin_rubber_band(true);
+        grid.update_rubber_band((0, 2), (0, 1));
+        assert_eq!(grid.get_selected_ids(), vec!["1", "2", "6"]);
+        
+        // 拖动过程中区域缩小，之前框入的项应被移除
+        grid.update_rubber_band((0, 2), (1, 2));
+        assert_eq!(grid.get_selected_ids(), vec!["2", "5", "6"]);
+        grid.end_rubber_band();
+        
+        grid.begin_rubber_band(false);
+        grid.update_rubber_band((1, 0), (5, 5));
+        grid.end_rubber_band();
+        assert_eq!(grid.get_selected_ids(), vec!["3", "4", "5", "6"]);
+        
+        // 列数为 0 时按 1 列处理
+        let mut grid: WallpaperGrid = create_test_grid(3, 0);
+        assert_eq!(grid.get_columns(), 1);
+        grid.begin_rubber_band(false);
+        grid.update_rubber_band((0, 0), (1, 3));
+        assert_eq!(grid.get_selected_ids(), vec!["0", "1"]);
+    }
+
+    #[test]
+    fn test_selection_preserved_across_sort() {
+        let mut grid: WallpaperGrid = create_test_grid(5, 2);
+        
+        grid.select_wallpaper(1);
+        grid.toggle_selection(3);
+        grid.sort_by_name(false);
+        
+        assert_eq!(grid.get_selected_ids(), vec!["3", "1"]);
+        assert_eq!(grid.get_selected_wallpaper().map(|w| w.id.as_str()), Some("3"));
+        assert_eq!(grid.get_selected_index(), Some(1));
+        
+        // 重新设置列表后，被移除的壁纸不再处于选中状态
+        let remaining: Vec<Wallpaper> = grid.get_wallpapers()
+            .iter()
+            .filter(|wallpaper| wallpaper.id != "3")
+            .cloned()
+            .collect();
+        grid.set_wallpapers(remaining);
+        assert_eq!(grid.get_selected_ids(), vec!["1"]);
+        assert_eq!(grid.get_selected_index(), None);
+    }
+
+    #[test]
+    fn test_multi_key_sort() {
+        let mut grid: WallpaperGrid = WallpaperGrid::new(3);
+        let mut wallpapers: Vec<Wallpaper> = vec![
+            create_test_wallpaper("a", "img10.jpg"),
+            create_test_wallpaper("b", "img2.jpg"),
+            create_test_wallpaper("c", "img1.jpg"),
+            create_test_wallpaper("d", "photo.jpg"),
+        ];
+        wallpapers[0].rating = 5;
+        wallpapers[1].rating = 3;
+        wallpapers[2].rating = 3;
+        wallpapers[3].file_size = 10;
+        grid.set_wallpapers(wallpapers);
+        grid.select_wallpaper(2);
+        
+        grid.sort_by_name(true);
+        let order: Vec<&str> = grid.get_wallpapers().iter().map(|w| w.filename.as_str()).collect();
+        assert_eq!(order, vec!["img1.jpg", "img2.jpg", "img10.jpg", "photo.jpg"]);
+        
+        let spec: SortSpec = SortSpec::by(SortKey::Rating, SortOrder::Descending)
+            .then(SortKey::Name, SortOrder::Descending);
+        grid.sort_by(&spec);
+        let order: Vec<&str> = grid.get_wallpapers().iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(order, vec!["a", "b", "c", "d"]);
+        assert_eq!(grid.get_selected_ids(), vec!["c"]);
+        assert_eq!(grid.get_selected_index(), Some(2));
+        
+        // 相同种子得到相同顺序，与原有顺序无关
+        grid.sort_by(&SortSpec::by(SortKey::Random { seed: 3 }, SortOrder::Ascending));
+        let shuffled: Vec<String> = grid.get_wallpapers().iter().map(|w| w.id.clone()).collect();
+        grid.sort_by(&SortSpec::by(SortKey::FileSize, SortOrder::Ascending));
+        grid.sort_by(&SortSpec::by(SortKey::Random { seed: 3 }, SortOrder::Ascending));
+        let again: Vec<String> = grid.get_wallpapers().iter().map(|w| w.id.clone()).collect();
+        assert_eq!(shuffled, again);
+    }
+
+    #[test]
+    fn test_sort_by_quality() {
+        let mut grid: WallpaperGrid = WallpaperGrid::new(4);
+        let mut wallpapers: Vec<Wallpaper> = vec![
+            create_test_wallpaper("native", "native.jpg"),
+            create_test_wallpaper("upscaled", "upscaled.jpg"),
+            create_test_wallpaper("unknown", "unknown.jpg"),
+        ];
+        wallpapers[0].quality = Some(ImageQuality {
+            sharpness: 300.0,
+            jpeg_quality: Some(90),
+            blockiness: 0.0,
+            effective_size: (1920, 1080),
+            score: 70,
+        });
+        wallpapers[1].size = (3840, 2160);
+        wallpapers[1].quality = Some(ImageQuality {
+            sharpness: 10.0,
+            jpeg_quality: Some(60),
+            blockiness: 0.3,
+            effective_size: (1280, 720),
+            score: 30,
+        });
+        grid.set_wallpapers(wallpapers);
+        
+        // 4K 的放大图片按 720p 计算，排在 1080p 之后
+        grid.sort_by_effective_resolution(false);
+        let order: Vec<&str> = grid.get_wallpapers().iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(order, vec!["native", "unknown", "upscaled"]);
+        
+        grid.sort_by(&SortSpec::by(SortKey::Quality, SortOrder::Descending));
+        let order: Vec<&str> = grid.get_wallpapers().iter().map(|w| w.id.as_str()).collect();
+        assert_eq!(order, vec!["native", "upscaled", "unknown"]);
+    }
+    
+    
+    fn create_grouped_grid() -> WallpaperGrid {
+        // jpg 分组 5 张，png 分组 2 张，webp 分组 4 张
+        let wallpapers: Vec<Wallpaper> = (0..11)
+            .map(|i| {
+                let extension: &str = match i {
+                    0..=4 => "jpg",
+                    5..=6 => "png",
+                    _ => "webp",
+                };
+                let mut wallpaper: Wallpaper = create_test_wallpaper(&i.to_string(), &format!("test{}.{}", i, extension));
+                wallpaper.format = extension.to_string();
+                wallpaper
+            })
+            .rev()
+            .collect();
+        
+        let mut grid: WallpaperGrid = WallpaperGrid::new(3);
+        grid.set_wallpapers(wallpapers);
+        grid.sort_by_name(true);
+        grid.set_group_by(GroupBy::Format);
+        grid
+    }
+
+    #[test]
+    fn test_grouped_layout() {
+        let grid: WallpaperGrid = create_grouped_grid();
+        
+        let sections: Vec<(&str, usize, usize)> = grid.get_sections()
+            .iter()
+            .map(|section| (section.title.as_str(), section.start, section.count))
+            .collect();
+        assert_eq!(sections, vec![("JPG", 0, 5), ("PNG", 5, 2), ("WEBP", 7, 4)]);
+        
+        // 标题 + 2 行、标题 + 1 行、标题 + 2 行
+        assert_eq!(grid.get_rows(), 8);
+        assert_eq!(grid.get_layout_rows()[3], GridRow::Header(1));
+        assert_eq!(grid.get_position(4), Some((2, 1)));
+        assert_eq!(grid.get_position(7), Some((6, 0)));
+        assert_eq!(grid.get_index_at(4, 1), Some(6));
+        assert_eq!(grid.get_index_at(3, 0), None);
+        assert_eq!(grid.get_index_at(4, 2), None);
+    }
+
+    #[test]
+    fn test_grouped_navigation() {
+        let mut grid: WallpaperGrid = create_grouped_grid();
+        
+        // 同一分组内下方为空位时不移动
+        grid.select_wallpaper(2);
+        assert_eq!(grid.move_selection(Direction::Down).unwrap().id, "2");
+        
+        // 跨越分组时移动到目标行中列号最接近的位置
+        grid.select_wallpaper(4);
+        assert_eq!(grid.move_selection(Direction::Down).unwrap().id, "6");
+        grid.select_wallpaper(6);
+        assert_eq!(grid.move_selection(Direction::Down).unwrap().id, "8");
+        assert_eq!(grid.move_selection(Direction::Up).unwrap().id, "6");
+        assert_eq!(grid.move_selection(Direction::Up).unwrap().id, "4");
+        
+        // 折叠的分组被跳过，焦点所在分组被折叠时焦点移出
+        grid.set_section_collapsed("png", true);
+        assert_eq!(grid.get_rows(), 7);
+        grid.select_wallpaper(4);
+        assert_eq!(grid.move_selection(Direction::Right).unwrap().id, "7");
+        assert_eq!(grid.move_selection(Direction::Up).unwrap().id, "3");
+        
+        grid.toggle_section("png");
+        grid.select_wallpaper(5);
+        grid.toggle_section("png");
+        assert_eq!(grid.get_selected_wallpaper().unwrap().id, "7");
+        
+        // 折叠状态在刷新后保留
+        let wallpapers: Vec<Wallpaper> = grid.get_wallpapers().to_vec();
+        grid.set_wallpapers(wallpapers);
+        assert!(grid.get_sections()[1].collapsed);
+        
+        grid.extend_selection_to(0);
+        assert_eq!(grid.get_selection_count(), 6);
+        
+        // 刷新后焦点所在的壁纸被归入折叠的分组时，焦点移出
+        grid.select_wallpaper(4);
+        let mut wallpapers: Vec<Wallpaper> = grid.get_wallpapers().to_vec();
+        wallpapers[4].format = "png".to_string();
+        grid.set_wallpapers(wallpapers);
+        assert!(grid.is_visible(grid.get_selected_index().unwrap()));
+        assert_eq!(grid.get_selected_wallpaper().unwrap().id, "7");
+    }
+}
\ No newline at end of file
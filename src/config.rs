@@ -1,14 +1,97 @@
 use std::path::{Path, PathBuf};
+use image::ImageFormat;
 use serde::{Deserialize, Serialize};
 use crate::{Result, WallpaperError};
+use crate::models::{SmartCollection, TagRule};
+use crate::utils::{ExtraFormat, enabled_extra_extensions};
+
+/// 使用 freedesktop.org 共享缩略图缓存（`~/.cache/thumbnails`）的方式
+///
+/// 该缓存是 Linux 桌面的约定，其他平台默认不使用
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SharedThumbnailMode {
+    #[serde(alias = "disabled")]
+    Off,
+    /// 只读取 Nautilus、Dolphin 等文件管理器已生成的缩略图
+    ReadOnly,
+    /// 同时写入符合规范的缩略图，供文件管理器复用
+    ReadWrite,
+}
+
+impl Default for SharedThumbnailMode {
+    fn default() -> Self {
+        if cfg!(target_os = "linux") {
+            Self::ReadOnly
+        } else {
+            Self::Off
+        }
+    }
+}
+
+/// 缩略图输出格式，图片含有透明区域而所选格式不支持透明时改用 PNG
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ThumbnailFormat {
+    /// 质量为 1-100
+    Jpeg { quality: u8 },
+    Png,
+    /// 有损 WebP，质量为 1-100，保留透明通道
+    Webp { quality: u8 },
+    WebpLossless,
+}
+
+impl Default for ThumbnailFormat {
+    fn default() -> Self {
+        Self::Jpeg { quality: 85 }
+    }
+}
+
+impl ThumbnailFormat {
+    pub fn get_image_format(&self) -> ImageFormat {
+        match self {
+            Self::Jpeg { .. } => ImageFormat::Jpeg,
+            Self::Png => ImageFormat::Png,
+            Self::Webp { .. } | Self::WebpLossless => ImageFormat::WebP,
+        }
+    }
+    
+    pub fn supports_alpha(&self) -> bool {
+        !matches!(self, Self::Jpeg { .. })
+    }
+    
+    /// 参与缩略图缓存键的标识，质量变化时也会重新生成
+    pub fn get_key(&self) -> String {
+        match self {
+            Self::Jpeg { quality } => format!("jpg-q{}", quality),
+            Self::Png => "png".to_string(),
+            Self::Webp { quality } => format!("webp-q{}", quality),
+            Self::WebpLossless => "webp-lossless".to_string(),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Config {
     pub wallpaper_directories: Vec<PathBuf>,
     pub supported_formats: Vec<String>,
+    /// 已合并到 `supported_formats` 的可选格式扩展名，用户之后删除的扩展名不会被重新加入
+    ///
+    /// 旧配置文件没有该字段，按空列表处理，使其中缺少的已启用格式在加载时被加入
+    #[serde(default)]
+    pub known_extra_formats: Vec<String>,
     pub thumbnail_size: (u32, u32),
+    pub thumbnail_format: ThumbnailFormat,
     pub cache_directory: PathBuf,
+    /// 收藏夹、标签等用户数据的存放目录
+    pub data_directory: PathBuf,
     pub max_cache_size_mb: u64,
+    /// 是否与文件管理器共用 freedesktop.org 缩略图缓存
+    pub shared_thumbnails: SharedThumbnailMode,
+    pub smart_collections: Vec<SmartCollection>,
+    /// 扫描时自动添加标签的规则
+    pub tag_rules: Vec<TagRule>,
 }
 
 impl Default for Config {
@@ -24,12 +107,23 @@ impl Default for Config {
                 "bmp".to_string(),
                 "gif".to_string(),
                 "webp".to_string(),
-            ],
+            ]
+            .into_iter()
+            .chain(enabled_extra_extensions().into_iter().map(String::from))
+            .collect(),
+            known_extra_formats: enabled_extra_extensions().into_iter().map(String::from).collect(),
             thumbnail_size: (200, 150),
+            thumbnail_format: ThumbnailFormat::default(),
             cache_directory: dirs::cache_dir()
                 .unwrap_or_else(|| PathBuf::from("."))
                 .join("wallpaper-explorer"),
+            data_directory: dirs::data_dir()
+                .unwrap_or_else(|| PathBuf::from("."))
+                .join("wallpaper-explorer"),
             max_cache_size_mb: 500,
+            shared_thumbnails: SharedThumbnailMode::default(),
+            smart_collections: Vec::new(),
+            tag_rules: Vec::new(),
         }
     }
 }
@@ -40,8 +134,11 @@ impl Config {
         
         if config_path.exists() {
             let content: String = std::fs::read_to_string(&config_path)?;
-            let config: Config = toml::from_str(&content)
+            let mut config: Config = toml::from_str(&content)
                 .map_err(|e| WallpaperError::Config(format!("解析配置文件失败: {}", e)))?;
+            if config.merge_extra_formats() {
+                config.save()?;
+            }
             Ok(config)
         } else {
             let config: Config = Self::default();
@@ -71,13 +168,31 @@ impl Config {
         Ok(config_dir.join("wallpaper-explorer").join("config.toml"))
     }
     
-    pub fn is_supported_format(&self, path: &Path) -> bool {
-        if let Some(extension) = path.extension() {
-            if let Some(ext_str) = extension.to_str() {
-                return self.supported_formats.iter()
-                    .any(|format| format.eq_ignore_ascii_case(ext_str));
+    /// 将编译时启用、但从未合并过的可选格式扩展名加入 `supported_formats`，返回是否有修改
+    ///
+    /// 旧配置文件由此获得后来启用的格式；已记录在 `known_extra_formats` 中的扩展名即使被用户删除也不会再次加入
+    pub fn merge_extra_formats(&mut self) -> bool {
+        let mut changed: bool = false;
+        for extension in enabled_extra_extensions() {
+            if self.known_extra_formats.iter().any(|known| known.eq_ignore_ascii_case(extension)) {
+                continue;
             }
+            if !self.supported_formats.iter().any(|format| format.eq_ignore_ascii_case(extension)) {
+                self.supported_formats.push(extension.to_string());
+            }
+            self.known_extra_formats.push(extension.to_string());
+            changed = true;
         }
-        false
+        changed
+    }
+    
+    /// 扩展名需要在 `supported_formats` 中，可选格式还需要编译时启用对应的 feature；
+    /// 加载配置时通过 [`Config::merge_extra_formats`] 加入新启用的可选格式
+    pub fn is_supported_format(&self, path: &Path) -> bool {
+        let Some(extension) = path.extension().and_then(|extension| extension.to_str()) else {
+            return false;
+        };
+        self.supported_formats.iter().any(|format| format.eq_ignore_ascii_case(extension))
+            && ExtraFormat::from_extension(extension).is_none_or(ExtraFormat::is_enabled)
     }
 } 
\ No newline at end of file
@@ -1,4 +1,5 @@
 use std::fmt;
+use crate::components::QueryParseError;
 
 pub type Result<T> = std::result::Result<T, WallpaperError>;
 
@@ -9,6 +10,7 @@ pub enum WallpaperError {
     Slint(slint::PlatformError),
     Config(String),
     Service(String),
+    Query(QueryParseError),
 }
 
 impl fmt::Display for WallpaperError {
@@ -19,6 +21,7 @@ impl fmt::Display for WallpaperError {
             WallpaperError::Slint(err) => write!(f, "UI错误: {}", err),
             WallpaperError::Config(msg) => write!(f, "配置错误: {}", msg),
             WallpaperError::Service(msg) => write!(f, "服务错误: {}", msg),
+            WallpaperError::Query(err) => write!(f, "查询语法错误: {}", err),
         }
     }
 }
@@ -41,4 +44,10 @@ impl From<slint::PlatformError> for WallpaperError {
     fn from(err: slint::PlatformError) -> Self {
         WallpaperError::Slint(err)
     }
-} 
\ No newline at end of file
+}
+
+impl From<QueryParseError> for WallpaperError {
+    fn from(err: QueryParseError) -> Self {
+        WallpaperError::Query(err)
+    }
+}
\ No newline at end of file
@@ -1,6 +1,7 @@
 #![allow(non_snake_case)]
 
 pub mod app;
+pub mod cli;
 pub mod components;
 pub mod config;
 pub mod error;
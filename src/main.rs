@@ -1,9 +1,16 @@
 use Wallpaper_Explorer::App;
+use Wallpaper_Explorer::cli::CacheCommand;
 
 fn main() -> Wallpaper_Explorer::Result<()> {
     // 初始化日志记录
     env_logger::init();
     
+    // 缓存维护命令直接运行，不启动界面
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = CacheCommand::parse(&args)? {
+        return command.run();
+    }
+    
     // 创建并运行应用程序
     let app: App = App::new()?;
     app.run()?;
@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use crate::utils::generate_unique_id;
+
+/// 手动整理的相册，按用户指定的顺序保存壁纸ID
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Album {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// 封面壁纸ID，未设置时使用相册中的第一张
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover_id: Option<String>,
+    #[serde(default)]
+    pub wallpaper_ids: Vec<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Album {
+    pub fn new(name: String, description: String) -> Self {
+        let now: chrono::DateTime<chrono::Utc> = chrono::Utc::now();
+        
+        Self {
+            id: generate_unique_id(),
+            name,
+            description,
+            cover_id: None,
+            wallpaper_ids: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+    
+    /// 相册封面对应的壁纸ID
+    pub fn get_cover_id(&self) -> Option<&str> {
+        self.cover_id
+            .as_deref()
+            .or_else(|| self.wallpaper_ids.first().map(|id| id.as_str()))
+    }
+    
+    pub fn contains(&self, wallpaper_id: &str) -> bool {
+        self.wallpaper_ids.iter().any(|id| id == wallpaper_id)
+    }
+    
+    pub fn len(&self) -> usize {
+        self.wallpaper_ids.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.wallpaper_ids.is_empty()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,16 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+/// 动图信息，扫描时从 GIF/WebP 文件结构中读取，不解码帧
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnimationInfo {
+    pub frame_count: u32,
+    /// 播放一遍的总时长（毫秒）
+    pub duration_ms: u64,
+}
+
+impl AnimationInfo {
+    pub fn get_duration(&self) -> Duration {
+        Duration::from_millis(self.duration_ms)
+    }
+}
\ No newline at end of file
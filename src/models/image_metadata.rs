@@ -0,0 +1,93 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+/// GPS 坐标，单位为度，南纬和西经为负数
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct GpsCoordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// 海拔（米），低于海平面为负数
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<f64>,
+}
+
+/// 从 EXIF 和 XMP 中读取的图片元数据
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ImageMetadata {
+    pub camera_make: Option<String>,
+    pub camera_model: Option<String>,
+    pub lens: Option<String>,
+    /// 拍摄时间（相机本地时间，EXIF 通常不含时区）
+    pub captured_at: Option<NaiveDateTime>,
+    pub gps: Option<GpsCoordinates>,
+    pub artist: Option<String>,
+    pub copyright: Option<String>,
+    /// XMP 中的关键词（`dc:subject` 以及 Lightroom 的层级关键词）
+    pub keywords: Vec<String>,
+    /// EXIF 方向值 1-8
+    pub orientation: Option<u16>,
+}
+
+impl ImageMetadata {
+    /// 相机名称，型号中已包含厂商名时不再重复，例如 `Canon EOS R5`
+    pub fn get_camera(&self) -> Option<String> {
+        match (&self.camera_make, &self.camera_model) {
+            (Some(make), Some(model)) => {
+                let brand: &str = make.split_whitespace().next().unwrap_or(make);
+                if model.to_lowercase().starts_with(&brand.to_lowercase()) {
+                    Some(model.clone())
+                } else {
+                    Some(format!("{} {}", make, model))
+                }
+            }
+            (Some(make), None) => Some(make.clone()),
+            (None, Some(model)) => Some(model.clone()),
+            (None, None) => None,
+        }
+    }
+    
+    /// 作者与版权信息，用于在使用图片前提示版权归属
+    pub fn get_attribution(&self) -> Option<String> {
+        match (&self.artist, &self.copyright) {
+            (Some(artist), Some(copyright)) if copyright.contains(artist.as_str()) => Some(copyright.clone()),
+            (Some(artist), Some(copyright)) => Some(format!("{} ({})", artist, copyright)),
+            (Some(artist), None) => Some(artist.clone()),
+            (None, Some(copyright)) => Some(copyright.clone()),
+            (None, None) => None,
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camera_and_attribution() {
+        let metadata: ImageMetadata = ImageMetadata {
+            camera_make: Some("Canon".to_string()),
+            camera_model: Some("Canon EOS R5".to_string()),
+            artist: Some("Jane Doe".to_string()),
+            copyright: Some("© 2023 Jane Doe".to_string()),
+            ..ImageMetadata::default()
+        };
+        assert_eq!(metadata.get_camera().as_deref(), Some("Canon EOS R5"));
+        assert_eq!(metadata.get_attribution().as_deref(), Some("© 2023 Jane Doe"));
+        
+        let metadata: ImageMetadata = ImageMetadata {
+            camera_make: Some("NIKON CORPORATION".to_string()),
+            camera_model: Some("Z 6".to_string()),
+            artist: Some("Jane Doe".to_string()),
+            copyright: Some("CC BY 4.0".to_string()),
+            ..ImageMetadata::default()
+        };
+        assert_eq!(metadata.get_camera().as_deref(), Some("NIKON CORPORATION Z 6"));
+        assert_eq!(metadata.get_attribution().as_deref(), Some("Jane Doe (CC BY 4.0)"));
+        assert!(ImageMetadata::default().is_empty());
+    }
+}
\ No newline at end of file
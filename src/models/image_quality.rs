@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+/// 有效分辨率低于实际分辨率的该比例时视为放大图片
+pub const UPSCALE_THRESHOLD: f32 = 0.8;
+
+/// 图像质量指标，扫描后在后台由原图计算
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ImageQuality {
+    /// 清晰度：灰度图拉普拉斯响应的方差，越大越清晰
+    pub sharpness: f32,
+    /// 由量化表估计的 JPEG 压缩质量（1-100），其他格式为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jpeg_quality: Option<u8>,
+    /// 块效应强度（0-1），由 8×8 块边界处与其他位置的梯度差异计算，0 表示没有块效应
+    pub blockiness: f32,
+    /// 由频谱估计的放大前分辨率，未检测到放大时与图片尺寸相同
+    pub effective_size: (u32, u32),
+    /// 综合质量分，0-100
+    pub score: u8,
+}
+
+impl ImageQuality {
+    pub fn get_effective_pixels(&self) -> u64 {
+        self.effective_size.0 as u64 * self.effective_size.1 as u64
+    }
+    
+    /// 相对实际尺寸 `size` 是否为放大图片
+    pub fn is_upscaled(&self, size: (u32, u32)) -> bool {
+        (self.effective_size.0 as f32) < size.0 as f32 * UPSCALE_THRESHOLD
+    }
+}
\ No newline at end of file
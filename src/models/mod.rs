@@ -1,3 +1,21 @@
+pub mod album;
+pub mod animation;
+pub mod image_metadata;
+pub mod image_quality;
+pub mod smart_collection;
+pub mod tag;
+pub mod tag_rule;
+pub mod usage;
 pub mod wallpaper;
+pub mod wallpaper_source;
 
-pub use wallpaper::Wallpaper; 
\ No newline at end of file
+pub use album::Album;
+pub use animation::AnimationInfo;
+pub use image_metadata::ImageMetadata;
+pub use image_quality::ImageQuality;
+pub use smart_collection::SmartCollection;
+pub use tag::{TagCount, TagDefinition};
+pub use tag_rule::{RuleCondition, TagRule};
+pub use usage::{UsageEntry, UsageStats};
+pub use wallpaper::Wallpaper;
+pub use wallpaper_source::WallpaperSource;
\ No newline at end of file
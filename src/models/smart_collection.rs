@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use crate::utils::generate_unique_id;
+
+/// 智能收藏：保存下来的查询，壁纸库变化时自动重新计算成员
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SmartCollection {
+    pub id: String,
+    pub name: String,
+    /// 查询语句，语法与搜索栏相同，例如 `tag:nature width>=3840`
+    pub query: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl SmartCollection {
+    pub fn new(name: String, query: String) -> Self {
+        Self {
+            id: generate_unique_id(),
+            name,
+            query,
+            created_at: chrono::Utc::now(),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+/// 层级分隔符，例如 `nature/mountains`
+pub const TAG_SEPARATOR: char = '/';
+
+/// 标签定义：颜色、别名等附加信息
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TagDefinition {
+    /// 规范化后的完整标签名
+    pub name: String,
+    /// 标签颜色，格式为 `#rrggbb`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// 别名和同义词（规范化后），搜索和添加标签时会解析为本标签
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl TagDefinition {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            color: None,
+            aliases: Vec::new(),
+        }
+    }
+}
+
+/// 标签统计，用于标签云和侧边栏
+#[derive(Debug, Clone, PartialEq)]
+pub struct TagCount {
+    pub tag: String,
+    /// 直接带有该标签的壁纸数量
+    pub count: usize,
+    /// 带有该标签或其子标签的壁纸数量
+    pub total: usize,
+    pub color: Option<String>,
+}
+
+/// 规范化标签：去除首尾空白、合并连续空白、转为小写，并清理层级分隔符两侧的空白和空层级
+///
+/// 规范化后为空时返回 `None`
+pub fn normalize_tag(raw: &str) -> Option<String> {
+    let segments: Vec<String> = raw
+        .split(TAG_SEPARATOR)
+        .map(|segment| {
+            segment
+                .split_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ")
+                .to_lowercase()
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect();
+    
+    if segments.is_empty() {
+        None
+    } else {
+        Some(segments.join(&TAG_SEPARATOR.to_string()))
+    }
+}
+
+/// 判断 `tag` 是否为 `ancestor` 本身或其子标签
+pub fn is_tag_or_descendant(tag: &str, ancestor: &str) -> bool {
+    tag == ancestor
+        || (tag.len() > ancestor.len()
+            && tag.starts_with(ancestor)
+            && tag[ancestor.len()..].starts_with(TAG_SEPARATOR))
+}
+
+/// 返回标签自身及其所有上级标签，例如 `a/b/c` 返回 `a`、`a/b`、`a/b/c`
+pub fn tag_with_ancestors(tag: &str) -> Vec<&str> {
+    tag.char_indices()
+        .filter(|(_, ch)| *ch == TAG_SEPARATOR)
+        .map(|(index, _)| &tag[..index])
+        .chain(std::iter::once(tag))
+        .collect()
+}
+
+/// 检查颜色是否为 `#rrggbb` 格式
+pub fn is_valid_tag_color(color: &str) -> bool {
+    color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(normalize_tag("  Nature / Mountains "), Some("nature/mountains".to_string()));
+        assert_eq!(normalize_tag("Deep   Space//Nebula/"), Some("deep space/nebula".to_string()));
+        assert_eq!(normalize_tag("风景/山"), Some("风景/山".to_string()));
+        assert_eq!(normalize_tag(" / "), None);
+    }
+
+    #[test]
+    fn test_tag_hierarchy() {
+        assert!(is_tag_or_descendant("nature/mountains", "nature"));
+        assert!(is_tag_or_descendant("nature", "nature"));
+        assert!(!is_tag_or_descendant("naturetown", "nature"));
+        assert_eq!(tag_with_ancestors("a/b/c"), vec!["a", "a/b", "a/b/c"]);
+    }
+
+    #[test]
+    fn test_tag_color() {
+        assert!(is_valid_tag_color("#33aaFF"));
+        assert!(!is_valid_tag_color("33aaff"));
+        assert!(!is_valid_tag_color("#33aaf"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use crate::utils::{generate_unique_id, ColorName};
+
+/// 图片方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    Landscape,
+    Portrait,
+    Square,
+}
+
+/// 自动标签规则的条件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleCondition {
+    /// 路径通配符，例如 `**/anime/**`，不区分大小写
+    PathPattern { pattern: String },
+    /// 最低分辨率，例如宽度不小于 3840 视为 4K
+    Resolution {
+        #[serde(default)]
+        min_width: u32,
+        #[serde(default)]
+        min_height: u32,
+    },
+    Orientation { orientation: Orientation },
+    /// 宽高比区间（闭区间）
+    AspectRatio { min: f32, max: f32 },
+    /// 主色调，由缩略图计算
+    DominantColor { color: ColorName },
+    /// 文件格式，`jpg` 与 `jpeg` 视为同一格式
+    Format { formats: Vec<String> },
+}
+
+/// 自动标签规则：所有条件都满足时为壁纸添加标签
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TagRule {
+    pub id: String,
+    pub name: String,
+    pub tag: String,
+    pub conditions: Vec<RuleCondition>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl TagRule {
+    pub fn new(name: String, tag: String, conditions: Vec<RuleCondition>) -> Self {
+        Self {
+            id: generate_unique_id(),
+            name,
+            tag,
+            conditions,
+            enabled: true,
+        }
+    }
+}
\ No newline at end of file
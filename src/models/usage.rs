@@ -0,0 +1,58 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 最高星级，0 表示未评分
+pub const MAX_RATING: u8 = 5;
+
+/// 一次设置壁纸的记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UsageEntry {
+    pub wallpaper_id: String,
+    pub applied_at: DateTime<Utc>,
+    /// 被替换或停止使用的时间，当前正在使用的壁纸为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+impl UsageEntry {
+    pub fn new(wallpaper_id: String, applied_at: DateTime<Utc>) -> Self {
+        Self {
+            wallpaper_id,
+            applied_at,
+            ended_at: None,
+        }
+    }
+    
+    /// 使用时长，仍在使用的记录计算到 `now`
+    pub fn get_duration(&self, now: DateTime<Utc>) -> Duration {
+        (self.ended_at.unwrap_or(now) - self.applied_at).max(Duration::zero())
+    }
+}
+
+/// 单张壁纸的使用统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageStats {
+    pub wallpaper_id: String,
+    pub apply_count: usize,
+    pub total_duration: Duration,
+    pub last_applied: Option<DateTime<Utc>>,
+}
+
+impl UsageStats {
+    pub fn new(wallpaper_id: String) -> Self {
+        Self {
+            wallpaper_id,
+            apply_count: 0,
+            total_duration: Duration::zero(),
+            last_applied: None,
+        }
+    }
+    
+    pub fn add_entry(&mut self, entry: &UsageEntry, now: DateTime<Utc>) {
+        self.apply_count += 1;
+        self.total_duration += entry.get_duration(now);
+        if self.last_applied.is_none_or(|last| entry.applied_at > last) {
+            self.last_applied = Some(entry.applied_at);
+        }
+    }
+}
\ No newline at end of file
@@ -1,5 +1,7 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
+use crate::models::{AnimationInfo, ImageMetadata, ImageQuality};
+use crate::utils::{ColorPalette, RAW_EXTENSIONS, stable_hash};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Wallpaper {
@@ -13,6 +15,29 @@ pub struct Wallpaper {
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub modified_at: chrono::DateTime<chrono::Utc>,
     pub tags: Vec<String>,
+    /// 调色板与亮度，由缩略图计算
+    #[serde(default)]
+    pub colors: Option<ColorPalette>,
+    /// EXIF/XMP 元数据
+    #[serde(default)]
+    pub metadata: ImageMetadata,
+    /// 星级 0-5，0 表示未评分
+    #[serde(default)]
+    pub rating: u8,
+    #[serde(default)]
+    pub favorite: bool,
+    /// 动图的帧数和时长，静态图片为 `None`
+    #[serde(default)]
+    pub animation: Option<AnimationInfo>,
+    /// RAW 与同目录同名的 JPEG 归为一组，指向组内另一个文件的 ID
+    #[serde(default)]
+    pub sibling_id: Option<String>,
+    /// 缩略图加载前显示的模糊占位图（BlurHash）
+    #[serde(default)]
+    pub blurhash: Option<String>,
+    /// 清晰度、压缩质量和放大检测结果，后台分析完成前以及动图、RAW 和 SVG 为 `None`
+    #[serde(default)]
+    pub quality: Option<ImageQuality>,
 }
 
 impl Wallpaper {
@@ -28,11 +53,7 @@ impl Wallpaper {
             .unwrap_or("unknown")
             .to_lowercase();
         
-        // 使用文件路径生成唯一ID
-        use std::hash::{Hash, Hasher};
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        path.hash(&mut hasher);
-        let id: String = format!("{:x}", hasher.finish());
+        let id: String = Self::generate_id(&path);
         
         let created_at: chrono::DateTime<chrono::Utc> = metadata.created()
             .unwrap_or(std::time::SystemTime::now())
@@ -53,9 +74,42 @@ impl Wallpaper {
             created_at,
             modified_at,
             tags: Vec::new(),
+            colors: None,
+            metadata: ImageMetadata::default(),
+            rating: 0,
+            favorite: false,
+            animation: None,
+            sibling_id: None,
+            blurhash: None,
+            quality: None,
         })
     }
     
+    /// 使用文件路径生成唯一ID
+    ///
+    /// ID 会持久化到标签、使用记录和相册中，因此使用跨版本稳定的哈希
+    pub fn generate_id(path: &Path) -> String {
+        format!("{:x}", stable_hash(path.as_os_str().as_encoded_bytes()))
+    }
+    
+    /// 旧版本基于 `DefaultHasher` 生成的ID，仅用于迁移已保存的数据
+    pub(crate) fn generate_legacy_id(path: &Path) -> String {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        path.hash(&mut hasher);
+        format!("{:x}", hasher.finish())
+    }
+    
+    /// 文件被移动或重命名后更新路径相关的字段
+    pub fn relocate(&mut self, path: PathBuf) {
+        self.filename = path.file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+        self.id = Self::generate_id(&path);
+        self.path = path;
+    }
+    
     pub fn with_dimensions(mut self, width: u32, height: u32) -> Self {
         self.size = (width, height);
         self
@@ -66,6 +120,50 @@ impl Wallpaper {
         self
     }
     
+    pub fn with_colors(mut self, colors: ColorPalette) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+    
+    pub fn with_metadata(mut self, metadata: ImageMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+    
+    pub fn with_blurhash(mut self, blurhash: String) -> Self {
+        self.blurhash = Some(blurhash);
+        self
+    }
+    
+    pub fn with_quality(mut self, quality: ImageQuality) -> Self {
+        self.quality = Some(quality);
+        self
+    }
+    
+    /// 有效分辨率，没有质量指标时为图片尺寸
+    pub fn get_effective_size(&self) -> (u32, u32) {
+        self.quality.map_or(self.size, |quality| quality.effective_size)
+    }
+    
+    /// 是否为由较低分辨率放大而来的图片
+    pub fn is_upscaled(&self) -> bool {
+        self.quality.is_some_and(|quality| quality.is_upscaled(self.size))
+    }
+    
+    pub fn with_animation(mut self, animation: AnimationInfo) -> Self {
+        self.animation = Some(animation);
+        self
+    }
+    
+    pub fn is_animated(&self) -> bool {
+        self.animation.is_some()
+    }
+    
+    /// 是否为相机 RAW 文件
+    pub fn is_raw(&self) -> bool {
+        RAW_EXTENSIONS.contains(&self.format.as_str())
+    }
+    
     pub fn add_tag(&mut self, tag: String) {
         if !self.tags.contains(&tag) {
             self.tags.push(tag);
@@ -75,4 +173,32 @@ impl Wallpaper {
     pub fn remove_tag(&mut self, tag: &str) {
         self.tags.retain(|t| t != tag);
     }
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+impl Wallpaper {
+    /// 测试用的壁纸：ID 为路径，格式取自扩展名，其余字段为固定的默认值
+    pub(crate) fn for_test(path: &str) -> Self {
+        let path: PathBuf = PathBuf::from(path);
+        Self {
+            id: path.to_string_lossy().to_string(),
+            filename: path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+            format: path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default(),
+            path,
+            size: (1920, 1080),
+            file_size: 1024,
+            thumbnail_path: None,
+            created_at: chrono::Utc::now(),
+            modified_at: chrono::Utc::now(),
+            tags: Vec::new(),
+            colors: None,
+            metadata: ImageMetadata::default(),
+            rating: 0,
+            favorite: false,
+            animation: None,
+            sibling_id: None,
+            blurhash: None,
+            quality: None,
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+/// 壁纸来源，用于轮换、导出等需要一组壁纸的功能
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum WallpaperSource {
+    /// 整个壁纸库
+    #[default]
+    All,
+    /// 指定的壁纸 ID 列表，例如网格中的当前选择
+    Selection(Vec<String>),
+    /// 智能收藏的 ID
+    SmartCollection(String),
+    /// 相册的 ID，按相册中的顺序
+    Album(String),
+}
\ No newline at end of file
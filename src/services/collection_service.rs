@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::{Result, WallpaperError};
+use crate::components::WallpaperGrid;
+use crate::config::Config;
+use crate::models::Album;
+use crate::utils::{load_toml_file, save_toml_file};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AlbumStore {
+    #[serde(default)]
+    albums: Vec<Album>,
+}
+
+/// 相册服务，负责相册的增删改查和持久化
+///
+/// 每次修改后立即写入 `albums.toml`
+pub struct CollectionService {
+    store_path: PathBuf,
+    albums: Vec<Album>,
+}
+
+impl CollectionService {
+    pub fn new(config: &Config) -> Result<Self> {
+        let store_path: PathBuf = config.data_directory.join("albums.toml");
+        let store: AlbumStore = load_toml_file(&store_path)?.unwrap_or_default();
+        
+        Ok(Self {
+            store_path,
+            albums: store.albums,
+        })
+    }
+    
+    pub fn get_albums(&self) -> &[Album] {
+        &self.albums
+    }
+    
+    pub fn get_album(&self, album_id: &str) -> Option<&Album> {
+        self.albums.iter().find(|album| album.id == album_id)
+    }
+    
+    /// 返回包含指定壁纸的所有相册
+    pub fn get_albums_containing(&self, wallpaper_id: &str) -> Vec<&Album> {
+        self.albums
+            .iter()
+            .filter(|album| album.contains(wallpaper_id))
+            .collect()
+    }
+    
+    pub fn create_album(&mut self, name: &str, description: &str) -> Result<&Album> {
+        let album: Album = Album::new(name.to_string(), description.to_string());
+        self.albums.push(album);
+        self.save()?;
+        
+        Ok(self.albums.last().unwrap())
+    }
+    
+    pub fn rename_album(&mut self, album_id: &str, name: &str) -> Result<()> {
+        self.update_album(album_id, |album| {
+            album.name = name.to_string();
+            Ok(())
+        })
+    }
+    
+    pub fn set_description(&mut self, album_id: &str, description: &str) -> Result<()> {
+        self.update_album(album_id, |album| {
+            album.description = description.to_string();
+            Ok(())
+        })
+    }
+    
+    /// 设置相册封面，封面必须是相册中的壁纸；传入 `None` 恢复为第一张
+    pub fn set_cover(&mut self, album_id: &str, wallpaper_id: Option<&str>) -> Result<()> {
+        self.update_album(album_id, |album| {
+            if let Some(id) = wallpaper_id {
+                if !album.contains(id) {
+                    return Err(WallpaperError::Service(format!("壁纸 {} 不在相册 {} 中", id, album.name)));
+                }
+            }
+            album.cover_id = wallpaper_id.map(|id| id.to_string());
+            Ok(())
+        })
+    }
+    
+    pub fn delete_album(&mut self, album_id: &str) -> Result<()> {
+        let before: usize = self.albums.len();
+        self.albums.retain(|album| album.id != album_id);
+        
+        if self.albums.len() == before {
+            return Err(WallpaperError::Service(format!("找不到相册: {}", album_id)));
+        }
+        
+        self.save()
+    }
+    
+    /// 将壁纸追加到相册末尾，已在相册中的壁纸会被跳过，返回实际添加的数量
+    pub fn add_wallpapers(&mut self, album_id: &str, wallpaper_ids: &[String]) -> Result<usize> {
+        let mut added: usize = 0;
+        
+        self.update_album(album_id, |album| {
+            for id in wallpaper_ids {
+                if !album.contains(id) {
+                    album.wallpaper_ids.push(id.clone());
+                    added += 1;
+                }
+            }
+            Ok(())
+        })?;
+        
+        Ok(added)
+    }
+    
+    /// 将网格中当前选中的壁纸添加到相册
+    pub fn add_selection(&mut self, album_id: &str, grid: &WallpaperGrid) -> Result<usize> {
+        self.add_wallpapers(album_id, &grid.get_selected_ids())
+    }
+    
+    pub fn remove_wallpapers(&mut self, album_id: &str, wallpaper_ids: &[String]) -> Result<()> {
+        self.update_album(album_id, |album| {
+            album.wallpaper_ids.retain(|id| !wallpaper_ids.contains(id));
+            if album.cover_id.as_ref().is_some_and(|cover| wallpaper_ids.contains(cover)) {
+                album.cover_id = None;
+            }
+            Ok(())
+        })
+    }
+    
+    /// 拖动排序：将一组壁纸移动到 `target_index` 处（按移动前的位置计算），保持它们原有的相对顺序
+    pub fn move_wallpapers(&mut self, album_id: &str, wallpaper_ids: &[String], target_index: usize) -> Result<()> {
+        self.update_album(album_id, |album| {
+            let target_index: usize = target_index.min(album.wallpaper_ids.len());
+            // 目标位置之前被移走的项会让插入位置前移
+            let shift: usize = album.wallpaper_ids[..target_index]
+                .iter()
+                .filter(|id| wallpaper_ids.contains(id))
+                .count();
+            
+            let (moving, mut remaining): (Vec<String>, Vec<String>) = album.wallpaper_ids
+                .drain(..)
+                .partition(|id| wallpaper_ids.contains(id));
+            
+            let insert_at: usize = target_index - shift;
+            remaining.splice(insert_at..insert_at, moving);
+            album.wallpaper_ids = remaining;
+            Ok(())
+        })
+    }
+    
+    /// 从所有相册中移除已删除的壁纸
+    pub fn remove_from_all(&mut self, wallpaper_ids: &[String]) -> Result<()> {
+        for album in &mut self.albums {
+            album.wallpaper_ids.retain(|id| !wallpaper_ids.contains(id));
+            if album.cover_id.as_ref().is_some_and(|cover| wallpaper_ids.contains(cover)) {
+                album.cover_id = None;
+            }
+        }
+        self.save()
+    }
+    
+    /// 壁纸移动或重命名后更新相册中的ID，`renamed` 为旧ID到新ID的映射
+    pub fn replace_ids(&mut self, renamed: &HashMap<String, String>) -> Result<()> {
+        if renamed.is_empty() {
+            return Ok(());
+        }
+        
+        for album in &mut self.albums {
+            for id in album.wallpaper_ids.iter_mut().chain(album.cover_id.iter_mut()) {
+                if let Some(new_id) = renamed.get(id) {
+                    *id = new_id.clone();
+                }
+            }
+        }
+        self.save()
+    }
+    
+    pub fn save(&self) -> Result<()> {
+        let store: AlbumStore = AlbumStore {
+            albums: self.albums.clone(),
+        };
+        save_toml_file(&self.store_path, &store)
+    }
+    
+    fn update_album<F>(&mut self, album_id: &str, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut Album) -> Result<()>,
+    {
+        let album: &mut Album = self.albums
+            .iter_mut()
+            .find(|album| album.id == album_id)
+            .ok_or_else(|| WallpaperError::Service(format!("找不到相册: {}", album_id)))?;
+        
+        update(album)?;
+        album.updated_at = chrono::Utc::now();
+        self.save()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_config(name: &str) -> Config {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-albums-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        
+        Config {
+            data_directory: directory,
+            ..Config::default()
+        }
+    }
+    
+    fn ids(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_album_crud_and_persistence() {
+        let config: Config = create_test_config("crud");
+        let mut service: CollectionService = CollectionService::new(&config).unwrap();
+        
+        let travel: String = service.create_album("旅行", "2023 年的照片").unwrap().id.clone();
+        let favorites: String = service.create_album("精选", "").unwrap().id.clone();
+        
+        assert_eq!(service.add_wallpapers(&travel, &ids(&["a", "b", "c"])).unwrap(), 3);
+        assert_eq!(service.add_wallpapers(&travel, &ids(&["b", "d"])).unwrap(), 1);
+        service.add_wallpapers(&favorites, &ids(&["b"])).unwrap();
+        
+        assert_eq!(service.get_album(&travel).unwrap().get_cover_id(), Some("a"));
+        service.set_cover(&travel, Some("c")).unwrap();
+        assert!(service.set_cover(&travel, Some("x")).is_err());
+        service.rename_album(&favorites, "最爱").unwrap();
+        
+        let containing: Vec<&str> = service.get_albums_containing("b")
+            .into_iter()
+            .map(|album| album.name.as_str())
+            .collect();
+        assert_eq!(containing, vec!["旅行", "最爱"]);
+        
+        // 重新加载后数据保持不变
+        let reloaded: CollectionService = CollectionService::new(&config).unwrap();
+        assert_eq!(reloaded.get_albums(), service.get_albums());
+        
+        service.remove_from_all(&ids(&["c"])).unwrap();
+        assert_eq!(service.get_album(&travel).unwrap().get_cover_id(), Some("a"));
+        
+        service.delete_album(&favorites).unwrap();
+        assert!(service.delete_album(&favorites).is_err());
+        assert_eq!(service.get_albums().len(), 1);
+        
+        let _ = std::fs::remove_dir_all(&config.data_directory);
+    }
+
+    #[test]
+    fn test_drag_to_reorder() {
+        let config: Config = create_test_config("reorder");
+        let mut service: CollectionService = CollectionService::new(&config).unwrap();
+        let album: String = service.create_album("排序", "").unwrap().id.clone();
+        service.add_wallpapers(&album, &ids(&["a", "b", "c", "d", "e"])).unwrap();
+        
+        service.move_wallpapers(&album, &ids(&["a"]), 3).unwrap();
+        assert_eq!(service.get_album(&album).unwrap().wallpaper_ids, ids(&["b", "c", "a", "d", "e"]));
+        
+        service.move_wallpapers(&album, &ids(&["d", "b"]), 0).unwrap();
+        assert_eq!(service.get_album(&album).unwrap().wallpaper_ids, ids(&["b", "d", "c", "a", "e"]));
+        
+        service.move_wallpapers(&album, &ids(&["b"]), 99).unwrap();
+        assert_eq!(service.get_album(&album).unwrap().wallpaper_ids, ids(&["d", "c", "a", "e", "b"]));
+        
+        let renamed: HashMap<String, String> = HashMap::from([("a".to_string(), "z".to_string())]);
+        service.replace_ids(&renamed).unwrap();
+        assert_eq!(service.get_album(&album).unwrap().wallpaper_ids, ids(&["d", "c", "z", "e", "b"]));
+        
+        let _ = std::fs::remove_dir_all(&config.data_directory);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,345 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::Result;
+use crate::config::Config;
+use crate::models::ImageQuality;
+use crate::utils::{load_toml_file, save_toml_file};
+
+/// 当前的壁纸ID生成方式，低于此版本的索引在下次扫描时迁移
+const ID_VERSION: u32 = 1;
+
+/// 上次扫描时网格中的一项，启动后在扫描完成前据此布局占位图
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub id: String,
+    pub path: PathBuf,
+    /// 原图尺寸，用于按宽高比布局
+    pub size: (u32, u32),
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LibraryStore {
+    /// 生成壁纸ID的方式，0 表示旧版本的 `DefaultHasher`
+    #[serde(default)]
+    id_version: u32,
+    /// 壁纸ID到文件指纹的映射
+    #[serde(default)]
+    fingerprints: BTreeMap<String, String>,
+    /// 壁纸ID到 BlurHash 占位图的映射
+    #[serde(default)]
+    placeholders: BTreeMap<String, String>,
+    /// 网格中显示的壁纸，按扫描顺序排列
+    #[serde(default)]
+    listing: Vec<LibraryEntry>,
+    /// 文件指纹到图像质量指标的映射，以指纹为键，文件被重命名或移动后仍可沿用
+    #[serde(default)]
+    quality: BTreeMap<String, ImageQuality>,
+}
+
+/// 壁纸库索引：记录上次扫描时每张壁纸的文件指纹
+///
+/// 壁纸ID由路径生成，文件在程序外被重命名或移动后ID会改变，
+/// 扫描时通过指纹找回旧ID，使标签、评分和使用历史等数据得以保留
+///
+/// 同时保存网格中的壁纸列表和每张壁纸的模糊占位图，启动后在扫描和缩略图加载完成前即可绘制网格；
+/// 以及需要完整解码原图才能计算的图像质量指标，内容未变化的文件无需重新分析
+pub struct LibraryIndex {
+    store_path: PathBuf,
+    id_version: u32,
+    fingerprints: BTreeMap<String, String>,
+    placeholders: BTreeMap<String, String>,
+    listing: Vec<LibraryEntry>,
+    quality: BTreeMap<String, ImageQuality>,
+}
+
+impl LibraryIndex {
+    pub fn new(config: &Config) -> Result<Self> {
+        let store_path: PathBuf = config.data_directory.join("library.toml");
+        let store: LibraryStore = load_toml_file(&store_path)?.unwrap_or_default();
+        
+        Ok(Self {
+            store_path,
+            id_version: store.id_version,
+            fingerprints: store.fingerprints,
+            placeholders: store.placeholders,
+            listing: store.listing,
+            quality: store.quality,
+        })
+    }
+    
+    /// 用本次扫描结果（壁纸ID, 指纹）更新索引，返回检测到的重命名（旧ID到新ID）
+    ///
+    /// 只有当新出现的壁纸与唯一一个消失的壁纸指纹相同时才视为重命名
+    pub fn reconcile(&mut self, scanned: &[(String, String)]) -> Result<HashMap<String, String>> {
+        let current: BTreeMap<String, String> = scanned.iter().cloned().collect();
+        
+        let mut missing: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (id, fingerprint) in &self.fingerprints {
+            if !current.contains_key(id) {
+                missing.entry(fingerprint.as_str()).or_default().push(id.as_str());
+            }
+        }
+        
+        let mut appeared: HashMap<&str, Vec<&str>> = HashMap::new();
+        for (id, fingerprint) in &current {
+            if !self.fingerprints.contains_key(id) {
+                appeared.entry(fingerprint.as_str()).or_default().push(id.as_str());
+            }
+        }
+        
+        let renamed: HashMap<String, String> = appeared
+            .iter()
+            .filter_map(|(fingerprint, new_ids)| match (missing.get(fingerprint)?.as_slice(), new_ids.as_slice()) {
+                ([old_id], [new_id]) => Some((old_id.to_string(), new_id.to_string())),
+                _ => None,
+            })
+            .collect();
+        
+        if current != self.fingerprints {
+            self.fingerprints = current;
+            self.save()?;
+        }
+        Ok(renamed)
+    }
+    
+    /// 已保存的数据是否仍使用旧的壁纸ID
+    pub fn needs_id_migration(&self) -> bool {
+        self.id_version < ID_VERSION
+    }
+    
+    /// 将索引中的旧ID替换为新ID（旧ID到新ID的映射），并记录迁移已完成
+    pub fn migrate_ids(&mut self, migrated: &HashMap<String, String>) -> Result<()> {
+        self.id_version = ID_VERSION;
+        self.replace_ids(migrated)?;
+        self.save()
+    }
+    
+    pub fn get_placeholder(&self, id: &str) -> Option<&str> {
+        self.placeholders.get(id).map(String::as_str)
+    }
+    
+    /// 用本次扫描计算的占位图替换索引中的占位图，有变化时才写入磁盘
+    pub fn update_placeholders(&mut self, placeholders: BTreeMap<String, String>) -> Result<()> {
+        if placeholders == self.placeholders {
+            return Ok(());
+        }
+        self.placeholders = placeholders;
+        self.save()
+    }
+    
+    /// 上次扫描时网格中的壁纸，按显示顺序排列
+    pub fn get_listing(&self) -> &[LibraryEntry] {
+        &self.listing
+    }
+    
+    /// 用当前网格中的壁纸替换索引中的列表，有变化时才写入磁盘
+    pub fn update_listing(&mut self, listing: Vec<LibraryEntry>) -> Result<()> {
+        if listing == self.listing {
+            return Ok(());
+        }
+        self.listing = listing;
+        self.save()
+    }
+    
+    /// 指纹为 `fingerprint` 的文件上次分析得到的质量指标
+    pub fn get_quality(&self, fingerprint: &str) -> Option<&ImageQuality> {
+        self.quality.get(fingerprint)
+    }
+    
+    /// 记录后台分析得到的质量指标
+    pub fn add_quality(&mut self, quality: impl IntoIterator<Item = (String, ImageQuality)>) -> Result<()> {
+        self.quality.extend(quality);
+        self.save()
+    }
+    
+    /// 用本次扫描的质量指标（指纹到指标）替换索引中的记录，有变化时才写入磁盘
+    pub fn update_quality(&mut self, quality: BTreeMap<String, ImageQuality>) -> Result<()> {
+        if quality == self.quality {
+            return Ok(());
+        }
+        self.quality = quality;
+        self.save()
+    }
+    
+    /// 程序内移动壁纸后更新索引
+    pub fn replace_ids(&mut self, renamed: &HashMap<String, String>) -> Result<()> {
+        if renamed.is_empty() {
+            return Ok(());
+        }
+        
+        for (old_id, new_id) in renamed {
+            if let Some(fingerprint) = self.fingerprints.remove(old_id) {
+                self.fingerprints.insert(new_id.clone(), fingerprint);
+            }
+            if let Some(placeholder) = self.placeholders.remove(old_id) {
+                self.placeholders.insert(new_id.clone(), placeholder);
+            }
+        }
+        for entry in &mut self.listing {
+            if let Some(new_id) = renamed.get(&entry.id) {
+                entry.id = new_id.clone();
+            }
+        }
+        self.save()
+    }
+    
+    pub fn remove_wallpapers(&mut self, wallpaper_ids: &[String]) -> Result<()> {
+        for id in wallpaper_ids {
+            self.fingerprints.remove(id);
+            self.placeholders.remove(id);
+        }
+        self.listing.retain(|entry| !wallpaper_ids.contains(&entry.id));
+        self.save()
+    }
+    
+    pub fn save(&self) -> Result<()> {
+        let store: LibraryStore = LibraryStore {
+            id_version: self.id_version,
+            fingerprints: self.fingerprints.clone(),
+            placeholders: self.placeholders.clone(),
+            listing: self.listing.clone(),
+            quality: self.quality.clone(),
+        };
+        save_toml_file(&self.store_path, &store)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(values: &[(&str, &str)]) -> Vec<(String, String)> {
+        values.iter().map(|(id, fingerprint)| (id.to_string(), fingerprint.to_string())).collect()
+    }
+
+    #[test]
+    fn test_detect_renames() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-library-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let config: Config = Config {
+            data_directory: directory.clone(),
+            ..Config::default()
+        };
+        
+        let mut index: LibraryIndex = LibraryIndex::new(&config).unwrap();
+        assert!(index.reconcile(&entries(&[("a", "f1"), ("b", "f2"), ("c", "f3"), ("d", "f3")])).unwrap().is_empty());
+        
+        // a 被重命名为 x；c、d 内容相同，无法判断哪一个被重命名
+        let mut index: LibraryIndex = LibraryIndex::new(&config).unwrap();
+        let renamed: HashMap<String, String> = index
+            .reconcile(&entries(&[("x", "f1"), ("b", "f2"), ("y", "f3")]))
+            .unwrap();
+        assert_eq!(renamed, HashMap::from([("a".to_string(), "x".to_string())]));
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-placeholders-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let config: Config = Config {
+            data_directory: directory.clone(),
+            ..Config::default()
+        };
+        
+        let mut index: LibraryIndex = LibraryIndex::new(&config).unwrap();
+        index.update_placeholders(BTreeMap::from([
+            ("a".to_string(), "LEHV6nWB2yk8pyo0adR*.7kCMdnj".to_string()),
+            ("b".to_string(), "L00000fQfQfQfQfQfQfQfQfQfQfQ".to_string()),
+        ])).unwrap();
+        
+        // 重新打开后无需扫描即可读取占位图
+        let mut index: LibraryIndex = LibraryIndex::new(&config).unwrap();
+        assert_eq!(index.get_placeholder("a"), Some("LEHV6nWB2yk8pyo0adR*.7kCMdnj"));
+        index.replace_ids(&HashMap::from([("a".to_string(), "x".to_string())])).unwrap();
+        index.remove_wallpapers(&["b".to_string()]).unwrap();
+        assert_eq!(index.get_placeholder("a"), None);
+        assert_eq!(index.get_placeholder("b"), None);
+        assert!(index.get_placeholder("x").is_some());
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_listing() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-listing-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let config: Config = Config {
+            data_directory: directory.clone(),
+            ..Config::default()
+        };
+        let entry = |id: &str, size: (u32, u32)| LibraryEntry {
+            id: id.to_string(),
+            path: PathBuf::from(format!("/walls/{}.jpg", id)),
+            size,
+        };
+        
+        let mut index: LibraryIndex = LibraryIndex::new(&config).unwrap();
+        index.update_listing(vec![entry("b", (1920, 1080)), entry("a", (1080, 1920)), entry("c", (3840, 2160))]).unwrap();
+        
+        // 重新打开后保留扫描顺序和尺寸
+        let mut index: LibraryIndex = LibraryIndex::new(&config).unwrap();
+        assert_eq!(index.get_listing(), [entry("b", (1920, 1080)), entry("a", (1080, 1920)), entry("c", (3840, 2160))]);
+        
+        index.replace_ids(&HashMap::from([("a".to_string(), "x".to_string())])).unwrap();
+        index.remove_wallpapers(&["c".to_string()]).unwrap();
+        let ids: Vec<&str> = index.get_listing().iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, ["b", "x"]);
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_quality_cache() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-quality-cache-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let config: Config = Config {
+            data_directory: directory.clone(),
+            ..Config::default()
+        };
+        let quality: ImageQuality = ImageQuality {
+            sharpness: 120.5,
+            jpeg_quality: Some(85),
+            blockiness: 0.1,
+            effective_size: (1280, 720),
+            score: 48,
+        };
+        
+        let mut index: LibraryIndex = LibraryIndex::new(&config).unwrap();
+        index.update_quality(BTreeMap::from([("f1".to_string(), quality)])).unwrap();
+        
+        let index: LibraryIndex = LibraryIndex::new(&config).unwrap();
+        assert_eq!(index.get_quality("f1"), Some(&quality));
+        assert_eq!(index.get_quality("f2"), None);
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_id_migration() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-library-migration-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let config: Config = Config {
+            data_directory: directory.clone(),
+            ..Config::default()
+        };
+        
+        let mut index: LibraryIndex = LibraryIndex::new(&config).unwrap();
+        assert!(index.needs_id_migration());
+        index.reconcile(&entries(&[("old", "f1")])).unwrap();
+        index.migrate_ids(&HashMap::from([("old".to_string(), "new".to_string())])).unwrap();
+        
+        let mut index: LibraryIndex = LibraryIndex::new(&config).unwrap();
+        assert!(!index.needs_id_migration());
+        assert!(index.reconcile(&entries(&[("new", "f1")])).unwrap().is_empty());
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
\ No newline at end of file
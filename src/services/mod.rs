@@ -1,5 +1,21 @@
 pub mod wallpaper_service;
 pub mod thumbnail_service;
+pub mod shared_thumbnail_cache;
+pub mod smart_collection_service;
+pub mod collection_service;
+pub mod tag_service;
+pub mod usage_service;
+pub mod library_index;
+pub mod quality_analysis;
 
-pub use wallpaper_service::WallpaperService;
-pub use thumbnail_service::ThumbnailService; 
\ No newline at end of file
+pub use wallpaper_service::{BatchReport, WallpaperService};
+pub use thumbnail_service::{
+    CacheRebuild, CacheStats, EvictionReport, RebuildProgress, RepairReport, ThumbnailService,
+};
+pub use shared_thumbnail_cache::SharedThumbnailCache;
+pub use smart_collection_service::{SmartCollectionEntry, SmartCollectionService};
+pub use collection_service::CollectionService;
+pub use tag_service::{RuleReport, TagService};
+pub use usage_service::UsageService;
+pub use library_index::{LibraryEntry, LibraryIndex};
+pub use quality_analysis::QualityAnalysis;
\ No newline at end of file
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, mpsc};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use crate::models::ImageQuality;
+use crate::utils::analyze_image_quality;
+
+/// 在后台线程中运行的图像质量分析
+///
+/// 质量分析需要完整解码原图，放在扫描中会使扫描时间成倍增加；
+/// 扫描只应用壁纸库索引中已有的结果，其余文件交给后台分析，结果由 `WallpaperService::poll_quality_analysis` 定时取回
+pub struct QualityAnalysis {
+    /// 文件指纹到使用该文件的壁纸 ID，内容相同的文件只分析一次
+    wallpaper_ids: HashMap<String, Vec<String>>,
+    receiver: mpsc::Receiver<(String, ImageQuality)>,
+    cancelled: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl QualityAnalysis {
+    /// 依次分析 `jobs`（壁纸ID, 原图路径, 文件指纹）
+    pub fn spawn(jobs: Vec<(String, PathBuf, String)>) -> Self {
+        let mut wallpaper_ids: HashMap<String, Vec<String>> = HashMap::new();
+        let mut sources: Vec<(String, PathBuf)> = Vec::new();
+        for (id, path, fingerprint) in jobs {
+            let ids: &mut Vec<String> = wallpaper_ids.entry(fingerprint.clone()).or_default();
+            if ids.is_empty() {
+                sources.push((fingerprint, path));
+            }
+            ids.push(id);
+        }
+        
+        let (sender, receiver) = mpsc::channel::<(String, ImageQuality)>();
+        let cancelled: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let flag: Arc<AtomicBool> = Arc::clone(&cancelled);
+        let handle: JoinHandle<()> = std::thread::spawn(move || {
+            for (fingerprint, path) in sources {
+                if flag.load(Ordering::Relaxed) {
+                    break;
+                }
+                match analyze_image_quality(&path) {
+                    Ok(quality) => {
+                        if sender.send((fingerprint, quality)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => log::warn!("分析图像质量失败 {:?}: {}", path, e),
+                }
+            }
+        });
+        
+        Self {
+            wallpaper_ids,
+            receiver,
+            cancelled,
+            handle: Some(handle),
+        }
+    }
+    
+    /// 取回自上次调用以来完成的结果（文件指纹, 质量指标）
+    pub fn take_results(&mut self) -> Vec<(String, ImageQuality)> {
+        self.receiver.try_iter().collect()
+    }
+    
+    /// 使用该文件的壁纸 ID
+    pub fn get_wallpaper_ids(&self, fingerprint: &str) -> &[String] {
+        self.wallpaper_ids
+            .get(fingerprint)
+            .map(|ids| ids.as_slice())
+            .unwrap_or(&[])
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().is_none_or(|handle| handle.is_finished())
+    }
+    
+    /// 请求在当前文件分析完成后停止，未分析的文件在下次扫描时重新排队
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    
+    /// 阻塞直到分析结束，结果仍需通过 `take_results` 取回
+    pub fn wait(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                log::warn!("图像质量分析线程异常退出");
+            }
+        }
+    }
+}
+
+impl Drop for QualityAnalysis {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
\ No newline at end of file
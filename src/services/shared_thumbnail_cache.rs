@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use image::DynamicImage;
+use image::imageops::FilterType;
+use crate::Result;
+use crate::utils::{calculate_scaled_size, encode_png_with_text, file_uri, md5_hex, read_png_text};
+
+/// 写入 `fail/` 目录时使用的程序标识
+const FAIL_DIRECTORY_NAME: &str = concat!("wallpaper-explorer-", env!("CARGO_PKG_VERSION"));
+
+/// freedesktop.org 规范定义的缩略图尺寸档位：(目录名, 最大边长)，从小到大排列
+const SIZE_TIERS: &[(&str, u32)] = &[
+    ("normal", 128),
+    ("large", 256),
+    ("x-large", 512),
+    ("xx-large", 1024),
+];
+
+/// freedesktop.org 共享缩略图缓存
+///
+/// 缩略图以原图 URI 的 MD5 命名，保存为带 `Thumb::URI`、`Thumb::MTime` 文本块的 PNG，
+/// 原图的修改时间与 `Thumb::MTime` 不一致时视为过期。无法解码的文件记录在
+/// `fail/<程序名>/` 目录中，避免反复尝试
+pub struct SharedThumbnailCache {
+    root: PathBuf,
+    writable: bool,
+}
+
+impl SharedThumbnailCache {
+    pub fn new(root: PathBuf, writable: bool) -> Self {
+        Self { root, writable }
+    }
+    
+    /// 默认位置 `$XDG_CACHE_HOME/thumbnails`
+    pub fn default_root() -> Option<PathBuf> {
+        dirs::cache_dir().map(|cache| cache.join("thumbnails"))
+    }
+    
+    pub fn is_writable(&self) -> bool {
+        self.writable
+    }
+    
+    /// 查找至少能覆盖 `min_size` 边长的有效缩略图，优先使用较小的档位
+    pub fn lookup(&self, source: &Path, min_size: u32) -> Option<PathBuf> {
+        let (uri, mtime) = source_identity(source)?;
+        let filename: String = format!("{}.png", md5_hex(uri.as_bytes()));
+        
+        SIZE_TIERS
+            .iter()
+            .filter(|(_, size)| *size >= min_size)
+            .map(|(directory, _)| self.root.join(directory).join(&filename))
+            .find(|path| is_valid_thumbnail(path, &uri, mtime))
+    }
+    
+    /// 本程序此前是否记录过该文件无法解码
+    pub fn is_failed(&self, source: &Path) -> bool {
+        match source_identity(source) {
+            Some((uri, mtime)) => is_valid_thumbnail(&self.get_fail_path(&uri), &uri, mtime),
+            None => false,
+        }
+    }
+    
+    /// 能覆盖 `min_size` 边长的最小档位的尺寸
+    pub fn get_tier_size(&self, min_size: u32) -> Option<u32> {
+        SIZE_TIERS.iter().find(|(_, size)| *size >= min_size).map(|(_, size)| *size)
+    }
+    
+    /// 按规范写入缩略图，`image` 为已按 EXIF 方向旋转的原图，或长边不小于档位尺寸的缩小图，
+    /// 写入能覆盖 `min_size` 的最小档位，原图较小时不放大
+    pub fn store(&self, source: &Path, image: &DynamicImage, min_size: u32) -> Result<()> {
+        let Some((uri, mtime)) = source_identity(source) else {
+            return Ok(());
+        };
+        let Some((directory, tier_size)) = SIZE_TIERS.iter().find(|(_, size)| *size >= min_size) else {
+            return Ok(());
+        };
+        
+        let thumbnail: DynamicImage = if image.width() > *tier_size || image.height() > *tier_size {
+            let (width, height) = calculate_scaled_size(image.width(), image.height(), *tier_size, *tier_size);
+            image.resize(width, height, FilterType::Triangle)
+        } else {
+            image.clone()
+        };
+        
+        let path: PathBuf = self.root.join(directory).join(format!("{}.png", md5_hex(uri.as_bytes())));
+        let size: u64 = std::fs::metadata(source)?.len();
+        self.write_thumbnail(&path, &thumbnail, &uri, mtime, Some(size))
+    }
+    
+    /// 记录文件无法解码，规范要求失败记录为带有相同文本块的 1x1 PNG
+    pub fn store_failure(&self, source: &Path) -> Result<()> {
+        let Some((uri, mtime)) = source_identity(source) else {
+            return Ok(());
+        };
+        let placeholder: DynamicImage = DynamicImage::new_rgba8(1, 1);
+        self.write_thumbnail(&self.get_fail_path(&uri), &placeholder, &uri, mtime, None)
+    }
+    
+    fn get_fail_path(&self, uri: &str) -> PathBuf {
+        self.root
+            .join("fail")
+            .join(FAIL_DIRECTORY_NAME)
+            .join(format!("{}.png", md5_hex(uri.as_bytes())))
+    }
+    
+    /// 先写入同目录下的临时文件再重命名，避免其他程序读到不完整的文件
+    fn write_thumbnail(&self, path: &Path, image: &DynamicImage, uri: &str, mtime: u64, size: Option<u64>) -> Result<()> {
+        if !self.writable {
+            return Ok(());
+        }
+        
+        let mut text: Vec<(&str, String)> = vec![
+            ("Thumb::URI", uri.to_string()),
+            ("Thumb::MTime", mtime.to_string()),
+            ("Software", FAIL_DIRECTORY_NAME.to_string()),
+        ];
+        if let Some(size) = size {
+            text.push(("Thumb::Size", size.to_string()));
+        }
+        let encoded: Vec<u8> = encode_png_with_text(image, &text)?;
+        
+        let directory: &Path = path.parent().unwrap_or(&self.root);
+        create_private_dir(directory)?;
+        let temporary: PathBuf = directory.join(format!(
+            "{}.{}.tmp",
+            path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default(),
+            std::process::id()
+        ));
+        std::fs::write(&temporary, encoded)?;
+        set_private_permissions(&temporary, 0o600)?;
+        std::fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
+
+/// 原图的 URI 和以秒为单位的修改时间
+fn source_identity(source: &Path) -> Option<(String, u64)> {
+    let uri: String = file_uri(source).ok()?;
+    let mtime: u64 = std::fs::metadata(source)
+        .and_then(|metadata| metadata.modified())
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some((uri, mtime))
+}
+
+fn is_valid_thumbnail(path: &Path, uri: &str, mtime: u64) -> bool {
+    if !path.is_file() {
+        return false;
+    }
+    
+    let text: HashMap<String, String> = match read_png_text(path) {
+        Ok(text) => text,
+        Err(e) => {
+            log::debug!("读取共享缩略图 {:?} 失败: {}", path, e);
+            return false;
+        }
+    };
+    text.get("Thumb::URI").map(String::as_str) == Some(uri)
+        && text.get("Thumb::MTime").and_then(|value| value.parse::<u64>().ok()) == Some(mtime)
+}
+
+/// 规范要求缩略图目录权限为 700
+fn create_private_dir(directory: &Path) -> Result<()> {
+    if !directory.exists() {
+        std::fs::create_dir_all(directory)?;
+        set_private_permissions(directory, 0o700)?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_private_permissions(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_private_permissions(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_cache() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-shared-thumbnails-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        
+        let source: PathBuf = directory.join("壁纸 1.png");
+        let image: DynamicImage = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(800, 400, image::Rgb([0, 90, 180])));
+        image.save(&source).unwrap();
+        
+        let read_only: SharedThumbnailCache = SharedThumbnailCache::new(directory.join("thumbnails"), false);
+        read_only.store(&source, &image, 200).unwrap();
+        assert!(read_only.lookup(&source, 200).is_none());
+        
+        let cache: SharedThumbnailCache = SharedThumbnailCache::new(directory.join("thumbnails"), true);
+        cache.store(&source, &image, 200).unwrap();
+        let thumbnail: PathBuf = cache.lookup(&source, 200).unwrap();
+        assert_eq!(thumbnail.parent().unwrap().file_name().unwrap(), "large");
+        assert_eq!(image::image_dimensions(&thumbnail).unwrap(), (256, 128));
+        // 档位不足以覆盖请求的尺寸时不使用
+        assert!(cache.lookup(&source, 300).is_none());
+        
+        // 原图修改后缩略图失效
+        let file: std::fs::File = std::fs::File::options().write(true).open(&source).unwrap();
+        file.set_modified(std::time::SystemTime::now() + std::time::Duration::from_secs(60)).unwrap();
+        assert!(cache.lookup(&source, 200).is_none());
+        
+        assert!(!cache.is_failed(&source));
+        cache.store_failure(&source).unwrap();
+        assert!(cache.is_failed(&source));
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
\ No newline at end of file
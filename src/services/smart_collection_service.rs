@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use crate::{Result, WallpaperError};
+use crate::components::Query;
+use crate::components::grouping::is_grouped_raw;
+use crate::config::Config;
+use crate::models::{SmartCollection, Wallpaper};
+
+/// 侧边栏中显示的智能收藏条目
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmartCollectionEntry {
+    pub id: String,
+    pub name: String,
+    pub count: usize,
+}
+
+/// 智能收藏服务，负责保存查询并在壁纸库变化时重新计算成员
+pub struct SmartCollectionService {
+    collections: Vec<SmartCollection>,
+    queries: HashMap<String, Query>,
+    members: HashMap<String, Vec<String>>,
+    /// 上一次计算成员时壁纸库的版本号
+    evaluated_generation: Option<u64>,
+}
+
+impl SmartCollectionService {
+    pub fn new(config: &Config) -> Self {
+        let mut service: SmartCollectionService = Self {
+            collections: Vec::new(),
+            queries: HashMap::new(),
+            members: HashMap::new(),
+            evaluated_generation: None,
+        };
+        
+        for collection in &config.smart_collections {
+            match Query::parse(&collection.query) {
+                Ok(query) => {
+                    service.queries.insert(collection.id.clone(), query);
+                }
+                Err(e) => {
+                    log::warn!("智能收藏 {} 的查询无效: {}", collection.name, e);
+                }
+            }
+            service.collections.push(collection.clone());
+        }
+        
+        service
+    }
+    
+    pub fn get_collections(&self) -> &[SmartCollection] {
+        &self.collections
+    }
+    
+    pub fn get_collection(&self, id: &str) -> Option<&SmartCollection> {
+        self.collections.iter().find(|collection| collection.id == id)
+    }
+    
+    /// 新建智能收藏，查询语句无效时返回错误
+    pub fn create(&mut self, name: &str, query: &str) -> Result<&SmartCollection> {
+        let parsed: Query = Query::parse(query)?;
+        let collection: SmartCollection = SmartCollection::new(name.to_string(), query.to_string());
+        
+        self.queries.insert(collection.id.clone(), parsed);
+        self.collections.push(collection);
+        self.evaluated_generation = None;
+        
+        Ok(self.collections.last().unwrap())
+    }
+    
+    pub fn rename(&mut self, id: &str, name: &str) -> Result<()> {
+        self.find_mut(id)?.name = name.to_string();
+        Ok(())
+    }
+    
+    /// 修改查询语句，下一次刷新时重新计算成员
+    pub fn update_query(&mut self, id: &str, query: &str) -> Result<()> {
+        let parsed: Query = Query::parse(query)?;
+        self.find_mut(id)?.query = query.to_string();
+        self.queries.insert(id.to_string(), parsed);
+        self.evaluated_generation = None;
+        Ok(())
+    }
+    
+    pub fn delete(&mut self, id: &str) -> Result<()> {
+        let before: usize = self.collections.len();
+        self.collections.retain(|collection| collection.id != id);
+        
+        if self.collections.len() == before {
+            return Err(WallpaperError::Service(format!("找不到智能收藏: {}", id)));
+        }
+        
+        self.queries.remove(id);
+        self.members.remove(id);
+        Ok(())
+    }
+    
+    /// 壁纸库版本号变化时重新计算所有智能收藏的成员，返回是否进行了计算
+    ///
+    /// 查询中的标签在计算时通过 `resolve` 解析别名，与搜索一致；保存的查询语句保持用户输入的原样
+    pub fn refresh<F>(&mut self, wallpapers: &[Wallpaper], generation: u64, resolve: &F) -> bool
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        if self.evaluated_generation == Some(generation) {
+            return false;
+        }
+        
+        self.members = self.queries
+            .iter()
+            .map(|(id, query)| {
+                let mut query: Query = query.clone();
+                query.resolve_tags(resolve);
+                let ids: Vec<String> = query
+                    .filter(wallpapers)
+                    .into_iter()
+                    .filter(|wallpaper| !is_grouped_raw(wallpaper))
+                    .map(|wallpaper| wallpaper.id.clone())
+                    .collect();
+                (id.clone(), ids)
+            })
+            .collect();
+        self.evaluated_generation = Some(generation);
+        
+        true
+    }
+    
+    /// 返回智能收藏当前的成员 ID（按壁纸库顺序）
+    pub fn get_members(&self, id: &str) -> &[String] {
+        self.members
+            .get(id)
+            .map(|ids| ids.as_slice())
+            .unwrap_or(&[])
+    }
+    
+    /// 侧边栏条目及实时数量
+    pub fn get_sidebar_entries(&self) -> Vec<SmartCollectionEntry> {
+        self.collections
+            .iter()
+            .map(|collection| SmartCollectionEntry {
+                id: collection.id.clone(),
+                name: collection.name.clone(),
+                count: self.get_members(&collection.id).len(),
+            })
+            .collect()
+    }
+    
+    /// 将智能收藏写回配置并保存
+    pub fn persist(&self, config: &mut Config) -> Result<()> {
+        config.smart_collections = self.collections.clone();
+        config.save()
+    }
+    
+    fn find_mut(&mut self, id: &str) -> Result<&mut SmartCollection> {
+        self.collections
+            .iter_mut()
+            .find(|collection| collection.id == id)
+            .ok_or_else(|| WallpaperError::Service(format!("找不到智能收藏: {}", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_wallpaper(id: &str, format: &str, width: u32) -> Wallpaper {
+        Wallpaper {
+            id: id.to_string(),
+            ..Wallpaper::for_test(&format!("{}.{}", id, format))
+        }
+        .with_dimensions(width, width * 9 / 16)
+    }
+    
+    fn no_aliases(tag: &str) -> Option<String> {
+        Some(tag.to_string())
+    }
+
+    #[test]
+    fn test_smart_collection_members_follow_library() {
+        let mut service: SmartCollectionService = SmartCollectionService::new(&Config::default());
+        let uhd_id: String = service.create("4K", "width>=3840").unwrap().id.clone();
+        let png_id: String = service.create("PNG", "format:png").unwrap().id.clone();
+        
+        let mut wallpapers: Vec<Wallpaper> = vec![
+            create_test_wallpaper("a", "png", 3840),
+            create_test_wallpaper("b", "jpg", 1920),
+        ];
+        assert!(service.refresh(&wallpapers, 1, &no_aliases));
+        assert_eq!(service.get_members(&uhd_id), ["a"]);
+        assert_eq!(service.get_members(&png_id), ["a"]);
+        
+        // 版本号未变化时不重新计算
+        assert!(!service.refresh(&wallpapers, 1, &no_aliases));
+        
+        wallpapers.push(create_test_wallpaper("c", "png", 5120));
+        assert!(service.refresh(&wallpapers, 2, &no_aliases));
+        
+        let counts: Vec<(String, usize)> = service.get_sidebar_entries()
+            .into_iter()
+            .map(|entry| (entry.name, entry.count))
+            .collect();
+        assert_eq!(counts, vec![("4K".to_string(), 2), ("PNG".to_string(), 2)]);
+        
+        service.update_query(&png_id, "format:jpg").unwrap();
+        assert!(service.refresh(&wallpapers, 2, &no_aliases));
+        assert_eq!(service.get_members(&png_id), ["b"]);
+    }
+
+    #[test]
+    fn test_invalid_query_is_rejected() {
+        let mut service: SmartCollectionService = SmartCollectionService::new(&Config::default());
+        
+        assert!(service.create("坏查询", "width>=").is_err());
+        assert!(service.get_collections().is_empty());
+        
+        let id: String = service.create("横屏", "is:landscape").unwrap().id.clone();
+        assert!(service.update_query(&id, "(is:portrait").is_err());
+        assert_eq!(service.get_collection(&id).unwrap().query, "is:landscape");
+        
+        service.delete(&id).unwrap();
+        assert!(service.delete(&id).is_err());
+    }
+
+    #[test]
+    fn test_smart_collection_skips_grouped_raw() {
+        let mut service: SmartCollectionService = SmartCollectionService::new(&Config::default());
+        let id: String = service.create("宽屏", "width>=3840").unwrap().id.clone();
+        
+        let mut wallpapers: Vec<Wallpaper> = vec![
+            create_test_wallpaper("IMG_0001", "jpg", 3840),
+            create_test_wallpaper("IMG_0001", "cr2", 3840),
+            create_test_wallpaper("IMG_0002", "cr2", 3840),
+        ];
+        wallpapers[1].id = "IMG_0001-raw".to_string();
+        crate::components::grouping::link_raw_siblings(&mut wallpapers);
+        
+        service.refresh(&wallpapers, 1, &no_aliases);
+        assert_eq!(service.get_members(&id), ["IMG_0001", "IMG_0002"]);
+    }
+
+    #[test]
+    fn test_smart_collection_resolves_tag_aliases() {
+        let mut service: SmartCollectionService = SmartCollectionService::new(&Config::default());
+        let id: String = service.create("风景", "tag:scenery").unwrap().id.clone();
+        
+        let mut wallpapers: Vec<Wallpaper> = vec![
+            create_test_wallpaper("a", "jpg", 1920),
+            create_test_wallpaper("b", "jpg", 1920),
+        ];
+        wallpapers[0].add_tag("nature".to_string());
+        
+        let resolve = |tag: &str| -> Option<String> {
+            Some(if tag == "scenery" { "nature".to_string() } else { tag.to_string() })
+        };
+        service.refresh(&wallpapers, 1, &resolve);
+        assert_eq!(service.get_members(&id), ["a"]);
+        assert_eq!(service.get_collection(&id).unwrap().query, "tag:scenery");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,589 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::{Result, WallpaperError};
+use crate::components::RuleMatch;
+use crate::config::Config;
+use crate::models::tag::{is_tag_or_descendant, is_valid_tag_color, normalize_tag, tag_with_ancestors};
+use crate::models::{TagCount, TagDefinition, Wallpaper};
+use crate::utils::{load_toml_file, save_toml_file};
+
+/// 从 XMP 关键词导入的标签在来源记录中使用的规则ID
+pub const XMP_KEYWORD_SOURCE: &str = "xmp";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TagStore {
+    #[serde(default)]
+    definitions: Vec<TagDefinition>,
+    /// 壁纸ID到标签列表的映射
+    #[serde(default)]
+    assignments: BTreeMap<String, Vec<String>>,
+    /// 由自动标签规则添加的标签：壁纸ID -> 标签 -> 规则ID
+    #[serde(default)]
+    rule_sources: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// 应用自动标签规则的结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleReport {
+    /// 新添加的标签数量
+    pub added: usize,
+    /// 因规则不再匹配而移除的标签数量
+    pub removed: usize,
+    /// 每条规则命中的壁纸数量
+    pub by_rule: BTreeMap<String, usize>,
+}
+
+/// 标签服务：层级标签、别名、颜色和标签分配的持久化
+///
+/// 所有标签在写入前都会经过规范化和别名解析，修改后立即写入 `tags.toml`
+pub struct TagService {
+    store_path: PathBuf,
+    definitions: BTreeMap<String, TagDefinition>,
+    /// 别名到标签名的映射
+    aliases: HashMap<String, String>,
+    assignments: BTreeMap<String, Vec<String>>,
+    rule_sources: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl TagService {
+    pub fn new(config: &Config) -> Result<Self> {
+        let store_path: PathBuf = config.data_directory.join("tags.toml");
+        let store: TagStore = load_toml_file(&store_path)?.unwrap_or_default();
+        
+        let mut service: TagService = Self {
+            store_path,
+            definitions: store.definitions
+                .into_iter()
+                .map(|definition| (definition.name.clone(), definition))
+                .collect(),
+            aliases: HashMap::new(),
+            assignments: store.assignments,
+            rule_sources: store.rule_sources,
+        };
+        service.rebuild_alias_index();
+        
+        Ok(service)
+    }
+    
+    /// 规范化标签并解析别名，例如别名 `scenery` 指向 `nature` 时，
+    /// `Scenery/Hills` 解析为 `nature/hills`
+    pub fn resolve(&self, raw: &str) -> Option<String> {
+        let tag: String = normalize_tag(raw)?;
+        
+        for prefix in tag_with_ancestors(&tag).into_iter().rev() {
+            if let Some(target) = self.aliases.get(prefix) {
+                return Some(format!("{}{}", target, &tag[prefix.len()..]));
+            }
+        }
+        
+        Some(tag)
+    }
+    
+    pub fn get_definition(&self, tag: &str) -> Option<&TagDefinition> {
+        self.resolve(tag).and_then(|tag| self.definitions.get(&tag))
+    }
+    
+    pub fn get_tags(&self, wallpaper_id: &str) -> &[String] {
+        self.assignments
+            .get(wallpaper_id)
+            .map(|tags| tags.as_slice())
+            .unwrap_or(&[])
+    }
+    
+    /// 返回为壁纸添加该标签的规则ID（XMP 关键词为 [`XMP_KEYWORD_SOURCE`]），手动添加的标签返回 `None`
+    pub fn get_rule_source(&self, wallpaper_id: &str, tag: &str) -> Option<&str> {
+        self.rule_sources
+            .get(wallpaper_id)
+            .and_then(|sources| sources.get(tag))
+            .map(|rule_id| rule_id.as_str())
+    }
+    
+    /// 设置标签颜色，`None` 表示清除颜色
+    pub fn set_color(&mut self, tag: &str, color: Option<&str>) -> Result<()> {
+        if let Some(color) = color {
+            if !is_valid_tag_color(color) {
+                return Err(WallpaperError::Service(format!("无效的标签颜色: {}", color)));
+            }
+        }
+        
+        let tag: String = self.resolve_or_error(tag)?;
+        self.definitions
+            .entry(tag.clone())
+            .or_insert_with(|| TagDefinition::new(tag))
+            .color = color.map(|color| color.to_lowercase());
+        self.save()
+    }
+    
+    /// 为标签添加别名或同义词
+    pub fn add_alias(&mut self, tag: &str, alias: &str) -> Result<()> {
+        let tag: String = self.resolve_or_error(tag)?;
+        let alias: String = normalize_tag(alias)
+            .ok_or_else(|| WallpaperError::Service("别名不能为空".to_string()))?;
+        
+        if is_tag_or_descendant(&alias, &tag) || is_tag_or_descendant(&tag, &alias) {
+            return Err(WallpaperError::Service(format!("别名 {} 不能与标签 {} 处于同一层级路径", alias, tag)));
+        }
+        if let Some(existing) = self.aliases.get(&alias) {
+            if *existing != tag {
+                return Err(WallpaperError::Service(format!("别名 {} 已属于标签 {}", alias, existing)));
+            }
+            return Ok(());
+        }
+        if self.is_in_use(&alias) {
+            return Err(WallpaperError::Service(format!("{} 已是正在使用的标签，请使用合并", alias)));
+        }
+        
+        let definition: &mut TagDefinition = self.definitions
+            .entry(tag.clone())
+            .or_insert_with(|| TagDefinition::new(tag));
+        definition.aliases.push(alias);
+        self.rebuild_alias_index();
+        self.save()
+    }
+    
+    pub fn remove_alias(&mut self, alias: &str) -> Result<()> {
+        let alias: String = normalize_tag(alias).unwrap_or_default();
+        for definition in self.definitions.values_mut() {
+            definition.aliases.retain(|existing| *existing != alias);
+        }
+        self.rebuild_alias_index();
+        self.save()
+    }
+    
+    /// 为一组壁纸添加标签，返回规范化后的标签名
+    pub fn add_tag(&mut self, wallpaper_ids: &[String], tag: &str) -> Result<String> {
+        let tag: String = self.resolve_or_error(tag)?;
+        
+        for id in wallpaper_ids {
+            let tags: &mut Vec<String> = self.assignments.entry(id.clone()).or_default();
+            if !tags.contains(&tag) {
+                tags.push(tag.clone());
+            }
+            // 手动添加后，该标签不再随规则变化而移除
+            self.forget_rule_source(id, &tag);
+        }
+        
+        self.save()?;
+        Ok(tag)
+    }
+    
+    /// 从一组壁纸上移除标签（不影响其子标签）
+    pub fn remove_tag(&mut self, wallpaper_ids: &[String], tag: &str) -> Result<()> {
+        let tag: String = self.resolve_or_error(tag)?;
+        
+        for id in wallpaper_ids {
+            if let Some(tags) = self.assignments.get_mut(id) {
+                tags.retain(|existing| *existing != tag);
+                if tags.is_empty() {
+                    self.assignments.remove(id);
+                }
+            }
+            self.forget_rule_source(id, &tag);
+        }
+        
+        self.save()
+    }
+    
+    /// 在整个壁纸库中重命名标签，子标签随之移动；新名称已存在时等同于合并
+    ///
+    /// 返回受影响的壁纸数量
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> Result<usize> {
+        let affected: usize = self.rename_tag_unsaved(from, to)?;
+        self.save()?;
+        Ok(affected)
+    }
+    
+    /// 重命名标签但不写入磁盘，供批量操作在结束时统一保存
+    fn rename_tag_unsaved(&mut self, from: &str, to: &str) -> Result<usize> {
+        let from: String = self.resolve_or_error(from)?;
+        let to: String = self.resolve_or_error(to)?;
+        
+        if from == to {
+            return Ok(0);
+        }
+        if is_tag_or_descendant(&to, &from) {
+            return Err(WallpaperError::Service(format!("不能将标签 {} 移动到其子标签 {} 下", from, to)));
+        }
+        
+        let rename = |tag: &str| -> Option<String> {
+            if is_tag_or_descendant(tag, &from) {
+                Some(format!("{}{}", to, &tag[from.len()..]))
+            } else {
+                None
+            }
+        };
+        
+        let mut affected: usize = 0;
+        for tags in self.assignments.values_mut() {
+            if !tags.iter().any(|tag| rename(tag).is_some()) {
+                continue;
+            }
+            affected += 1;
+            
+            let mut renamed: Vec<String> = Vec::with_capacity(tags.len());
+            for tag in tags.iter() {
+                let tag: String = rename(tag).unwrap_or_else(|| tag.clone());
+                if !renamed.contains(&tag) {
+                    renamed.push(tag);
+                }
+            }
+            *tags = renamed;
+        }
+        
+        // 重命名后的标签视为手动标签
+        for sources in self.rule_sources.values_mut() {
+            sources.retain(|tag, _| rename(tag).is_none());
+        }
+        self.rule_sources.retain(|_, sources| !sources.is_empty());
+        
+        let moved: Vec<String> = self.definitions
+            .keys()
+            .filter(|name| rename(name).is_some())
+            .cloned()
+            .collect();
+        for name in moved {
+            let mut definition: TagDefinition = self.definitions.remove(&name).unwrap();
+            let new_name: String = rename(&name).unwrap();
+            
+            match self.definitions.get_mut(&new_name) {
+                Some(existing) => {
+                    existing.color = existing.color.take().or(definition.color);
+                    existing.aliases.append(&mut definition.aliases);
+                }
+                None => {
+                    definition.name = new_name.clone();
+                    self.definitions.insert(new_name, definition);
+                }
+            }
+        }
+        
+        self.rebuild_alias_index();
+        Ok(affected)
+    }
+    
+    /// 将若干标签合并到目标标签，被合并的标签名成为目标标签的同义词
+    ///
+    /// 返回受影响的壁纸数量
+    pub fn merge_tags(&mut self, sources: &[String], target: &str) -> Result<usize> {
+        let target: String = self.resolve_or_error(target)?;
+        let mut affected: usize = 0;
+        
+        for source in sources {
+            let source: String = self.resolve_or_error(source)?;
+            if source == target {
+                continue;
+            }
+            
+            affected += self.rename_tag_unsaved(&source, &target)?;
+            
+            // 只有顶层名称不与现有标签冲突时才能作为别名
+            if !self.is_in_use(&source) && !self.aliases.contains_key(&source) {
+                self.definitions
+                    .entry(target.clone())
+                    .or_insert_with(|| TagDefinition::new(target.clone()))
+                    .aliases
+                    .push(source);
+            }
+        }
+        
+        self.rebuild_alias_index();
+        self.save()?;
+        Ok(affected)
+    }
+    
+    /// 从整个壁纸库删除标签及其子标签
+    pub fn delete_tag(&mut self, tag: &str) -> Result<()> {
+        let tag: String = self.resolve_or_error(tag)?;
+        
+        for tags in self.assignments.values_mut() {
+            tags.retain(|existing| !is_tag_or_descendant(existing, &tag));
+        }
+        self.assignments.retain(|_, tags| !tags.is_empty());
+        for sources in self.rule_sources.values_mut() {
+            sources.retain(|existing, _| !is_tag_or_descendant(existing, &tag));
+        }
+        self.rule_sources.retain(|_, sources| !sources.is_empty());
+        self.definitions.retain(|name, _| !is_tag_or_descendant(name, &tag));
+        
+        self.rebuild_alias_index();
+        self.save()
+    }
+    
+    /// 所有标签的统计信息（包含上级标签的汇总），按标签名排序
+    pub fn get_tag_counts(&self) -> Vec<TagCount> {
+        let mut direct: BTreeMap<&str, usize> = BTreeMap::new();
+        let mut total: BTreeMap<&str, usize> = BTreeMap::new();
+        
+        for tags in self.assignments.values() {
+            // 同一张壁纸在某个上级标签下只计一次
+            let mut counted: BTreeSet<&str> = BTreeSet::new();
+            for tag in tags {
+                *direct.entry(tag.as_str()).or_default() += 1;
+                counted.extend(tag_with_ancestors(tag));
+            }
+            for tag in counted {
+                *total.entry(tag).or_default() += 1;
+            }
+        }
+        
+        total
+            .into_iter()
+            .map(|(tag, total)| TagCount {
+                tag: tag.to_string(),
+                count: direct.get(tag).copied().unwrap_or(0),
+                total,
+                color: self.definitions.get(tag).and_then(|definition| definition.color.clone()),
+            })
+            .collect()
+    }
+    
+    /// 用规则的最新匹配结果替换壁纸上由规则添加的标签
+    ///
+    /// 规则不再匹配的标签会被移除；壁纸已手动带有的标签保持为手动标签
+    pub fn apply_rule_matches(&mut self, results: &[(String, Vec<RuleMatch>)]) -> Result<RuleReport> {
+        let mut report: RuleReport = RuleReport::default();
+        
+        for (wallpaper_id, matches) in results {
+            let previous: BTreeMap<String, String> = self.rule_sources
+                .remove(wallpaper_id)
+                .unwrap_or_default();
+            let mut sources: BTreeMap<String, String> = BTreeMap::new();
+            
+            for found in matches {
+                let Some(tag) = self.resolve(&found.tag) else {
+                    continue;
+                };
+                *report.by_rule.entry(found.rule_id.clone()).or_default() += 1;
+                
+                let tags: &mut Vec<String> = self.assignments.entry(wallpaper_id.clone()).or_default();
+                if tags.contains(&tag) {
+                    if !previous.contains_key(&tag) {
+                        continue;
+                    }
+                } else {
+                    tags.push(tag.clone());
+                    report.added += 1;
+                }
+                sources.insert(tag, found.rule_id.clone());
+            }
+            
+            if let Some(tags) = self.assignments.get_mut(wallpaper_id) {
+                let before: usize = tags.len();
+                tags.retain(|tag| !previous.contains_key(tag) || sources.contains_key(tag));
+                report.removed += before - tags.len();
+                if tags.is_empty() {
+                    self.assignments.remove(wallpaper_id);
+                }
+            }
+            if !sources.is_empty() {
+                self.rule_sources.insert(wallpaper_id.clone(), sources);
+            }
+        }
+        
+        self.save()?;
+        Ok(report)
+    }
+    
+    /// 将持久化的标签写入壁纸模型
+    pub fn apply_to(&self, wallpapers: &mut [Wallpaper]) {
+        for wallpaper in wallpapers {
+            wallpaper.tags = self.get_tags(&wallpaper.id).to_vec();
+        }
+    }
+    
+    /// 壁纸ID变化后迁移其标签
+    pub fn replace_ids(&mut self, renamed: &HashMap<String, String>) -> Result<()> {
+        if renamed.is_empty() {
+            return Ok(());
+        }
+        
+        for (old_id, new_id) in renamed {
+            if let Some(tags) = self.assignments.remove(old_id) {
+                self.assignments.insert(new_id.clone(), tags);
+            }
+            if let Some(sources) = self.rule_sources.remove(old_id) {
+                self.rule_sources.insert(new_id.clone(), sources);
+            }
+        }
+        self.save()
+    }
+    
+    /// 删除壁纸后清理其标签
+    pub fn remove_wallpapers(&mut self, wallpaper_ids: &[String]) -> Result<()> {
+        for id in wallpaper_ids {
+            self.assignments.remove(id);
+            self.rule_sources.remove(id);
+        }
+        self.save()
+    }
+    
+    pub fn save(&self) -> Result<()> {
+        let store: TagStore = TagStore {
+            definitions: self.definitions.values().cloned().collect(),
+            assignments: self.assignments.clone(),
+            rule_sources: self.rule_sources.clone(),
+        };
+        save_toml_file(&self.store_path, &store)
+    }
+    
+    fn resolve_or_error(&self, tag: &str) -> Result<String> {
+        self.resolve(tag)
+            .ok_or_else(|| WallpaperError::Service("标签不能为空".to_string()))
+    }
+    
+    fn forget_rule_source(&mut self, wallpaper_id: &str, tag: &str) {
+        if let Some(sources) = self.rule_sources.get_mut(wallpaper_id) {
+            sources.remove(tag);
+            if sources.is_empty() {
+                self.rule_sources.remove(wallpaper_id);
+            }
+        }
+    }
+    
+    fn is_in_use(&self, tag: &str) -> bool {
+        self.definitions.keys().any(|name| is_tag_or_descendant(name, tag))
+            || self.assignments
+                .values()
+                .flatten()
+                .any(|existing| is_tag_or_descendant(existing, tag))
+    }
+    
+    fn rebuild_alias_index(&mut self) {
+        self.aliases = self.definitions
+            .values()
+            .flat_map(|definition| {
+                definition.aliases
+                    .iter()
+                    .map(|alias| (alias.clone(), definition.name.clone()))
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_service(name: &str) -> (TagService, Config) {
+        let config: Config = Config {
+            data_directory: std::env::temp_dir()
+                .join(format!("wallpaper-explorer-tags-{}-{}", name, std::process::id())),
+            ..Config::default()
+        };
+        let _ = std::fs::remove_dir_all(&config.data_directory);
+        
+        (TagService::new(&config).unwrap(), config)
+    }
+    
+    fn ids(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_bulk_edit_and_counts() {
+        let (mut service, config) = create_test_service("counts");
+        
+        service.add_tag(&ids(&["a", "b"]), "Nature/Mountains").unwrap();
+        service.add_tag(&ids(&["b", "c"]), "nature / sea").unwrap();
+        service.add_tag(&ids(&["c"]), "NATURE").unwrap();
+        service.remove_tag(&ids(&["a"]), "nature/mountains").unwrap();
+        service.set_color("nature", Some("#2E8B57")).unwrap();
+        
+        assert_eq!(service.get_tags("b"), ["nature/mountains", "nature/sea"]);
+        assert!(service.get_tags("a").is_empty());
+        
+        let counts: Vec<(String, usize, usize)> = service.get_tag_counts()
+            .into_iter()
+            .map(|count| (count.tag, count.count, count.total))
+            .collect();
+        assert_eq!(counts, vec![
+            ("nature".to_string(), 1, 2),
+            ("nature/mountains".to_string(), 1, 1),
+            ("nature/sea".to_string(), 2, 2),
+        ]);
+        assert_eq!(service.get_tag_counts()[0].color.as_deref(), Some("#2e8b57"));
+        
+        let reloaded: TagService = TagService::new(&config).unwrap();
+        assert_eq!(reloaded.get_tags("b"), service.get_tags("b"));
+        assert_eq!(reloaded.get_definition("nature"), service.get_definition("nature"));
+        
+        let _ = std::fs::remove_dir_all(&config.data_directory);
+    }
+
+    #[test]
+    fn test_aliases() {
+        let (mut service, config) = create_test_service("aliases");
+        
+        service.add_tag(&ids(&["a"]), "nature").unwrap();
+        service.add_alias("nature", "Scenery").unwrap();
+        
+        assert_eq!(service.resolve("scenery/Hills"), Some("nature/hills".to_string()));
+        assert_eq!(service.add_tag(&ids(&["b"]), "SCENERY").unwrap(), "nature");
+        assert!(service.add_alias("nature", "nature/sub").is_err());
+        
+        service.add_tag(&ids(&["c"]), "city").unwrap();
+        assert!(service.add_alias("nature", "city").is_err());
+        
+        service.remove_alias("scenery").unwrap();
+        assert_eq!(service.resolve("scenery"), Some("scenery".to_string()));
+        
+        let _ = std::fs::remove_dir_all(&config.data_directory);
+    }
+
+    #[test]
+    fn test_rename_and_merge() {
+        let (mut service, config) = create_test_service("rename");
+        
+        service.add_tag(&ids(&["a"]), "nature/mountains/alps").unwrap();
+        service.add_tag(&ids(&["a", "b"]), "landscape/mountains").unwrap();
+        service.add_tag(&ids(&["c"]), "scenery").unwrap();
+        service.set_color("nature", Some("#00ff00")).unwrap();
+        
+        assert_eq!(service.rename_tag("nature", "landscape").unwrap(), 1);
+        assert_eq!(service.get_tags("a"), ["landscape/mountains/alps", "landscape/mountains"]);
+        assert_eq!(service.get_definition("landscape").unwrap().color.as_deref(), Some("#00ff00"));
+        assert!(service.rename_tag("landscape", "landscape/sub").is_err());
+        
+        assert_eq!(service.merge_tags(&ids(&["scenery"]), "landscape").unwrap(), 1);
+        assert_eq!(service.get_tags("c"), ["landscape"]);
+        assert_eq!(service.resolve("scenery"), Some("landscape".to_string()));
+        
+        service.delete_tag("landscape/mountains").unwrap();
+        assert!(service.get_tags("b").is_empty());
+        assert_eq!(service.get_tags("a"), Vec::<String>::new());
+        
+        let _ = std::fs::remove_dir_all(&config.data_directory);
+    }
+    #[test]
+    fn test_rule_provenance() {
+        let (mut service, config) = create_test_service("rules");
+        let found = |rule_id: &str, tag: &str| RuleMatch {
+            rule_id: rule_id.to_string(),
+            tag: tag.to_string(),
+        };
+        
+        service.add_tag(&ids(&["a"]), "4k").unwrap();
+        let report: RuleReport = service.apply_rule_matches(&[
+            ("a".to_string(), vec![found("r1", "Anime"), found("r2", "4K")]),
+            ("b".to_string(), vec![found("r1", "anime")]),
+        ]).unwrap();
+        assert_eq!((report.added, report.removed), (2, 0));
+        assert_eq!(report.by_rule.get("r1"), Some(&2));
+        assert_eq!(service.get_tags("a"), ["4k", "anime"]);
+        assert_eq!(service.get_rule_source("a", "anime"), Some("r1"));
+        // 手动标签不记录规则来源
+        assert_eq!(service.get_rule_source("a", "4k"), None);
+        
+        service.add_tag(&ids(&["b"]), "anime").unwrap();
+        let report: RuleReport = service.apply_rule_matches(&[
+            ("a".to_string(), Vec::new()),
+            ("b".to_string(), Vec::new()),
+        ]).unwrap();
+        assert_eq!((report.added, report.removed), (0, 1));
+        assert_eq!(service.get_tags("a"), ["4k"]);
+        assert_eq!(service.get_tags("b"), ["anime"]);
+        
+        let _ = std::fs::remove_dir_all(&config.data_directory);
+    }
+}
\ No newline at end of file
@@ -1,11 +1,189 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
-use image::{DynamicImage, ImageFormat};
-use crate::Result;
-use crate::config::Config;
+use std::sync::{Arc, mpsc};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread::JoinHandle;
+use chrono::{DateTime, Utc};
+use image::{DynamicImage, RgbImage, RgbaImage};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use serde::{Deserialize, Serialize};
+use crate::{Result, WallpaperError};
+use crate::config::{Config, SharedThumbnailMode, ThumbnailFormat};
+use crate::services::SharedThumbnailCache;
+use crate::utils::{
+    THUMBNAIL_TIERS, calculate_scaled_size, content_hash, file_fingerprint, has_transparency, load_for_thumbnail,
+    load_representative_frame, load_toml_file, read_animation_info, resize_thumbnail, safe_remove_file, save_toml_file, stable_hash,
+};
+
+/// 缩略图生成方式的版本号，生成规则变化（例如开始应用 EXIF 方向）时递增，使旧缓存失效
+const THUMBNAIL_VERSION: u32 = 6;
+
+/// 缩放缩略图使用的滤波器（见 `resize_thumbnail`），记录在生成参数中
+const THUMBNAIL_FILTER: &str = "lanczos3";
+
+/// 缓存清单文件名，位于缩略图目录中
+const MANIFEST_FILENAME: &str = "manifest.toml";
+
+/// 临时文件序号，同一进程中后台重建与前台生成可能同时写入同一个缩略图
+static TEMPORARY_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 生成缩略图的参数，与原图内容哈希一起决定缩略图文件名，任一项变化都会重新生成
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ThumbnailParams {
+    version: u32,
+    size: (u32, u32),
+    filter: String,
+    format: String,
+}
+
+/// 缓存清单中的一个缩略图
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// 最近一次使用该缩略图的原图路径，清单建立前已存在的缩略图为 `None`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<PathBuf>,
+    /// 原图内容哈希
+    #[serde(default)]
+    content_hash: String,
+    /// 旧版本缓存没有记录参数，视为已失效
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    params: Option<ThumbnailParams>,
+    size: u64,
+    last_access: DateTime<Utc>,
+}
+
+/// 文件系统修改时间的最粗精度（FAT 为 2 秒）
+const MTIME_RESOLUTION_SECONDS: i64 = 2;
+
+/// 原图的状态，文件大小、修改时间和头尾指纹都未变化时沿用上次计算的内容哈希，避免每次扫描都读取整个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SourceRecord {
+    file_size: u64,
+    modified: DateTime<Utc>,
+    /// 头尾各 64 KiB 的指纹，发现保留大小和修改时间的改写
+    #[serde(default)]
+    fingerprint: String,
+    /// 计算内容哈希的时间
+    #[serde(default)]
+    checked_at: DateTime<Utc>,
+    content_hash: String,
+}
+
+impl SourceRecord {
+    /// 计算哈希时修改时间已早于当时足够久；否则文件可能在同一个时间戳内又被改写，修改时间看不出变化
+    fn is_settled(&self) -> bool {
+        self.checked_at - self.modified >= chrono::Duration::seconds(MTIME_RESOLUTION_SECONDS)
+    }
+}
+
+/// 缓存清单：缩略图文件名到缓存信息的映射，以及原图路径到内容哈希的映射
+///
+/// 文件系统的访问时间常因 `noatime` 挂载选项而不可靠，因此自行记录
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheManifest {
+    #[serde(default)]
+    entries: BTreeMap<String, CacheEntry>,
+    #[serde(default)]
+    sources: BTreeMap<String, SourceRecord>,
+}
+
+/// 缓存清理的结果报告
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvictionReport {
+    /// 因原图不存在而删除的缩略图数量
+    pub orphans_removed: usize,
+    /// 由旧版本或其他生成参数产生、不会再被使用的缩略图数量
+    pub stale_removed: usize,
+    /// 因超出缓存上限而删除的最久未使用的缩略图数量
+    pub evicted: usize,
+    pub freed_bytes: u64,
+    /// 清理后的缓存大小
+    pub remaining_bytes: u64,
+}
+
+/// 缓存校验的统计结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheStats {
+    /// 缩略图数量，不含清单和临时文件
+    pub count: usize,
+    pub total_bytes: u64,
+    /// 原图已不存在的缩略图数量
+    pub orphaned: usize,
+    /// 为空、被截断或无法解码的缩略图文件名
+    pub broken: Vec<String>,
+    /// 写入中断遗留的临时文件数量
+    pub temporary_files: usize,
+}
+
+/// 缓存修复的结果报告
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepairReport {
+    /// 从原图重新生成的缩略图数量
+    pub regenerated: usize,
+    /// 原图缺失或无法解码而直接删除的损坏缩略图数量
+    pub removed: usize,
+    /// 删除的临时文件数量
+    pub temporary_removed: usize,
+}
+
+/// 重建缓存的进度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RebuildProgress {
+    pub completed: usize,
+    pub total: usize,
+    pub failed: usize,
+}
+
+/// 在后台线程中运行的缓存重建任务
+///
+/// 界面定时调用 `get_progress` 显示进度，`is_finished` 为真后调用 `wait` 取回重建后的服务
+pub struct CacheRebuild {
+    receiver: mpsc::Receiver<RebuildProgress>,
+    progress: RebuildProgress,
+    cancelled: Arc<AtomicBool>,
+    handle: JoinHandle<Result<(ThumbnailService, RebuildProgress)>>,
+}
+
+impl CacheRebuild {
+    /// 最新的进度
+    pub fn get_progress(&mut self) -> RebuildProgress {
+        while let Ok(progress) = self.receiver.try_recv() {
+            self.progress = progress;
+        }
+        self.progress
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+    
+    /// 请求在当前缩略图完成后停止，未重建的缩略图之后按需生成
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+    
+    /// 等待重建结束，返回重建使用的服务（其清单已包含新生成的缩略图）和最终进度
+    pub fn wait(self) -> Result<(ThumbnailService, RebuildProgress)> {
+        self.handle
+            .join()
+            .map_err(|_| WallpaperError::Service("缩略图重建线程异常退出".to_string()))?
+    }
+}
 
 pub struct ThumbnailService {
     cache_directory: PathBuf,
     thumbnail_size: (u32, u32),
+    thumbnail_format: ThumbnailFormat,
+    /// 缓存上限（字节），0 表示不限制
+    max_cache_size: u64,
+    manifest: CacheManifest,
+    /// 清单有未保存的修改
+    manifest_dirty: bool,
+    shared_cache: Option<SharedThumbnailCache>,
 }
 
 impl ThumbnailService {
@@ -15,91 +193,580 @@ impl ThumbnailService {
         // 确保缓存目录存在
         std::fs::create_dir_all(&cache_directory)?;
         
+        // 清单损坏时重新建立，不影响缩略图的使用
+        let manifest: CacheManifest = match load_toml_file(&cache_directory.join(MANIFEST_FILENAME)) {
+            Ok(manifest) => manifest.unwrap_or_default(),
+            Err(e) => {
+                log::warn!("读取缩略图缓存清单失败，将重新建立: {}", e);
+                CacheManifest::default()
+            }
+        };
+        
         Ok(Self {
             cache_directory,
             thumbnail_size: config.thumbnail_size,
+            thumbnail_format: config.thumbnail_format,
+            max_cache_size: config.max_cache_size_mb * 1024 * 1024,
+            manifest,
+            manifest_dirty: false,
+            shared_cache: match config.shared_thumbnails {
+                SharedThumbnailMode::Off => None,
+                SharedThumbnailMode::ReadOnly => SharedThumbnailCache::default_root()
+                    .map(|root| SharedThumbnailCache::new(root, false)),
+                SharedThumbnailMode::ReadWrite => SharedThumbnailCache::default_root()
+                    .map(|root| SharedThumbnailCache::new(root, true)),
+            },
         })
     }
     
-    pub fn generate_thumbnail(&self, image_path: &Path) -> Result<PathBuf> {
-        let thumbnail_path: PathBuf = self.get_thumbnail_path(image_path);
+    /// 生成配置中默认尺寸的缩略图
+    pub fn generate_thumbnail(&mut self, image_path: &Path) -> Result<PathBuf> {
+        self.generate_sized(image_path, self.thumbnail_size, false)
+    }
+    
+    /// 生成指定档位的缩略图，档位见 [`THUMBNAIL_TIERS`]，各档位分别缓存
+    pub fn generate_thumbnail_tier(&mut self, image_path: &Path, tier: u32) -> Result<PathBuf> {
+        if !THUMBNAIL_TIERS.contains(&tier) {
+            return Err(WallpaperError::Service(format!("不支持的缩略图档位: {}", tier)));
+        }
+        self.generate_sized(image_path, (tier, tier), false)
+    }
+    
+    /// `overwrite` 为真时即使已有有效的缩略图也重新生成，新文件通过重命名原子地替换旧文件
+    fn generate_sized(&mut self, image_path: &Path, size: (u32, u32), overwrite: bool) -> Result<PathBuf> {
+        let content_hash: String = self.get_content_hash(image_path)?;
+        let params: ThumbnailParams = self.get_params(size);
+        let max_side: u32 = size.0.max(size.1);
+        let stem: String = self.get_thumbnail_stem(&content_hash, &params);
         
-        // 如果缩略图已存在且比原图新，直接返回
-        if self.is_thumbnail_valid(&thumbnail_path, image_path)? {
+        // 内容和生成参数都相同的缩略图已存在，直接返回
+        if let Some(thumbnail_path) = self.find_thumbnail(&stem, &content_hash, &params).filter(|_| !overwrite) {
+            self.record_access(&thumbnail_path, image_path, content_hash, params);
             return Ok(thumbnail_path);
         }
         
         log::debug!("为 {:?} 生成缩略图", image_path);
         
-        // 加载原图
-        let image: DynamicImage = image::open(image_path)?;
+        // 动图使用代表帧而不是第一帧，第一帧常常是淡入前的纯色画面
+        let image: DynamicImage = match read_animation_info(image_path)? {
+            Some(animation) => load_representative_frame(image_path, &animation)?,
+            None => {
+                // 优先缩放文件管理器已生成的共享缩略图，避免解码原图
+                let shared: Option<DynamicImage> = self.shared_cache
+                    .as_ref()
+                    .and_then(|cache| cache.lookup(image_path, max_side))
+                    .and_then(|path| image::open(path).ok());
+                match shared {
+                    Some(image) => image,
+                    None => self.load_source(image_path, max_side)?,
+                }
+            }
+        };
         
         // 计算缩略图尺寸，保持宽高比
         let (original_width, original_height) = (image.width(), image.height());
-        let (thumb_width, thumb_height) = self.calculate_thumbnail_size(
-            original_width, 
-            original_height
-        );
+        let (thumb_width, thumb_height) = calculate_scaled_size(original_width, original_height, size.0, size.1);
         
         // 生成缩略图
-        let thumbnail: DynamicImage = image.resize(
-            thumb_width,
-            thumb_height,
-            image::imageops::FilterType::Lanczos3,
-        );
+        let thumbnail: DynamicImage = resize_thumbnail(&image, thumb_width, thumb_height);
         
         // 确保缩略图目录存在
-        if let Some(parent) = thumbnail_path.parent() {
-            std::fs::create_dir_all(parent)?;
-        }
+        std::fs::create_dir_all(&self.cache_directory)?;
         
-        // 保存缩略图
-        thumbnail.save_with_format(&thumbnail_path, ImageFormat::Jpeg)?;
+        let thumbnail_path: PathBuf = self.save_thumbnail(&thumbnail, &stem)?;
+        self.record_access(&thumbnail_path, image_path, content_hash, params);
         
         Ok(thumbnail_path)
     }
     
-    fn get_thumbnail_path(&self, image_path: &Path) -> PathBuf {
-        // 使用原图路径的哈希作为缩略图文件名
-        use std::hash::{Hash, Hasher};
-        let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        image_path.hash(&mut hasher);
-        let hash: u64 = hasher.finish();
-        let filename: String = format!("{:x}.jpg", hash);
+    /// 按配置的格式保存缩略图；含透明区域而该格式不支持透明时改用 PNG
+    fn save_thumbnail(&self, thumbnail: &DynamicImage, stem: &str) -> Result<PathBuf> {
+        let format: ThumbnailFormat = if has_transparency(thumbnail) && !self.thumbnail_format.supports_alpha() {
+            ThumbnailFormat::Png
+        } else {
+            self.thumbnail_format
+        };
+        
+        let path: PathBuf = self.cache_directory.join(format!("{}.{}", stem, format.get_image_format().extensions_str()[0]));
+        
+        // 先写入临时文件再重命名，写入中断时不会留下不完整的缩略图
+        let temporary: PathBuf = self.cache_directory.join(format!(
+            "{}.{}-{}.tmp",
+            stem,
+            std::process::id(),
+            TEMPORARY_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        if let Err(e) = Self::write_thumbnail(thumbnail, format, &temporary) {
+            let _ = std::fs::remove_file(&temporary);
+            return Err(e);
+        }
+        std::fs::rename(&temporary, &path)?;
+        Ok(path)
+    }
+    
+    fn write_thumbnail(thumbnail: &DynamicImage, format: ThumbnailFormat, path: &Path) -> Result<()> {
+        let mut writer: BufWriter<File> = BufWriter::new(File::create(path)?);
+        match format {
+            ThumbnailFormat::Jpeg { quality } => {
+                thumbnail.to_rgb8().write_with_encoder(JpegEncoder::new_with_quality(writer, quality.clamp(1, 100)))?;
+            }
+            ThumbnailFormat::Png => {
+                thumbnail.write_with_encoder(PngEncoder::new(writer))?;
+            }
+            ThumbnailFormat::Webp { quality } => {
+                // image 内置的 WebP 编码器只支持无损，有损压缩交给 libwebp
+                let quality: f32 = quality.clamp(1, 100) as f32;
+                let encoded: webp::WebPMemory = if thumbnail.color().has_alpha() {
+                    let pixels: RgbaImage = thumbnail.to_rgba8();
+                    webp::Encoder::from_rgba(&pixels, pixels.width(), pixels.height()).encode(quality)
+                } else {
+                    let pixels: RgbImage = thumbnail.to_rgb8();
+                    webp::Encoder::from_rgb(&pixels, pixels.width(), pixels.height()).encode(quality)
+                };
+                writer.write_all(&encoded)?;
+                writer.flush()?;
+            }
+            ThumbnailFormat::WebpLossless if thumbnail.color().has_alpha() => {
+                thumbnail.to_rgba8().write_with_encoder(WebPEncoder::new_lossless(writer))?;
+            }
+            ThumbnailFormat::WebpLossless => {
+                thumbnail.to_rgb8().write_with_encoder(WebPEncoder::new_lossless(writer))?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// 加载原图（或足够大的缩小图）并按 EXIF 方向旋转，共享缓存可写时同时写入共享缩略图或失败记录
+    fn load_source(&self, image_path: &Path, max_side: u32) -> Result<DynamicImage> {
+        let Some(cache) = &self.shared_cache else {
+            return load_for_thumbnail(image_path, max_side);
+        };
+        if cache.is_failed(image_path) {
+            return Err(WallpaperError::Service(format!("此前已无法解码: {:?}", image_path)));
+        }
+        
+        // 写入共享缓存时至少按共享档位的尺寸加载，避免写入过小的缩略图
+        let load_side: u32 = match cache.get_tier_size(max_side) {
+            Some(tier_size) if cache.is_writable() => tier_size.max(max_side),
+            _ => max_side,
+        };
+        match load_for_thumbnail(image_path, load_side) {
+            Ok(image) => {
+                if cache.is_writable() {
+                    if let Err(e) = cache.store(image_path, &image, max_side) {
+                        log::warn!("写入共享缩略图失败 {:?}: {}", image_path, e);
+                    }
+                }
+                Ok(image)
+            }
+            Err(e) => {
+                if cache.is_writable() && matches!(e, WallpaperError::Image(_)) {
+                    if let Err(e) = cache.store_failure(image_path) {
+                        log::warn!("写入缩略图失败记录失败 {:?}: {}", image_path, e);
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+    
+    /// 原图的内容哈希，文件大小、修改时间或头尾指纹变化时，以及上次计算时修改时间尚未稳定时重新计算
+    fn get_content_hash(&mut self, image_path: &Path) -> Result<String> {
+        let metadata: std::fs::Metadata = std::fs::metadata(image_path)?;
+        let modified: DateTime<Utc> = DateTime::<Utc>::from(metadata.modified()?);
+        let fingerprint: String = file_fingerprint(image_path)?;
+        let key: String = image_path.to_string_lossy().to_string();
+        
+        if let Some(record) = self.manifest.sources.get(&key) {
+            let unchanged: bool = record.file_size == metadata.len()
+                && record.modified == modified
+                && record.fingerprint == fingerprint;
+            if unchanged && record.is_settled() {
+                return Ok(record.content_hash.clone());
+            }
+        }
+        
+        let hash: String = content_hash(image_path)?;
+        self.manifest.sources.insert(key, SourceRecord {
+            file_size: metadata.len(),
+            modified,
+            fingerprint,
+            checked_at: Utc::now(),
+            content_hash: hash.clone(),
+        });
+        self.manifest_dirty = true;
+        Ok(hash)
+    }
+    
+    fn get_params(&self, size: (u32, u32)) -> ThumbnailParams {
+        ThumbnailParams {
+            version: THUMBNAIL_VERSION,
+            size,
+            filter: THUMBNAIL_FILTER.to_string(),
+            format: self.thumbnail_format.get_key(),
+        }
+    }
+    
+    /// 在清单中记录缩略图的访问时间和大小
+    fn record_access(&mut self, thumbnail_path: &Path, image_path: &Path, content_hash: String, params: ThumbnailParams) {
+        let Some(filename) = thumbnail_path.file_name().map(|name| name.to_string_lossy().to_string()) else {
+            return;
+        };
+        let size: u64 = std::fs::metadata(thumbnail_path).map(|metadata| metadata.len()).unwrap_or(0);
+        
+        self.manifest.entries.insert(filename, CacheEntry {
+            source: Some(image_path.to_path_buf()),
+            content_hash,
+            params: Some(params),
+            size,
+            last_access: Utc::now(),
+        });
+        self.manifest_dirty = true;
+    }
+    
+    /// 保存缓存清单（如有修改）
+    pub fn flush_manifest(&mut self) -> Result<()> {
+        if self.manifest_dirty {
+            save_toml_file(&self.cache_directory.join(MANIFEST_FILENAME), &self.manifest)?;
+            self.manifest_dirty = false;
+        }
+        Ok(())
+    }
+    
+    /// 清理缩略图缓存：删除原图已不存在的缩略图，缓存超出上限时按最久未使用的顺序删除
+    pub fn prune_cache(&mut self) -> Result<EvictionReport> {
+        let mut report: EvictionReport = EvictionReport::default();
+        let mut entries: BTreeMap<String, CacheEntry> = BTreeMap::new();
+        
+        // 以磁盘上的文件为准：清单中缺失的文件被忽略，清单外的文件以修改时间作为访问时间
+        for entry in std::fs::read_dir(&self.cache_directory)? {
+            let entry: std::fs::DirEntry = entry?;
+            let filename: String = entry.file_name().to_string_lossy().to_string();
+            let metadata: std::fs::Metadata = entry.metadata()?;
+            if !metadata.is_file() || filename == MANIFEST_FILENAME || filename.ends_with(".tmp") {
+                continue;
+            }
+            
+            let cache_entry: CacheEntry = match self.manifest.entries.remove(&filename) {
+                Some(cache_entry) => CacheEntry {
+                    size: metadata.len(),
+                    ..cache_entry
+                },
+                None => CacheEntry {
+                    source: None,
+                    content_hash: String::new(),
+                    params: None,
+                    size: metadata.len(),
+                    last_access: metadata.modified().map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now()),
+                },
+            };
+            entries.insert(filename, cache_entry);
+        }
+        
+        // 清单中记录的参数与当前参数不同的缩略图不会再被使用
+        let stale: Vec<String> = entries
+            .iter()
+            .filter(|(_, entry)| entry.source.is_some() && !entry.params.as_ref().is_some_and(|params| self.is_current(params)))
+            .map(|(filename, _)| filename.clone())
+            .collect();
+        for filename in stale {
+            if let Some(entry) = self.remove_cached_file(&mut entries, &filename) {
+                report.stale_removed += 1;
+                report.freed_bytes += entry.size;
+            }
+        }
+        
+        let orphans: Vec<String> = entries
+            .iter()
+            .filter(|(_, entry)| entry.source.as_ref().is_some_and(|source| !source.exists()))
+            .map(|(filename, _)| filename.clone())
+            .collect();
+        for filename in orphans {
+            if let Some(entry) = self.remove_cached_file(&mut entries, &filename) {
+                report.orphans_removed += 1;
+                report.freed_bytes += entry.size;
+            }
+        }
+        
+        let mut total: u64 = entries.values().map(|entry| entry.size).sum();
+        if self.max_cache_size > 0 && total > self.max_cache_size {
+            let mut by_access: Vec<(String, DateTime<Utc>)> = entries
+                .iter()
+                .map(|(filename, entry)| (filename.clone(), entry.last_access))
+                .collect();
+            by_access.sort_by_key(|(_, last_access)| *last_access);
+            
+            for (filename, _) in by_access {
+                if total <= self.max_cache_size {
+                    break;
+                }
+                if let Some(entry) = self.remove_cached_file(&mut entries, &filename) {
+                    report.evicted += 1;
+                    report.freed_bytes += entry.size;
+                    total -= entry.size;
+                }
+            }
+        }
+        
+        report.remaining_bytes = total;
+        self.manifest.entries = entries;
+        self.manifest.sources.retain(|path, _| Path::new(path).exists());
+        self.manifest_dirty = true;
+        self.flush_manifest()?;
+        
+        Ok(report)
+    }
+    
+    fn remove_cached_file(&self, entries: &mut BTreeMap<String, CacheEntry>, filename: &str) -> Option<CacheEntry> {
+        match safe_remove_file(&self.cache_directory.join(filename)) {
+            Ok(()) => entries.remove(filename),
+            Err(e) => {
+                log::warn!("删除缩略图 {} 失败: {}", filename, e);
+                None
+            }
+        }
+    }
+    
+    /// 缩略图文件名由原图内容哈希和生成参数决定，与原图路径无关，
+    /// 因此移动或重命名原图后仍可复用，内容相同的原图共用一个缩略图
+    fn get_thumbnail_stem(&self, content_hash: &str, params: &ThumbnailParams) -> String {
+        let key: String = format!(
+            "{}|{}|{}x{}|{}|{}",
+            params.version, content_hash, params.size.0, params.size.1, params.filter, params.format
+        );
+        format!("{:016x}", stable_hash(key.as_bytes()))
+    }
+    
+    /// 查找已有的缩略图，透明图片可能已改用 PNG 保存，因此两种扩展名都要检查
+    fn find_thumbnail(&self, stem: &str, content_hash: &str, params: &ThumbnailParams) -> Option<PathBuf> {
+        [self.thumbnail_format, ThumbnailFormat::Png]
+            .iter()
+            .map(|format| self.cache_directory.join(format!("{}.{}", stem, format.get_image_format().extensions_str()[0])))
+            .find(|path| self.is_thumbnail_valid(path, content_hash, params))
+    }
+    
+    /// 清单中有记录时还要核对内容哈希和参数，防止文件名哈希冲突；
+    /// 清单丢失时文件名本身已包含这些信息，直接沿用
+    fn is_thumbnail_valid(&self, thumbnail_path: &Path, content_hash: &str, params: &ThumbnailParams) -> bool {
+        // 空文件来自旧版本中断的写入
+        if !std::fs::metadata(thumbnail_path).is_ok_and(|metadata| metadata.is_file() && metadata.len() > 0) {
+            return false;
+        }
         
-        self.cache_directory.join(filename)
+        let filename: String = thumbnail_path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        match self.manifest.entries.get(&filename) {
+            Some(entry) => entry.content_hash == content_hash && entry.params.as_ref() == Some(params),
+            None => true,
+        }
+    }
+    
+    /// 参数是否仍会被使用：默认尺寸或任一档位，且其余生成参数与当前一致
+    fn is_current(&self, params: &ThumbnailParams) -> bool {
+        let size: (u32, u32) = params.size;
+        let known_size: bool = size == self.thumbnail_size || (size.0 == size.1 && THUMBNAIL_TIERS.contains(&size.0));
+        known_size && *params == self.get_params(size)
     }
     
-    fn is_thumbnail_valid(&self, thumbnail_path: &Path, original_path: &Path) -> Result<bool> {
-        if !thumbnail_path.exists() {
-            return Ok(false);
+    /// 原图被删除后立即删除其所有尺寸的缩略图，内容相同的其他原图仍在使用时保留
+    pub fn remove_source(&mut self, image_path: &Path) -> Result<usize> {
+        let Some(record) = self.manifest.sources.remove(&image_path.to_string_lossy().to_string()) else {
+            return Ok(0);
+        };
+        self.manifest_dirty = true;
+        let shared: bool = self.manifest.sources
+            .iter()
+            .any(|(path, other)| other.content_hash == record.content_hash && Path::new(path).exists());
+        
+        let mut removed: usize = 0;
+        if !shared {
+            let filenames: Vec<String> = self.manifest.entries
+                .iter()
+                .filter(|(_, entry)| entry.content_hash == record.content_hash)
+                .map(|(filename, _)| filename.clone())
+                .collect();
+            let mut entries: BTreeMap<String, CacheEntry> = std::mem::take(&mut self.manifest.entries);
+            for filename in filenames {
+                if self.remove_cached_file(&mut entries, &filename).is_some() {
+                    removed += 1;
+                }
+            }
+            self.manifest.entries = entries;
         }
         
-        let thumbnail_metadata = std::fs::metadata(thumbnail_path)?;
-        let original_metadata = std::fs::metadata(original_path)?;
+        self.flush_manifest()?;
+        Ok(removed)
+    }
+    
+    /// 校验缓存中的每个缩略图：完整读取并解码，统计数量、大小、孤立和损坏的缩略图
+    pub fn verify_cache(&self) -> Result<CacheStats> {
+        let mut stats: CacheStats = CacheStats::default();
         
-        // 检查缩略图是否比原图新
-        Ok(thumbnail_metadata.modified()? >= original_metadata.modified()?)
+        for entry in std::fs::read_dir(&self.cache_directory)? {
+            let entry: std::fs::DirEntry = entry?;
+            let filename: String = entry.file_name().to_string_lossy().to_string();
+            let metadata: std::fs::Metadata = entry.metadata()?;
+            if !metadata.is_file() || filename == MANIFEST_FILENAME {
+                continue;
+            }
+            if filename.ends_with(".tmp") {
+                stats.temporary_files += 1;
+                continue;
+            }
+            
+            stats.count += 1;
+            stats.total_bytes += metadata.len();
+            let orphaned: bool = self.manifest.entries
+                .get(&filename)
+                .and_then(|cache_entry| cache_entry.source.as_ref())
+                .is_some_and(|source| !source.exists());
+            if orphaned {
+                stats.orphaned += 1;
+            }
+            if !std::fs::read(entry.path()).is_ok_and(|data| is_thumbnail_intact(&data)) {
+                stats.broken.push(filename);
+            }
+        }
+        
+        Ok(stats)
     }
     
-    fn calculate_thumbnail_size(&self, original_width: u32, original_height: u32) -> (u32, u32) {
-        let (max_width, max_height) = self.thumbnail_size;
+    /// 修复缓存：损坏的缩略图从原图重新生成，原图缺失或无法解码时直接删除，
+    /// 同时删除其他进程中断写入遗留的临时文件
+    pub fn repair_cache(&mut self) -> Result<RepairReport> {
+        let mut report: RepairReport = RepairReport::default();
+        let stats: CacheStats = self.verify_cache()?;
         
-        let width_ratio: f32 = max_width as f32 / original_width as f32;
-        let height_ratio: f32 = max_height as f32 / original_height as f32;
-        let ratio: f32 = width_ratio.min(height_ratio);
+        if stats.temporary_files > 0 {
+            let own_prefix: String = format!(".{}-", std::process::id());
+            for entry in std::fs::read_dir(&self.cache_directory)? {
+                let filename: String = entry?.file_name().to_string_lossy().to_string();
+                if filename.ends_with(".tmp") && !filename.contains(&own_prefix) {
+                    match safe_remove_file(&self.cache_directory.join(&filename)) {
+                        Ok(()) => report.temporary_removed += 1,
+                        Err(e) => log::warn!("删除临时文件 {} 失败: {}", filename, e),
+                    }
+                }
+            }
+        }
+        
+        for filename in stats.broken {
+            safe_remove_file(&self.cache_directory.join(&filename))?;
+            self.manifest_dirty = true;
+            let regenerated: bool = match self.manifest.entries.remove(&filename) {
+                Some(CacheEntry { source: Some(source), params: Some(params), .. }) if source.exists() && self.is_current(&params) => {
+                    match self.generate_sized(&source, params.size, false) {
+                        Ok(_) => true,
+                        Err(e) => {
+                            log::warn!("重新生成 {:?} 的缩略图失败: {}", source, e);
+                            false
+                        }
+                    }
+                }
+                _ => false,
+            };
+            if regenerated {
+                report.regenerated += 1;
+            } else {
+                report.removed += 1;
+            }
+        }
+        
+        self.flush_manifest()?;
+        Ok(report)
+    }
+    
+    /// 重新生成缩略图：清单中仍会使用的缩略图（包括各档位）以及 `sources` 的默认尺寸缩略图
+    ///
+    /// 每完成一个缩略图调用一次 `progress`，其返回 `false` 时停止重建
+    pub fn rebuild_cache<F: FnMut(RebuildProgress) -> bool>(&mut self, sources: &[PathBuf], progress: F) -> Result<RebuildProgress> {
+        let state: RebuildProgress = self.regenerate_all(sources, progress);
+        // 不再使用的缩略图（旧参数、原图已删除）在重建后统一清理
+        self.prune_cache()?;
+        Ok(state)
+    }
+    
+    /// 原地覆盖重新生成缩略图，不清空缓存目录，重建期间其他服务仍可读写缓存；不保存清单
+    fn regenerate_all<F: FnMut(RebuildProgress) -> bool>(&mut self, sources: &[PathBuf], mut progress: F) -> RebuildProgress {
+        let mut jobs: BTreeSet<(PathBuf, (u32, u32))> = self.manifest.entries
+            .values()
+            .filter_map(|entry| match (&entry.source, &entry.params) {
+                (Some(source), Some(params)) if self.is_current(params) => Some((source.clone(), params.size)),
+                _ => None,
+            })
+            .collect();
+        jobs.extend(sources.iter().map(|source| (source.clone(), self.thumbnail_size)));
+        jobs.retain(|(source, _)| source.exists());
         
-        let new_width: u32 = (original_width as f32 * ratio) as u32;
-        let new_height: u32 = (original_height as f32 * ratio) as u32;
+        let mut state: RebuildProgress = RebuildProgress {
+            total: jobs.len(),
+            ..RebuildProgress::default()
+        };
+        for (source, size) in jobs {
+            if let Err(e) = self.generate_sized(&source, size, true) {
+                log::warn!("重建 {:?} 的缩略图失败: {}", source, e);
+                state.failed += 1;
+            }
+            state.completed += 1;
+            if !progress(state) {
+                log::info!("缩略图重建已取消，完成 {}/{}", state.completed, state.total);
+                break;
+            }
+        }
+        state
+    }
+    
+    /// 在后台线程中重建缓存，见 [`Self::rebuild_cache`]
+    ///
+    /// 后台服务不写入清单也不清理缓存，结束后由前台服务通过 [`Self::merge_rebuild`] 合并其记录
+    pub fn spawn_rebuild(mut self, sources: Vec<PathBuf>) -> CacheRebuild {
+        let (sender, receiver) = mpsc::channel::<RebuildProgress>();
+        let cancelled: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let flag: Arc<AtomicBool> = Arc::clone(&cancelled);
+        let handle: JoinHandle<Result<(ThumbnailService, RebuildProgress)>> = std::thread::spawn(move || {
+            let state: RebuildProgress = self.regenerate_all(&sources, |state| {
+                let _ = sender.send(state);
+                !flag.load(Ordering::Relaxed)
+            });
+            Ok((self, state))
+        });
         
-        (new_width.max(1), new_height.max(1))
+        CacheRebuild {
+            receiver,
+            progress: RebuildProgress::default(),
+            cancelled,
+            handle,
+        }
+    }
+    
+    /// 合并后台重建服务的清单记录，保留重建期间前台生成或访问的记录，然后清理不再使用的缩略图
+    pub fn merge_rebuild(&mut self, rebuilt: ThumbnailService) -> Result<EvictionReport> {
+        for (filename, entry) in rebuilt.manifest.entries {
+            match self.manifest.entries.get_mut(&filename) {
+                Some(existing) => {
+                    existing.size = entry.size;
+                    existing.last_access = existing.last_access.max(entry.last_access);
+                }
+                None => {
+                    self.manifest.entries.insert(filename, entry);
+                }
+            }
+        }
+        for (path, record) in rebuilt.manifest.sources {
+            self.manifest.sources.entry(path).or_insert(record);
+        }
+        self.manifest_dirty = true;
+        self.prune_cache()
     }
     
-    pub fn clear_cache(&self) -> Result<()> {
+    pub fn clear_cache(&mut self) -> Result<()> {
         if self.cache_directory.exists() {
             std::fs::remove_dir_all(&self.cache_directory)?;
             std::fs::create_dir_all(&self.cache_directory)?;
         }
+        self.manifest = CacheManifest::default();
+        self.manifest_dirty = false;
         Ok(())
     }
     
@@ -120,4 +787,335 @@ impl ThumbnailService {
         
         Ok(total_size)
     }
-} 
\ No newline at end of file
+} 
+
+/// 缩略图文件是否完整：检查各格式的结尾标记（中断的写入会截断文件尾部）后再完整解码
+fn is_thumbnail_intact(data: &[u8]) -> bool {
+    let complete: bool = match image::guess_format(data) {
+        Ok(image::ImageFormat::Jpeg) => data.ends_with(&[0xFF, 0xD9]),
+        Ok(image::ImageFormat::Png) => data.len() >= 12 && &data[data.len() - 8..data.len() - 4] == b"IEND",
+        Ok(image::ImageFormat::WebP) => data.len() >= 12
+            && u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize + 8 == data.len(),
+        Ok(_) => true,
+        Err(_) => false,
+    };
+    complete && image::load_from_memory(data).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_prune_cache() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-thumbnails-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let config: Config = Config {
+            cache_directory: directory.join("cache"),
+            thumbnail_size: (32, 32),
+            ..Config::default()
+        };
+        
+        let sources: Vec<PathBuf> = (0..3).map(|i| directory.join(format!("{}.png", i))).collect();
+        for (i, source) in sources.iter().enumerate() {
+            image::RgbImage::from_pixel(64, 64, image::Rgb([i as u8 * 80, 0, 0])).save(source).unwrap();
+        }
+        
+        let mut service: ThumbnailService = ThumbnailService::new(&config).unwrap();
+        let thumbnails: Vec<PathBuf> = sources.iter().map(|source| service.generate_thumbnail(source).unwrap()).collect();
+        
+        // 0 号最久未使用，2 号的原图被删除
+        let start: DateTime<Utc> = Utc::now() - Duration::hours(3);
+        for (i, entry) in service.manifest.entries.values_mut().enumerate() {
+            entry.last_access = start + Duration::hours(i as i64);
+        }
+        let oldest: String = service.manifest.entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_access)
+            .map(|(filename, _)| filename.clone())
+            .unwrap();
+        let (orphan, orphan_thumbnail): (&PathBuf, &PathBuf) = sources
+            .iter()
+            .zip(&thumbnails)
+            .find(|(_, thumbnail)| thumbnail.file_name().unwrap().to_string_lossy() != oldest)
+            .unwrap();
+        std::fs::remove_file(orphan).unwrap();
+        
+        let sizes: Vec<u64> = thumbnails.iter().map(|path| std::fs::metadata(path).unwrap().len()).collect();
+        service.max_cache_size = sizes.iter().max().copied().unwrap();
+        let report: EvictionReport = service.prune_cache().unwrap();
+        assert_eq!((report.orphans_removed, report.evicted), (1, 1));
+        assert_eq!(report.freed_bytes + report.remaining_bytes, sizes.iter().sum::<u64>());
+        assert!(!service.cache_directory.join(&oldest).exists());
+        assert!(!orphan_thumbnail.exists());
+        
+        // 清单已保存，重新加载后只剩一个缩略图
+        let reloaded: ThumbnailService = ThumbnailService::new(&config).unwrap();
+        assert_eq!(reloaded.manifest.entries.len(), 1);
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_thumbnail_keys() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-thumbnail-keys-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let config: Config = Config {
+            cache_directory: directory.join("cache"),
+            thumbnail_size: (32, 32),
+            ..Config::default()
+        };
+        
+        let source: PathBuf = directory.join("a.png");
+        image::RgbImage::from_pixel(64, 64, image::Rgb([200, 0, 0])).save(&source).unwrap();
+        let mut service: ThumbnailService = ThumbnailService::new(&config).unwrap();
+        let thumbnail: PathBuf = service.generate_thumbnail(&source).unwrap();
+        
+        // 移动原图后复用同一个缩略图
+        let moved: PathBuf = directory.join("b.png");
+        std::fs::rename(&source, &moved).unwrap();
+        assert_eq!(service.generate_thumbnail(&moved).unwrap(), thumbnail);
+        
+        // 内容变化时即使修改时间不变也重新生成
+        let modified: std::time::SystemTime = std::fs::metadata(&moved).unwrap().modified().unwrap();
+        image::RgbImage::from_pixel(64, 64, image::Rgb([0, 0, 200])).save(&moved).unwrap();
+        std::fs::File::options().write(true).open(&moved).unwrap().set_modified(modified).unwrap();
+        let edited: PathBuf = service.generate_thumbnail(&moved).unwrap();
+        assert_ne!(edited, thumbnail);
+        service.flush_manifest().unwrap();
+        
+        // 缩略图尺寸变化后旧缩略图被视为过期
+        let mut resized: ThumbnailService = ThumbnailService::new(&Config {
+            thumbnail_size: (16, 16),
+            ..config.clone()
+        }).unwrap();
+        let small: PathBuf = resized.generate_thumbnail(&moved).unwrap();
+        assert_ne!(small, edited);
+        assert_eq!(image::image_dimensions(&small).unwrap(), (16, 16));
+        let report: EvictionReport = resized.prune_cache().unwrap();
+        assert_eq!(report.stale_removed, 2);
+        assert!(small.exists() && !edited.exists());
+        
+        // 各档位分别缓存，清理时不视为过期
+        let tier: PathBuf = resized.generate_thumbnail_tier(&moved, 128).unwrap();
+        assert_eq!(image::image_dimensions(&tier).unwrap(), (128, 128));
+        assert!(resized.generate_thumbnail_tier(&moved, 100).is_err());
+        assert_eq!(resized.prune_cache().unwrap().stale_removed, 0);
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_rewrite_with_same_size_and_mtime() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-thumbnail-rewrite-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let config: Config = Config {
+            cache_directory: directory.join("cache"),
+            thumbnail_size: (32, 32),
+            ..Config::default()
+        };
+        let mut service: ThumbnailService = ThumbnailService::new(&config).unwrap();
+        // BMP 不压缩，尺寸相同的图片文件长度相同
+        let rewrite = |path: &Path, image: &image::RgbImage, modified: std::time::SystemTime| {
+            image.save(path).unwrap();
+            std::fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+        };
+        
+        // 修改时间早已稳定：头尾指纹发现改写
+        let old: std::time::SystemTime = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        let source: PathBuf = directory.join("a.bmp");
+        rewrite(&source, &image::RgbImage::from_pixel(64, 64, image::Rgb([200, 0, 0])), old);
+        let red: PathBuf = service.generate_thumbnail(&source).unwrap();
+        rewrite(&source, &image::RgbImage::from_pixel(64, 64, image::Rgb([0, 0, 200])), old);
+        let blue: PathBuf = service.generate_thumbnail(&source).unwrap();
+        assert_ne!(blue, red);
+        assert!(image::open(&blue).unwrap().to_rgb8().get_pixel(16, 16)[2] > 150);
+        
+        // 刚写入的文件：只改动头尾 64 KiB 之外的内容，修改时间不变也会重新计算
+        let source: PathBuf = directory.join("b.bmp");
+        let plain: image::RgbImage = image::RgbImage::from_pixel(256, 256, image::Rgb([0, 200, 0]));
+        plain.save(&source).unwrap();
+        let plain_fingerprint: String = file_fingerprint(&source).unwrap();
+        let modified: std::time::SystemTime = std::fs::metadata(&source).unwrap().modified().unwrap();
+        let before: PathBuf = service.generate_thumbnail(&source).unwrap();
+        let striped: image::RgbImage = image::RgbImage::from_fn(256, 256, |_, y| {
+            if (100..150).contains(&y) { image::Rgb([0, 0, 0]) } else { image::Rgb([0, 200, 0]) }
+        });
+        rewrite(&source, &striped, modified);
+        assert_eq!(file_fingerprint(&source).unwrap(), plain_fingerprint);
+        assert_ne!(service.generate_thumbnail(&source).unwrap(), before);
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_thumbnail_formats() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-thumbnail-formats-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let config: Config = Config {
+            cache_directory: directory.join("cache"),
+            thumbnail_size: (32, 32),
+            ..Config::default()
+        };
+        
+        let opaque: PathBuf = directory.join("opaque.png");
+        image::RgbImage::from_pixel(64, 64, image::Rgb([0, 120, 0])).save(&opaque).unwrap();
+        let transparent: PathBuf = directory.join("transparent.png");
+        let mut pixels: image::RgbaImage = image::RgbaImage::from_pixel(64, 64, image::Rgba([200, 0, 0, 255]));
+        for x in 0..32 {
+            for y in 0..64 {
+                pixels.put_pixel(x, y, image::Rgba([0, 0, 0, 0]));
+            }
+        }
+        pixels.save(&transparent).unwrap();
+        
+        // JPEG 不支持透明，透明图片改用 PNG 并保留透明度
+        let mut service: ThumbnailService = ThumbnailService::new(&config).unwrap();
+        assert_eq!(service.generate_thumbnail(&opaque).unwrap().extension().unwrap(), "jpg");
+        let thumbnail: PathBuf = service.generate_thumbnail(&transparent).unwrap();
+        assert_eq!(thumbnail.extension().unwrap(), "png");
+        assert_eq!(image::open(&thumbnail).unwrap().to_rgba8().get_pixel(0, 0)[3], 0);
+        assert_eq!(service.generate_thumbnail(&transparent).unwrap(), thumbnail);
+        
+        let mut webp: ThumbnailService = ThumbnailService::new(&Config {
+            thumbnail_format: ThumbnailFormat::WebpLossless,
+            ..config.clone()
+        }).unwrap();
+        let thumbnail: PathBuf = webp.generate_thumbnail(&transparent).unwrap();
+        assert_eq!(thumbnail.extension().unwrap(), "webp");
+        assert_eq!(image::open(&thumbnail).unwrap().to_rgba8().get_pixel(0, 0)[3], 0);
+        
+        // 有损 WebP 同样保留透明度，且与无损结果分开缓存
+        let mut lossy: ThumbnailService = ThumbnailService::new(&Config {
+            thumbnail_format: ThumbnailFormat::Webp { quality: 75 },
+            ..config.clone()
+        }).unwrap();
+        let lossy_thumbnail: PathBuf = lossy.generate_thumbnail(&transparent).unwrap();
+        assert_eq!(lossy_thumbnail.extension().unwrap(), "webp");
+        assert_ne!(lossy_thumbnail, thumbnail);
+        let decoded: image::RgbaImage = image::open(&lossy_thumbnail).unwrap().to_rgba8();
+        assert_eq!(decoded.dimensions(), (32, 32));
+        assert_eq!(decoded.get_pixel(0, 0)[3], 0);
+        assert_eq!(decoded.get_pixel(31, 16)[3], u8::MAX);
+        assert!(lossy.generate_thumbnail(&opaque).unwrap().exists());
+        
+        // 质量参与缓存键
+        let mut low_quality: ThumbnailService = ThumbnailService::new(&Config {
+            thumbnail_format: ThumbnailFormat::Jpeg { quality: 40 },
+            ..config.clone()
+        }).unwrap();
+        assert_ne!(low_quality.generate_thumbnail(&opaque).unwrap(), service.generate_thumbnail(&opaque).unwrap());
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_cache_maintenance() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-thumbnail-maintenance-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let config: Config = Config {
+            cache_directory: directory.join("cache"),
+            thumbnail_size: (32, 32),
+            ..Config::default()
+        };
+        
+        let sources: Vec<PathBuf> = (0..3).map(|i| directory.join(format!("{}.png", i))).collect();
+        for (i, source) in sources.iter().enumerate() {
+            image::RgbImage::from_pixel(64, 64, image::Rgb([0, i as u8 * 80, 0])).save(source).unwrap();
+        }
+        let mut service: ThumbnailService = ThumbnailService::new(&config).unwrap();
+        let thumbnails: Vec<PathBuf> = sources.iter().map(|source| service.generate_thumbnail(source).unwrap()).collect();
+        let stats: CacheStats = service.verify_cache().unwrap();
+        assert_eq!((stats.count, stats.orphaned, stats.broken.len(), stats.temporary_files), (3, 0, 0, 0));
+        
+        // 0 号被截断，1 号为空文件且原图已删除，另有一个其他进程遗留的临时文件
+        let data: Vec<u8> = std::fs::read(&thumbnails[0]).unwrap();
+        std::fs::write(&thumbnails[0], &data[..data.len() / 2]).unwrap();
+        std::fs::write(&thumbnails[1], b"").unwrap();
+        std::fs::remove_file(&sources[1]).unwrap();
+        std::fs::write(service.cache_directory.join("0000000000000000.1-0.tmp"), b"partial").unwrap();
+        let stats: CacheStats = service.verify_cache().unwrap();
+        assert_eq!((stats.count, stats.orphaned, stats.broken.len(), stats.temporary_files), (3, 1, 2, 1));
+        assert!(!is_thumbnail_intact(&data[..data.len() - 2]));
+        assert!(is_thumbnail_intact(&data));
+        
+        let report: RepairReport = service.repair_cache().unwrap();
+        assert_eq!(report, RepairReport { regenerated: 1, removed: 1, temporary_removed: 1 });
+        assert!(image::open(&thumbnails[0]).is_ok() && !thumbnails[1].exists());
+        let stats: CacheStats = service.verify_cache().unwrap();
+        assert_eq!((stats.count, stats.broken.len(), stats.temporary_files), (2, 0, 0));
+        
+        // 后台重建包括清单中的档位缩略图，原地覆盖而不清空缓存目录
+        let tier: PathBuf = service.generate_thumbnail_tier(&sources[2], 128).unwrap();
+        service.flush_manifest().unwrap();
+        std::fs::write(&thumbnails[2], b"").unwrap();
+        let manifest: Vec<u8> = std::fs::read(service.cache_directory.join(MANIFEST_FILENAME)).unwrap();
+        let mut rebuild: CacheRebuild = ThumbnailService::new(&config)
+            .unwrap()
+            .spawn_rebuild(vec![sources[0].clone(), sources[1].clone()]);
+        // 重建期间前台生成的缩略图在合并后仍保留在清单中
+        let foreground: PathBuf = service.generate_thumbnail_tier(&sources[0], 256).unwrap();
+        while !rebuild.is_finished() {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert_eq!(rebuild.get_progress(), RebuildProgress { completed: 3, total: 3, failed: 0 });
+        let (rebuilt, progress): (ThumbnailService, RebuildProgress) = rebuild.wait().unwrap();
+        assert_eq!(progress.completed, 3);
+        assert_eq!(std::fs::read(service.cache_directory.join(MANIFEST_FILENAME)).unwrap(), manifest);
+        
+        service.merge_rebuild(rebuilt).unwrap();
+        assert!(tier.exists() && foreground.exists() && std::fs::metadata(&thumbnails[2]).unwrap().len() > 0);
+        let foreground_name: String = foreground.file_name().unwrap().to_string_lossy().to_string();
+        assert!(ThumbnailService::new(&config).unwrap().manifest.entries.contains_key(&foreground_name));
+        let stats: CacheStats = service.verify_cache().unwrap();
+        assert_eq!((stats.count, stats.broken.len()), (4, 0));
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_remove_source() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-thumbnail-remove-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        let config: Config = Config {
+            cache_directory: directory.join("cache"),
+            thumbnail_size: (32, 32),
+            ..Config::default()
+        };
+        
+        let image: image::RgbImage = image::RgbImage::from_pixel(64, 64, image::Rgb([200, 0, 0]));
+        let sources: Vec<PathBuf> = vec![directory.join("a.png"), directory.join("b.png"), directory.join("c.png")];
+        image.save(&sources[0]).unwrap();
+        image.save(&sources[1]).unwrap();
+        image::RgbImage::from_pixel(64, 64, image::Rgb([0, 200, 0])).save(&sources[2]).unwrap();
+        
+        let mut service: ThumbnailService = ThumbnailService::new(&config).unwrap();
+        let thumbnails: Vec<PathBuf> = sources.iter().map(|source| service.generate_thumbnail(source).unwrap()).collect();
+        assert_eq!(thumbnails[0], thumbnails[1]);
+        
+        // a 与 b 内容相同，删除 a 后缩略图仍供 b 使用
+        std::fs::remove_file(&sources[0]).unwrap();
+        assert_eq!(service.remove_source(&sources[0]).unwrap(), 0);
+        assert!(thumbnails[0].exists());
+        
+        std::fs::remove_file(&sources[2]).unwrap();
+        assert_eq!(service.remove_source(&sources[2]).unwrap(), 1);
+        assert!(!thumbnails[2].exists());
+        assert_eq!(service.remove_source(&sources[2]).unwrap(), 0);
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,292 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use crate::{Result, WallpaperError};
+use crate::config::Config;
+use crate::models::usage::{UsageEntry, UsageStats, MAX_RATING};
+use crate::models::Wallpaper;
+use crate::utils::{load_toml_file, save_toml_file};
+
+/// 最多保留的使用记录条数，超出时丢弃最早的记录
+const MAX_HISTORY_ENTRIES: usize = 10_000;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UsageStore {
+    /// 壁纸ID到星级的映射，未评分的壁纸不记录
+    #[serde(default)]
+    ratings: BTreeMap<String, u8>,
+    #[serde(default)]
+    favorites: BTreeSet<String>,
+    /// 按设置时间从早到晚排列
+    #[serde(default)]
+    history: Vec<UsageEntry>,
+}
+
+/// 评分、收藏和使用历史服务
+///
+/// 修改后立即写入 `usage.toml`
+pub struct UsageService {
+    store_path: PathBuf,
+    ratings: BTreeMap<String, u8>,
+    favorites: BTreeSet<String>,
+    history: Vec<UsageEntry>,
+}
+
+impl UsageService {
+    pub fn new(config: &Config) -> Result<Self> {
+        let store_path: PathBuf = config.data_directory.join("usage.toml");
+        let store: UsageStore = load_toml_file(&store_path)?.unwrap_or_default();
+        
+        Ok(Self {
+            store_path,
+            ratings: store.ratings,
+            favorites: store.favorites,
+            history: store.history,
+        })
+    }
+    
+    pub fn get_rating(&self, wallpaper_id: &str) -> u8 {
+        self.ratings.get(wallpaper_id).copied().unwrap_or(0)
+    }
+    
+    pub fn is_favorite(&self, wallpaper_id: &str) -> bool {
+        self.favorites.contains(wallpaper_id)
+    }
+    
+    /// 设置星级（0-5），0 表示清除评分
+    pub fn set_rating(&mut self, wallpaper_ids: &[String], rating: u8) -> Result<()> {
+        if rating > MAX_RATING {
+            return Err(WallpaperError::Service(format!("评分必须在 0 到 {} 之间: {}", MAX_RATING, rating)));
+        }
+        
+        for id in wallpaper_ids {
+            if rating == 0 {
+                self.ratings.remove(id);
+            } else {
+                self.ratings.insert(id.clone(), rating);
+            }
+        }
+        self.save()
+    }
+    
+    pub fn set_favorite(&mut self, wallpaper_ids: &[String], favorite: bool) -> Result<()> {
+        for id in wallpaper_ids {
+            if favorite {
+                self.favorites.insert(id.clone());
+            } else {
+                self.favorites.remove(id);
+            }
+        }
+        self.save()
+    }
+    
+    /// 记录壁纸被设置为桌面壁纸，同时结束上一张壁纸的使用
+    pub fn record_applied(&mut self, wallpaper_id: &str, applied_at: DateTime<Utc>) -> Result<()> {
+        self.close_current(applied_at);
+        self.history.push(UsageEntry::new(wallpaper_id.to_string(), applied_at));
+        
+        if self.history.len() > MAX_HISTORY_ENTRIES {
+            let excess: usize = self.history.len() - MAX_HISTORY_ENTRIES;
+            self.history.drain(..excess);
+        }
+        self.save()
+    }
+    
+    /// 结束当前壁纸的使用，例如停止轮换或退出程序时
+    pub fn finish_current(&mut self, ended_at: DateTime<Utc>) -> Result<()> {
+        if self.close_current(ended_at) {
+            self.save()?;
+        }
+        Ok(())
+    }
+    
+    /// 当前正在使用的壁纸记录
+    pub fn get_current(&self) -> Option<&UsageEntry> {
+        self.history.last().filter(|entry| entry.ended_at.is_none())
+    }
+    
+    pub fn get_history(&self) -> &[UsageEntry] {
+        &self.history
+    }
+    
+    pub fn is_used(&self, wallpaper_id: &str) -> bool {
+        self.history.iter().any(|entry| entry.wallpaper_id == wallpaper_id)
+    }
+    
+    pub fn get_stats(&self, wallpaper_id: &str, now: DateTime<Utc>) -> UsageStats {
+        let mut stats: UsageStats = UsageStats::new(wallpaper_id.to_string());
+        for entry in self.history.iter().filter(|entry| entry.wallpaper_id == wallpaper_id) {
+            stats.add_entry(entry, now);
+        }
+        stats
+    }
+    
+    /// 最常用的壁纸，按累计使用时长从长到短排列，时长相同时按使用次数
+    pub fn get_most_used(&self, limit: usize, now: DateTime<Utc>) -> Vec<UsageStats> {
+        let mut stats: HashMap<&str, UsageStats> = HashMap::new();
+        for entry in &self.history {
+            stats.entry(entry.wallpaper_id.as_str())
+                .or_insert_with(|| UsageStats::new(entry.wallpaper_id.clone()))
+                .add_entry(entry, now);
+        }
+        
+        let mut stats: Vec<UsageStats> = stats.into_values().collect();
+        stats.sort_by(|a, b| {
+            b.total_duration.cmp(&a.total_duration)
+                .then(b.apply_count.cmp(&a.apply_count))
+                .then(a.wallpaper_id.cmp(&b.wallpaper_id))
+        });
+        stats.truncate(limit);
+        stats
+    }
+    
+    /// 最近使用的壁纸ID（不重复），最近的在前
+    pub fn get_recently_applied(&self, limit: usize) -> Vec<&str> {
+        let mut seen: BTreeSet<&str> = BTreeSet::new();
+        self.history
+            .iter()
+            .rev()
+            .map(|entry| entry.wallpaper_id.as_str())
+            .filter(|id| seen.insert(id))
+            .take(limit)
+            .collect()
+    }
+    
+    /// 将评分和收藏状态写入壁纸模型
+    pub fn apply_to(&self, wallpapers: &mut [Wallpaper]) {
+        for wallpaper in wallpapers {
+            wallpaper.rating = self.get_rating(&wallpaper.id);
+            wallpaper.favorite = self.is_favorite(&wallpaper.id);
+        }
+    }
+    
+    /// 壁纸ID变化后迁移其评分、收藏和使用历史
+    pub fn replace_ids(&mut self, renamed: &HashMap<String, String>) -> Result<()> {
+        if renamed.is_empty() {
+            return Ok(());
+        }
+        
+        for (old_id, new_id) in renamed {
+            if let Some(rating) = self.ratings.remove(old_id) {
+                self.ratings.insert(new_id.clone(), rating);
+            }
+            if self.favorites.remove(old_id) {
+                self.favorites.insert(new_id.clone());
+            }
+        }
+        for entry in &mut self.history {
+            if let Some(new_id) = renamed.get(&entry.wallpaper_id) {
+                entry.wallpaper_id = new_id.clone();
+            }
+        }
+        self.save()
+    }
+    
+    /// 删除壁纸后清理其评分、收藏和使用历史
+    pub fn remove_wallpapers(&mut self, wallpaper_ids: &[String]) -> Result<()> {
+        for id in wallpaper_ids {
+            self.ratings.remove(id);
+            self.favorites.remove(id);
+        }
+        self.history.retain(|entry| !wallpaper_ids.contains(&entry.wallpaper_id));
+        self.save()
+    }
+    
+    pub fn save(&self) -> Result<()> {
+        let store: UsageStore = UsageStore {
+            ratings: self.ratings.clone(),
+            favorites: self.favorites.clone(),
+            history: self.history.clone(),
+        };
+        save_toml_file(&self.store_path, &store)
+    }
+    
+    fn close_current(&mut self, ended_at: DateTime<Utc>) -> bool {
+        match self.history.last_mut() {
+            Some(entry) if entry.ended_at.is_none() => {
+                entry.ended_at = Some(ended_at.max(entry.applied_at));
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn create_test_config(name: &str) -> Config {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-usage-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        
+        Config {
+            data_directory: directory,
+            ..Config::default()
+        }
+    }
+    
+    fn ids(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_ratings_and_favorites() {
+        let config: Config = create_test_config("ratings");
+        let mut service: UsageService = UsageService::new(&config).unwrap();
+        
+        service.set_rating(&ids(&["a", "b"]), 4).unwrap();
+        service.set_rating(&ids(&["b"]), 0).unwrap();
+        assert!(service.set_rating(&ids(&["a"]), 6).is_err());
+        service.set_favorite(&ids(&["a", "c"]), true).unwrap();
+        service.set_favorite(&ids(&["c"]), false).unwrap();
+        
+        let reloaded: UsageService = UsageService::new(&config).unwrap();
+        assert_eq!((reloaded.get_rating("a"), reloaded.get_rating("b")), (4, 0));
+        assert!(reloaded.is_favorite("a") && !reloaded.is_favorite("c"));
+        
+        let _ = std::fs::remove_dir_all(&config.data_directory);
+    }
+
+    #[test]
+    fn test_usage_history() {
+        let config: Config = create_test_config("history");
+        let mut service: UsageService = UsageService::new(&config).unwrap();
+        let start: DateTime<Utc> = Utc.with_ymd_and_hms(2024, 3, 1, 8, 0, 0).unwrap();
+        
+        service.record_applied("a", start).unwrap();
+        service.record_applied("b", start + Duration::hours(1)).unwrap();
+        service.record_applied("a", start + Duration::hours(4)).unwrap();
+        service.finish_current(start + Duration::hours(5)).unwrap();
+        service.record_applied("c", start + Duration::hours(6)).unwrap();
+        
+        let now: DateTime<Utc> = start + Duration::hours(6) + Duration::minutes(30);
+        let stats: UsageStats = service.get_stats("a", now);
+        assert_eq!((stats.apply_count, stats.total_duration), (2, Duration::hours(2)));
+        assert_eq!(stats.last_applied, Some(start + Duration::hours(4)));
+        assert_eq!(service.get_current().unwrap().wallpaper_id, "c");
+        
+        let most_used: Vec<String> = service.get_most_used(2, now)
+            .into_iter()
+            .map(|stats| stats.wallpaper_id)
+            .collect();
+        assert_eq!(most_used, vec!["b", "a"]);
+        assert_eq!(service.get_recently_applied(10), vec!["c", "a", "b"]);
+        
+        // 重命名后历史和评分跟随新ID
+        service.set_rating(&ids(&["a"]), 5).unwrap();
+        service.replace_ids(&HashMap::from([("a".to_string(), "z".to_string())])).unwrap();
+        let reloaded: UsageService = UsageService::new(&config).unwrap();
+        assert_eq!(reloaded.get_stats("z", now).apply_count, 2);
+        assert_eq!(reloaded.get_rating("z"), 5);
+        assert!(!reloaded.is_used("a"));
+        
+        service.remove_wallpapers(&ids(&["z"])).unwrap();
+        assert_eq!(service.get_history().len(), 2);
+        
+        let _ = std::fs::remove_dir_all(&config.data_directory);
+    }
+}
\ No newline at end of file
@@ -1,24 +1,81 @@
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use image::{DynamicImage, ImageFormat, RgbImage};
+use image::codecs::jpeg::JpegEncoder;
+use image::metadata::Orientation;
 use walkdir::WalkDir;
 use crate::{Result, WallpaperError};
+use crate::components::{FuzzyIndex, Query, RotationScheduler, RuleMatch, TagRuleEngine};
+use crate::components::grouping::{is_grouped_raw, link_raw_siblings};
 use crate::config::Config;
-use crate::models::Wallpaper;
-use crate::services::ThumbnailService;
+use crate::models::{ImageQuality, TagRule, UsageStats, Wallpaper, WallpaperSource};
+use crate::services::{
+    CacheRebuild, CacheStats, CollectionService, EvictionReport, LibraryEntry, LibraryIndex, QualityAnalysis,
+    RebuildProgress, RepairReport, RuleReport, SmartCollectionService, TagService, ThumbnailService, UsageService,
+};
+use crate::services::tag_service::XMP_KEYWORD_SOURCE;
+use crate::utils::{
+    BLURHASH_COMPONENTS, ColorPalette, ExtraFormat, PALETTE_SIZE, decode_blurhash,
+    encode_blurhash, extract_palette, file_fingerprint, format_file_size, get_image_dimensions, is_jpeg_alias,
+    load_frame, move_file, open_oriented, read_animation_info, read_image_metadata, read_orientation,
+    safe_remove_file, unique_destination_path,
+};
+
+/// 批量操作的结果报告
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    /// 操作成功的壁纸 ID（移动操作返回移动后的新 ID）
+    pub succeeded: Vec<String>,
+    /// 操作失败的壁纸 ID 及错误原因
+    pub failed: Vec<(String, String)>,
+    /// 因移动而改变 ID 的壁纸，旧 ID 到新 ID 的映射
+    pub renamed: HashMap<String, String>,
+}
+
+impl BatchReport {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
 
 pub struct WallpaperService {
     config: Config,
     thumbnail_service: ThumbnailService,
+    /// 正在后台进行的缩略图缓存重建
+    thumbnail_rebuild: Option<CacheRebuild>,
+    /// 正在后台进行的图像质量分析
+    quality_analysis: Option<QualityAnalysis>,
+    tag_service: TagService,
+    tag_rules: TagRuleEngine,
+    usage_service: UsageService,
+    library_index: LibraryIndex,
     wallpapers: Vec<Wallpaper>,
+    /// 上次扫描检测到的外部重命名和ID迁移，旧 ID 到新 ID 的映射
+    scan_renamed: HashMap<String, String>,
+    /// 壁纸库版本号，每次壁纸列表或壁纸属性变化时递增
+    generation: u64,
 }
 
 impl WallpaperService {
     pub fn new(config: &Config) -> Result<Self> {
         let thumbnail_service: ThumbnailService = ThumbnailService::new(config)?;
+        let tag_service: TagService = TagService::new(config)?;
+        let tag_rules: TagRuleEngine = TagRuleEngine::new(&config.tag_rules);
+        let usage_service: UsageService = UsageService::new(config)?;
+        let library_index: LibraryIndex = LibraryIndex::new(config)?;
         
         Ok(Self {
             config: config.clone(),
             thumbnail_service,
+            thumbnail_rebuild: None,
+            quality_analysis: None,
+            tag_service,
+            tag_rules,
+            usage_service,
+            library_index,
             wallpapers: Vec::new(),
+            scan_renamed: HashMap::new(),
+            generation: 0,
         })
     }
     
@@ -26,45 +83,173 @@ impl WallpaperService {
         log::info!("开始扫描壁纸目录...");
         self.wallpapers.clear();
         
-        // 克隆目录列表以避免借用冲突
-        let directories = self.config.wallpaper_directories.clone();
-        for directory in &directories {
-            if directory.exists() {
-                self.scan_directory(directory)?;
-            } else {
-                log::warn!("目录不存在: {:?}", directory);
+        for path in self.find_wallpaper_files()? {
+            match self.process_wallpaper_file(path.clone()) {
+                Ok(wallpaper) => {
+                    self.wallpapers.push(wallpaper);
+                }
+                Err(e) => {
+                    log::warn!("处理文件失败 {:?}: {}", path, e);
+                }
             }
         }
         
+        link_raw_siblings(&mut self.wallpapers);
+        
+        // 在计算自动标签之前迁移被重命名壁纸的数据
+        let migrated: HashMap<String, String> = self.migrate_legacy_ids()?;
+        let fingerprints: Vec<(String, String)> = self.get_fingerprints();
+        self.scan_renamed = self.library_index.reconcile(&fingerprints)?;
+        if !self.scan_renamed.is_empty() {
+            log::info!("检测到 {} 张壁纸在外部被重命名或移动", self.scan_renamed.len());
+            self.tag_service.replace_ids(&self.scan_renamed)?;
+            self.usage_service.replace_ids(&self.scan_renamed)?;
+        }
+        self.scan_renamed.extend(migrated);
+        
+        let placeholders: BTreeMap<String, String> = self.wallpapers
+            .iter()
+            .filter_map(|wallpaper| Some((wallpaper.id.clone(), wallpaper.blurhash.clone()?)))
+            .collect();
+        self.library_index.update_placeholders(placeholders)?;
+        self.update_library_listing()?;
+        self.update_quality(&fingerprints)?;
+        
+        let report: RuleReport = self.evaluate_automatic_tags()?;
+        if report.added + report.removed > 0 {
+            log::info!("自动标签添加了 {} 个标签，移除了 {} 个标签", report.added, report.removed);
+        }
+        self.tag_service.apply_to(&mut self.wallpapers);
+        self.usage_service.apply_to(&mut self.wallpapers);
+        self.generation += 1;
         log::info!("扫描完成，找到 {} 张壁纸", self.wallpapers.len());
+        
+        // 缓存清理失败不影响扫描结果；后台重建期间跳过，重建结束时会清理缓存
+        if self.thumbnail_rebuild.is_none() {
+            if let Err(e) = self.prune_thumbnail_cache() {
+                log::warn!("清理缩略图缓存失败: {}", e);
+            }
+        }
+        Ok(())
+    }
+    
+    /// 删除原图已不存在的缩略图，并将缓存控制在配置的大小以内
+    pub fn prune_thumbnail_cache(&mut self) -> Result<EvictionReport> {
+        self.ensure_not_rebuilding()?;
+        let report: EvictionReport = self.thumbnail_service.prune_cache()?;
+        if report.orphans_removed + report.stale_removed + report.evicted > 0 {
+            log::info!(
+                "清理缩略图缓存：删除 {} 个失效缩略图和 {} 个最久未使用的缩略图，释放 {}",
+                report.orphans_removed + report.stale_removed,
+                report.evicted,
+                format_file_size(report.freed_bytes)
+            );
+        }
+        Ok(report)
+    }
+    
+    /// 校验缩略图缓存，供设置页面显示缓存统计
+    pub fn verify_thumbnail_cache(&self) -> Result<CacheStats> {
+        self.thumbnail_service.verify_cache()
+    }
+    
+    /// 重新生成损坏的缩略图
+    pub fn repair_thumbnail_cache(&mut self) -> Result<RepairReport> {
+        self.ensure_not_rebuilding()?;
+        let report: RepairReport = self.thumbnail_service.repair_cache()?;
+        log::info!(
+            "修复缩略图缓存：重新生成 {} 个，删除 {} 个损坏的缩略图和 {} 个临时文件",
+            report.regenerated,
+            report.removed,
+            report.temporary_removed
+        );
+        Ok(report)
+    }
+    
+    /// 为壁纸目录中的所有文件重建缩略图缓存，命令行使用，完成前不返回
+    ///
+    /// 直接遍历壁纸目录而不扫描壁纸库，扫描会为每个文件生成一次缩略图，随后又被重建
+    pub fn rebuild_thumbnail_cache<F: FnMut(RebuildProgress) -> bool>(&mut self, progress: F) -> Result<RebuildProgress> {
+        self.ensure_not_rebuilding()?;
+        let sources: Vec<PathBuf> = self.find_wallpaper_files()?;
+        self.thumbnail_service.rebuild_cache(&sources, progress)
+    }
+    
+    /// 在后台重建缩略图缓存，重建期间仍使用现有的缩略图服务，缺失的缩略图按需生成
+    pub fn start_thumbnail_rebuild(&mut self) -> Result<()> {
+        self.ensure_not_rebuilding()?;
+        
+        // 先保存清单，后台服务从磁盘加载以得到完整的缓存记录
+        self.thumbnail_service.flush_manifest()?;
+        let service: ThumbnailService = ThumbnailService::new(&self.config)?;
+        let sources: Vec<PathBuf> = self.wallpapers.iter().map(|wallpaper| wallpaper.path.clone()).collect();
+        self.thumbnail_rebuild = Some(service.spawn_rebuild(sources));
         Ok(())
     }
     
-    fn scan_directory(&mut self, directory: &Path) -> Result<()> {
-        let walker = WalkDir::new(directory)
-            .follow_links(true)
-            .max_depth(5); // 限制递归深度
+    /// 后台重建期间清理和修复会删除后台服务正在使用的缩略图，需等待重建结束
+    fn ensure_not_rebuilding(&self) -> Result<()> {
+        match self.thumbnail_rebuild {
+            Some(_) => Err(WallpaperError::Service("缩略图缓存正在重建".to_string())),
+            None => Ok(()),
+        }
+    }
+    
+    /// 后台重建的进度，没有进行中的重建时返回 `None`
+    pub fn get_thumbnail_rebuild_progress(&mut self) -> Option<RebuildProgress> {
+        self.thumbnail_rebuild.as_mut().map(|rebuild| rebuild.get_progress())
+    }
+    
+    pub fn cancel_thumbnail_rebuild(&self) {
+        if let Some(rebuild) = &self.thumbnail_rebuild {
+            rebuild.cancel();
+        }
+    }
+    
+    /// 后台重建结束后合并其缓存记录并返回最终进度；仍在进行或未开始时返回 `None`
+    pub fn finish_thumbnail_rebuild(&mut self) -> Result<Option<RebuildProgress>> {
+        if !self.thumbnail_rebuild.as_ref().is_some_and(|rebuild| rebuild.is_finished()) {
+            return Ok(None);
+        }
+        let Some(rebuild) = self.thumbnail_rebuild.take() else {
+            return Ok(None);
+        };
         
-        for entry in walker {
-            let entry = entry.map_err(|e| {
-                WallpaperError::Service(format!("遍历目录时出错: {}", e))
-            })?;
+        let (rebuilt, progress): (ThumbnailService, RebuildProgress) = rebuild.wait()?;
+        self.thumbnail_service.merge_rebuild(rebuilt)?;
+        self.generation += 1;
+        log::info!("缩略图缓存重建完成：{}/{}，失败 {} 个", progress.completed, progress.total, progress.failed);
+        Ok(Some(progress))
+    }
+    
+    /// 壁纸目录中所有支持格式的文件，不存在的目录跳过
+    fn find_wallpaper_files(&self) -> Result<Vec<PathBuf>> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        
+        for directory in &self.config.wallpaper_directories {
+            if !directory.exists() {
+                log::warn!("目录不存在: {:?}", directory);
+                continue;
+            }
             
-            let path: PathBuf = entry.path().to_path_buf();
+            let walker = WalkDir::new(directory)
+                .follow_links(true)
+                .max_depth(5); // 限制递归深度
             
-            if path.is_file() && self.config.is_supported_format(&path) {
-                match self.process_wallpaper_file(path) {
-                    Ok(wallpaper) => {
-                        self.wallpapers.push(wallpaper);
-                    }
-                    Err(e) => {
-                        log::warn!("处理文件失败 {:?}: {}", entry.path(), e);
-                    }
+            for entry in walker {
+                let entry = entry.map_err(|e| {
+                    WallpaperError::Service(format!("遍历目录时出错: {}", e))
+                })?;
+                
+                let path: PathBuf = entry.path().to_path_buf();
+                
+                if path.is_file() && self.config.is_supported_format(&path) {
+                    files.push(path);
                 }
             }
         }
         
-        Ok(())
+        Ok(files)
     }
     
     fn process_wallpaper_file(&mut self, path: PathBuf) -> Result<Wallpaper> {
@@ -74,13 +259,33 @@ impl WallpaperService {
         let mut wallpaper: Wallpaper = Wallpaper::new(path.clone())
             .map_err(|e| WallpaperError::Service(format!("创建壁纸模型失败: {}", e)))?;
         
-        // 获取图片尺寸
-        if let Ok(dimensions) = image::image_dimensions(&path) {
+        // 获取图片按 EXIF 方向显示时的尺寸
+        if let Ok(dimensions) = get_image_dimensions(&path) {
             wallpaper = wallpaper.with_dimensions(dimensions.0, dimensions.1);
         }
         
-        // 生成缩略图
+        // 检测 GIF/WebP 动图的帧数和时长
+        match read_animation_info(&path) {
+            Ok(Some(animation)) => wallpaper = wallpaper.with_animation(animation),
+            Ok(None) => {}
+            Err(e) => log::warn!("读取动图信息失败 {:?}: {}", path, e),
+        }
+        
+        // 读取 EXIF/XMP 元数据
+        match read_image_metadata(&path) {
+            Ok(metadata) => wallpaper = wallpaper.with_metadata(metadata),
+            Err(e) => log::warn!("读取元数据失败 {:?}: {}", path, e),
+        }
+        
+        // 生成缩略图，并由缩略图计算调色板、亮度和占位图
         if let Ok(thumbnail_path) = self.thumbnail_service.generate_thumbnail(&path) {
+            if let Ok(thumbnail) = image::open(&thumbnail_path) {
+                let colors: ColorPalette = extract_palette(&thumbnail, PALETTE_SIZE);
+                if !colors.colors.is_empty() {
+                    wallpaper = wallpaper.with_colors(colors);
+                }
+                wallpaper = wallpaper.with_blurhash(encode_blurhash(&thumbnail, BLURHASH_COMPONENTS));
+            }
             wallpaper = wallpaper.with_thumbnail(thumbnail_path);
         }
         
@@ -91,21 +296,607 @@ impl WallpaperService {
         &self.wallpapers
     }
     
+    /// 网格中显示的壁纸，与同名 JPEG 归为一组的 RAW 不单独显示
+    pub fn get_display_wallpapers(&self) -> Vec<&Wallpaper> {
+        self.wallpapers.iter().filter(|wallpaper| !is_grouped_raw(wallpaper)).collect()
+    }
+    
+    /// 上次扫描时网格中的壁纸，扫描完成前按此顺序布局占位图
+    pub fn get_cached_listing(&self) -> &[LibraryEntry] {
+        self.library_index.get_listing()
+    }
+    
+    /// 将当前网格中的壁纸写入壁纸库索引，供下次启动时布局
+    fn update_library_listing(&mut self) -> Result<()> {
+        let listing: Vec<LibraryEntry> = self.get_display_wallpapers()
+            .into_iter()
+            .map(|wallpaper| LibraryEntry {
+                id: wallpaper.id.clone(),
+                path: wallpaper.path.clone(),
+                size: wallpaper.size,
+            })
+            .collect();
+        self.library_index.update_listing(listing)
+    }
+    
+    /// 壁纸的模糊占位图；扫描完成前从壁纸库索引中读取上次扫描的结果
+    pub fn get_placeholder(&self, id: &str) -> Option<&str> {
+        match self.get_wallpaper_by_id(id) {
+            Some(wallpaper) => wallpaper.blurhash.as_deref(),
+            None => self.library_index.get_placeholder(id),
+        }
+    }
+    
+    /// 将占位图解码为指定尺寸的图像，由网格放大显示，通常 32 像素左右即可
+    pub fn get_placeholder_image(&self, id: &str, width: u32, height: u32) -> Option<RgbImage> {
+        let hash: &str = self.get_placeholder(id)?;
+        match decode_blurhash(hash, width, height, 1.0) {
+            Ok(image) => Some(image),
+            Err(e) => {
+                log::warn!("解码占位图失败 {}: {}", id, e);
+                None
+            }
+        }
+    }
+    
     pub fn get_wallpaper_by_id(&self, id: &str) -> Option<&Wallpaper> {
         self.wallpapers.iter().find(|w| w.id == id)
     }
     
-    pub fn filter_by_format(&self, format: &str) -> Vec<&Wallpaper> {
+    /// 壁纸库版本号，智能收藏等依赖壁纸库的数据据此判断是否需要重新计算
+    pub fn get_generation(&self) -> u64 {
+        self.generation
+    }
+    
+    pub fn get_tag_service(&self) -> &TagService {
+        &self.tag_service
+    }
+    
+    pub fn get_usage_service(&self) -> &UsageService {
+        &self.usage_service
+    }
+    
+    /// 上次扫描检测到的外部重命名和ID迁移（旧 ID 到新 ID），相册等由其他服务保存的数据需据此更新
+    pub fn get_scan_renames(&self) -> &HashMap<String, String> {
+        &self.scan_renamed
+    }
+    
+    /// 按当前壁纸库和标签别名重新计算智能收藏的成员，壁纸库未变化时不重新计算
+    pub fn refresh_smart_collections(&self, smart_collections: &mut SmartCollectionService) -> bool {
+        smart_collections.refresh(&self.wallpapers, self.generation, &|tag| self.tag_service.resolve(tag))
+    }
+    
+    /// 解析壁纸来源，返回其中仍存在于壁纸库的壁纸；整个壁纸库与网格一致不包含归组的 RAW
+    pub fn resolve_source(
+        &self,
+        source: &WallpaperSource,
+        smart_collections: &SmartCollectionService,
+        collections: &CollectionService,
+    ) -> Vec<&Wallpaper> {
+        let ids: &[String] = match source {
+            WallpaperSource::All => return self.get_display_wallpapers(),
+            WallpaperSource::Selection(ids) => ids,
+            WallpaperSource::SmartCollection(id) => smart_collections.get_members(id),
+            WallpaperSource::Album(id) => collections
+                .get_album(id)
+                .map(|album| album.wallpaper_ids.as_slice())
+                .unwrap_or(&[]),
+        };
+        
+        ids.iter()
+            .filter_map(|id| self.get_wallpaper_by_id(id))
+            .collect()
+    }
+    
+    /// 为选中的壁纸批量添加标签，标签会经过规范化和别名解析
+    pub fn add_tag_to_wallpapers(&mut self, ids: &[String], tag: &str) -> Result<BatchReport> {
+        let existing: Vec<String> = self.existing_ids(ids);
+        let tag: String = self.tag_service.add_tag(&existing, tag)?;
+        
+        Ok(self.apply_to_wallpapers(ids, |wallpaper| {
+            wallpaper.add_tag(tag.clone());
+            Ok(())
+        }))
+    }
+    
+    /// 为选中的壁纸批量移除标签
+    pub fn remove_tag_from_wallpapers(&mut self, ids: &[String], tag: &str) -> Result<BatchReport> {
+        let existing: Vec<String> = self.existing_ids(ids);
+        self.tag_service.remove_tag(&existing, tag)?;
+        let tag: String = self.tag_service.resolve(tag).unwrap_or_default();
+        
+        Ok(self.apply_to_wallpapers(ids, |wallpaper| {
+            wallpaper.remove_tag(&tag);
+            Ok(())
+        }))
+    }
+    
+    /// 在整个壁纸库中重命名标签（包括子标签），返回受影响的壁纸数量
+    pub fn rename_tag(&mut self, from: &str, to: &str) -> Result<usize> {
+        let affected: usize = self.tag_service.rename_tag(from, to)?;
+        self.refresh_tags();
+        Ok(affected)
+    }
+    
+    /// 将若干标签合并到目标标签，返回受影响的壁纸数量
+    pub fn merge_tags(&mut self, sources: &[String], target: &str) -> Result<usize> {
+        let affected: usize = self.tag_service.merge_tags(sources, target)?;
+        self.refresh_tags();
+        Ok(affected)
+    }
+    
+    /// 从整个壁纸库删除标签及其子标签
+    pub fn delete_tag(&mut self, tag: &str) -> Result<()> {
+        self.tag_service.delete_tag(tag)?;
+        self.refresh_tags();
+        Ok(())
+    }
+    
+    pub fn set_tag_color(&mut self, tag: &str, color: Option<&str>) -> Result<()> {
+        self.tag_service.set_color(tag, color)
+    }
+    
+    pub fn add_tag_alias(&mut self, tag: &str, alias: &str) -> Result<()> {
+        self.tag_service.add_alias(tag, alias)
+    }
+    
+    /// 为选中的壁纸设置星级（0-5），0 表示清除评分
+    pub fn set_rating(&mut self, ids: &[String], rating: u8) -> Result<BatchReport> {
+        let existing: Vec<String> = self.existing_ids(ids);
+        self.usage_service.set_rating(&existing, rating)?;
+        
+        Ok(self.apply_to_wallpapers(ids, |wallpaper| {
+            wallpaper.rating = rating;
+            Ok(())
+        }))
+    }
+    
+    pub fn set_favorite(&mut self, ids: &[String], favorite: bool) -> Result<BatchReport> {
+        let existing: Vec<String> = self.existing_ids(ids);
+        self.usage_service.set_favorite(&existing, favorite)?;
+        
+        Ok(self.apply_to_wallpapers(ids, |wallpaper| {
+            wallpaper.favorite = favorite;
+            Ok(())
+        }))
+    }
+    
+    /// 记录壁纸被设置为桌面壁纸，上一张壁纸的使用时长同时结束计算
+    pub fn record_wallpaper_applied(&mut self, id: &str) -> Result<()> {
+        if self.get_wallpaper_by_id(id).is_none() {
+            return Err(WallpaperError::Service(format!("找不到壁纸: {}", id)));
+        }
+        self.usage_service.record_applied(id, chrono::Utc::now())
+    }
+    
+    /// 结束当前壁纸的使用计时，例如停止轮换或退出程序时
+    pub fn finish_wallpaper_usage(&mut self) -> Result<()> {
+        self.usage_service.finish_current(chrono::Utc::now())
+    }
+    
+    /// 最常用的壁纸及其使用统计，按累计使用时长排列
+    pub fn get_most_used(&self, limit: usize) -> Vec<(&Wallpaper, UsageStats)> {
+        self.usage_service
+            .get_most_used(usize::MAX, chrono::Utc::now())
+            .into_iter()
+            .filter_map(|stats| Some((self.get_wallpaper_by_id(&stats.wallpaper_id)?, stats)))
+            .take(limit)
+            .collect()
+    }
+    
+    /// 最近使用过的壁纸，最近的在前
+    pub fn get_recently_applied(&self, limit: usize) -> Vec<&Wallpaper> {
+        self.usage_service
+            .get_recently_applied(usize::MAX)
+            .into_iter()
+            .filter_map(|id| self.get_wallpaper_by_id(id))
+            .take(limit)
+            .collect()
+    }
+    
+    /// 从未被设置为桌面壁纸的壁纸
+    pub fn get_never_used(&self) -> Vec<&Wallpaper> {
         self.wallpapers
             .iter()
-            .filter(|w| w.format.eq_ignore_ascii_case(format))
+            .filter(|wallpaper| !self.usage_service.is_used(&wallpaper.id))
             .collect()
     }
     
-    pub fn filter_by_size(&self, min_width: u32, min_height: u32) -> Vec<&Wallpaper> {
+    /// 从候选壁纸（通常来自 [`Self::resolve_source`]）中挑选下一张轮换的壁纸，跳过最近使用过的壁纸
+    pub fn pick_rotation_wallpaper<'a>(
+        &self,
+        scheduler: &mut RotationScheduler,
+        candidates: &[&'a Wallpaper],
+    ) -> Option<&'a Wallpaper> {
+        let recent: Vec<&str> = self.usage_service.get_recently_applied(candidates.len());
+        scheduler.pick(candidates, &recent)
+    }
+    
+    pub fn get_tag_rules(&self) -> &[TagRule] {
+        &self.config.tag_rules
+    }
+    
+    /// 替换自动标签规则，任一规则无效时返回错误且不做修改
+    ///
+    /// 新规则在下一次扫描或调用 [`WallpaperService::reapply_tag_rules`] 时生效
+    pub fn set_tag_rules(&mut self, rules: Vec<TagRule>) -> Result<()> {
+        for rule in &rules {
+            TagRuleEngine::validate(rule)?;
+        }
+        
+        self.tag_rules = TagRuleEngine::new(&rules);
+        self.config.tag_rules = rules;
+        Ok(())
+    }
+    
+    /// 将自动标签规则写回配置并保存
+    pub fn persist_tag_rules(&self, config: &mut Config) -> Result<()> {
+        config.tag_rules = self.config.tag_rules.clone();
+        config.save()
+    }
+    
+    /// 对整个壁纸库重新应用自动标签规则和 XMP 关键词
+    pub fn reapply_tag_rules(&mut self) -> Result<RuleReport> {
+        let report: RuleReport = self.evaluate_automatic_tags()?;
+        self.refresh_tags();
+        Ok(report)
+    }
+    
+    /// 返回为壁纸添加该标签的规则，手动添加的标签和 XMP 关键词返回 `None`
+    pub fn get_tag_rule(&self, wallpaper_id: &str, tag: &str) -> Option<&TagRule> {
+        let rule_id: &str = self.tag_service.get_rule_source(wallpaper_id, tag)?;
+        self.config.tag_rules.iter().find(|rule| rule.id == rule_id)
+    }
+    
+    /// 获取指定档位的缩略图，按需生成，网格缩放或显示器缩放比例变化时使用
+    pub fn get_thumbnail_tier(&mut self, id: &str, tier: u32) -> Result<PathBuf> {
+        let path: PathBuf = self.get_wallpaper_by_id(id)
+            .map(|wallpaper| wallpaper.path.clone())
+            .ok_or_else(|| WallpaperError::Service(format!("找不到壁纸: {}", id)))?;
+        self.thumbnail_service.generate_thumbnail_tier(&path, tier)
+    }
+    
+    /// 准备用于设置为桌面壁纸的文件
+    ///
+    /// 桌面环境通常会忽略 EXIF 方向，因此带有旋转或翻转的图片会在缓存目录中生成
+    /// 已校正方向的副本；不需要校正的图片直接返回原文件路径
+    pub fn prepare_wallpaper_file(&self, id: &str) -> Result<PathBuf> {
+        let wallpaper: &Wallpaper = self.get_wallpaper_by_id(id)
+            .ok_or_else(|| WallpaperError::Service(format!("找不到壁纸: {}", id)))?;
+        
+        // 桌面环境大多不能显示 AVIF、HEIF、JPEG XL 和 SVG，同样转换为 PNG
+        let is_extra_format: bool = ExtraFormat::from_path(&wallpaper.path).is_some();
+        if !is_extra_format && read_orientation(&wallpaper.path)? == Orientation::NoTransforms {
+            return Ok(wallpaper.path.clone());
+        }
+        
+        let directory: PathBuf = self.config.cache_directory.join("applied");
+        std::fs::create_dir_all(&directory)?;
+        
+        // JPEG 保持 JPEG 以控制文件大小，其他格式使用无损的 PNG
+        let is_jpeg: bool = is_jpeg_alias(&wallpaper.format);
+        let target: PathBuf = directory.join(format!("{}.{}", wallpaper.id, if is_jpeg { "jpg" } else { "png" }));
+        
+        let source_modified: std::time::SystemTime = std::fs::metadata(&wallpaper.path)?.modified()?;
+        if let Ok(target_modified) = std::fs::metadata(&target).and_then(|metadata| metadata.modified()) {
+            if target_modified >= source_modified {
+                return Ok(target);
+            }
+        }
+        
+        let image: DynamicImage = open_oriented(&wallpaper.path)?;
+        if is_jpeg {
+            let writer: std::io::BufWriter<std::fs::File> = std::io::BufWriter::new(std::fs::File::create(&target)?);
+            image.to_rgb8().write_with_encoder(JpegEncoder::new_with_quality(writer, 95))?;
+        } else {
+            image.save_with_format(&target, ImageFormat::Png)?;
+        }
+        
+        log::debug!("已为 {:?} 生成校正方向的壁纸文件 {:?}", wallpaper.path, target);
+        Ok(target)
+    }
+    
+    /// 将动图的指定帧保存为 PNG，用于设置为静态桌面壁纸
+    pub fn prepare_wallpaper_frame(&self, id: &str, frame_index: usize) -> Result<PathBuf> {
+        let wallpaper: &Wallpaper = self.get_wallpaper_by_id(id)
+            .ok_or_else(|| WallpaperError::Service(format!("找不到壁纸: {}", id)))?;
+        let frame_count: u32 = wallpaper.animation.map(|animation| animation.frame_count).unwrap_or(1);
+        if frame_index >= frame_count as usize {
+            return Err(WallpaperError::Service(format!("帧序号超出范围: {} (共 {} 帧)", frame_index, frame_count)));
+        }
+        
+        let directory: PathBuf = self.config.cache_directory.join("applied");
+        std::fs::create_dir_all(&directory)?;
+        let target: PathBuf = directory.join(format!("{}-frame{}.png", wallpaper.id, frame_index));
+        
+        let source_modified: std::time::SystemTime = std::fs::metadata(&wallpaper.path)?.modified()?;
+        if let Ok(target_modified) = std::fs::metadata(&target).and_then(|metadata| metadata.modified()) {
+            if target_modified >= source_modified {
+                return Ok(target);
+            }
+        }
+        
+        load_frame(&wallpaper.path, frame_index)?.save_with_format(&target, ImageFormat::Png)?;
+        log::debug!("已将 {:?} 的第 {} 帧保存为 {:?}", wallpaper.path, frame_index, target);
+        Ok(target)
+    }
+    
+    /// 将选中的壁纸移动到目标目录
+    pub fn move_wallpapers(&mut self, ids: &[String], destination: &Path) -> Result<BatchReport> {
+        std::fs::create_dir_all(destination)?;
+        
+        let report: BatchReport = self.apply_to_wallpapers(ids, |wallpaper| {
+            let target: PathBuf = unique_destination_path(destination, &wallpaper.filename);
+            move_file(&wallpaper.path, &target)?;
+            wallpaper.relocate(target);
+            Ok(())
+        });
+        
+        link_raw_siblings(&mut self.wallpapers);
+        self.tag_service.replace_ids(&report.renamed)?;
+        self.usage_service.replace_ids(&report.renamed)?;
+        self.library_index.replace_ids(&report.renamed)?;
+        self.update_library_listing()?;
+        Ok(report)
+    }
+    
+    /// 从磁盘删除选中的壁纸
+    pub fn delete_wallpapers(&mut self, ids: &[String]) -> BatchReport {
+        let report: BatchReport = self.apply_to_wallpapers(ids, |wallpaper| {
+            safe_remove_file(&wallpaper.path)
+        });
+        
+        // 缩略图随原图一起删除，不必等到下次扫描时清理
+        for wallpaper in self.wallpapers.iter().filter(|w| report.succeeded.contains(&w.id)) {
+            if let Err(e) = self.thumbnail_service.remove_source(&wallpaper.path) {
+                log::warn!("删除缩略图失败 {:?}: {}", wallpaper.path, e);
+            }
+        }
+        self.wallpapers.retain(|w| !report.succeeded.contains(&w.id));
+        link_raw_siblings(&mut self.wallpapers);
+        if let Err(e) = self.tag_service.remove_wallpapers(&report.succeeded) {
+            log::warn!("清理已删除壁纸的标签失败: {}", e);
+        }
+        if let Err(e) = self.usage_service.remove_wallpapers(&report.succeeded) {
+            log::warn!("清理已删除壁纸的使用记录失败: {}", e);
+        }
+        if let Err(e) = self.library_index.remove_wallpapers(&report.succeeded) {
+            log::warn!("清理已删除壁纸的索引失败: {}", e);
+        }
+        // 删除 JPEG 后与其归组的 RAW 会重新显示在网格中
+        if let Err(e) = self.update_library_listing() {
+            log::warn!("更新壁纸库索引失败: {}", e);
+        }
+        report
+    }
+    
+    /// 将选中的壁纸复制到导出目录
+    pub fn export_wallpapers(&self, ids: &[String], destination: &Path) -> Result<BatchReport> {
+        std::fs::create_dir_all(destination)?;
+        
+        let mut report: BatchReport = BatchReport::default();
+        for id in ids {
+            let result: Result<()> = match self.get_wallpaper_by_id(id) {
+                Some(wallpaper) => {
+                    let target: PathBuf = unique_destination_path(destination, &wallpaper.filename);
+                    std::fs::copy(&wallpaper.path, &target)
+                        .map(|_| ())
+                        .map_err(WallpaperError::from)
+                }
+                None => Err(WallpaperError::Service(format!("找不到壁纸: {}", id))),
+            };
+            
+            match result {
+                Ok(()) => report.succeeded.push(id.clone()),
+                Err(e) => report.failed.push((id.clone(), e.to_string())),
+            }
+        }
+        
+        Ok(report)
+    }
+    
+    fn existing_ids(&self, ids: &[String]) -> Vec<String> {
+        ids.iter()
+            .filter(|id| self.get_wallpaper_by_id(id).is_some())
+            .cloned()
+            .collect()
+    }
+    
+    /// 旧版本的壁纸ID由 `DefaultHasher` 生成，在不同 Rust 版本间不稳定；
+    /// 首次扫描时按路径把已保存的数据迁移到新ID，返回旧ID到新ID的映射
+    fn migrate_legacy_ids(&mut self) -> Result<HashMap<String, String>> {
+        if !self.library_index.needs_id_migration() {
+            return Ok(HashMap::new());
+        }
+        
+        let migrated: HashMap<String, String> = self.wallpapers
+            .iter()
+            .map(|wallpaper| (Wallpaper::generate_legacy_id(&wallpaper.path), wallpaper.id.clone()))
+            .filter(|(legacy_id, id)| legacy_id != id)
+            .collect();
+        self.tag_service.replace_ids(&migrated)?;
+        self.usage_service.replace_ids(&migrated)?;
+        self.library_index.migrate_ids(&migrated)?;
+        log::info!("已将 {} 张壁纸的数据迁移到新的ID", migrated.len());
+        Ok(migrated)
+    }
+    
+    /// 计算所有壁纸的文件指纹（壁纸ID, 指纹），用于检测外部重命名和沿用质量指标
+    fn get_fingerprints(&self) -> Vec<(String, String)> {
         self.wallpapers
             .iter()
-            .filter(|w| w.size.0 >= min_width && w.size.1 >= min_height)
+            .filter_map(|wallpaper| match file_fingerprint(&wallpaper.path) {
+                Ok(fingerprint) => Some((wallpaper.id.clone(), fingerprint)),
+                Err(e) => {
+                    log::warn!("计算文件指纹失败 {:?}: {}", wallpaper.path, e);
+                    None
+                }
+            })
             .collect()
     }
+    
+    /// 应用壁纸库索引中内容未变化的文件的质量指标，其余文件在后台分析
+    ///
+    /// 动图、RAW 和 SVG 不分析：动图的帧通常经过调色板量化，RAW 的清晰度取决于去马赛克，SVG 没有固定分辨率
+    fn update_quality(&mut self, fingerprints: &[(String, String)]) -> Result<()> {
+        let fingerprint_by_id: HashMap<&str, &str> = fingerprints
+            .iter()
+            .map(|(id, fingerprint)| (id.as_str(), fingerprint.as_str()))
+            .collect();
+        
+        let mut quality_by_fingerprint: BTreeMap<String, ImageQuality> = BTreeMap::new();
+        let mut pending: Vec<(String, PathBuf, String)> = Vec::new();
+        for wallpaper in &mut self.wallpapers {
+            if wallpaper.is_animated() || wallpaper.is_raw() || ExtraFormat::from_path(&wallpaper.path) == Some(ExtraFormat::Svg) {
+                continue;
+            }
+            let Some(fingerprint) = fingerprint_by_id.get(wallpaper.id.as_str()) else {
+                continue;
+            };
+            
+            match self.library_index.get_quality(fingerprint) {
+                Some(quality) => {
+                    wallpaper.quality = Some(*quality);
+                    quality_by_fingerprint.insert(fingerprint.to_string(), *quality);
+                }
+                None => pending.push((wallpaper.id.clone(), wallpaper.path.clone(), fingerprint.to_string())),
+            }
+        }
+        
+        // 上一次扫描排队的分析随旧的分析任务一起取消
+        self.quality_analysis = None;
+        if !pending.is_empty() {
+            log::info!("在后台分析 {} 张壁纸的图像质量", pending.len());
+            self.quality_analysis = Some(QualityAnalysis::spawn(pending));
+        }
+        self.library_index.update_quality(quality_by_fingerprint)
+    }
+    
+    /// 取回后台质量分析的结果并应用到壁纸，返回更新的壁纸数量；分析全部结束后重新应用自动标签规则
+    pub fn poll_quality_analysis(&mut self) -> Result<usize> {
+        let Some(analysis) = self.quality_analysis.as_mut() else {
+            return Ok(0);
+        };
+        // 先判断是否结束再取回结果，结束前发出的结果不会遗漏
+        let finished: bool = analysis.is_finished();
+        let results: Vec<(String, ImageQuality)> = analysis.take_results();
+        
+        let mut updated: usize = 0;
+        for (fingerprint, quality) in &results {
+            for id in analysis.get_wallpaper_ids(fingerprint) {
+                if let Some(wallpaper) = self.wallpapers.iter_mut().find(|wallpaper| &wallpaper.id == id) {
+                    wallpaper.quality = Some(*quality);
+                    updated += 1;
+                }
+            }
+        }
+        if !results.is_empty() {
+            self.library_index.add_quality(results)?;
+            self.generation += 1;
+        }
+        
+        if finished {
+            self.quality_analysis = None;
+            log::info!("图像质量分析完成");
+            self.reapply_tag_rules()?;
+        }
+        Ok(updated)
+    }
+    
+    /// 等待后台质量分析结束并应用全部结果，测试使用；命令行不扫描壁纸库，不会启动质量分析
+    pub fn wait_quality_analysis(&mut self) -> Result<usize> {
+        if let Some(analysis) = self.quality_analysis.as_mut() {
+            analysis.wait();
+        }
+        self.poll_quality_analysis()
+    }
+    
+    /// 计算自动标签：规则命中的标签和 XMP 中导入的关键词
+    fn evaluate_automatic_tags(&mut self) -> Result<RuleReport> {
+        let results: Vec<(String, Vec<RuleMatch>)> = self.wallpapers
+            .iter()
+            .map(|wallpaper| {
+                let mut matches: Vec<RuleMatch> = self.tag_rules.evaluate(wallpaper);
+                matches.extend(wallpaper.metadata.keywords.iter().map(|keyword| RuleMatch {
+                    rule_id: XMP_KEYWORD_SOURCE.to_string(),
+                    tag: keyword.clone(),
+                }));
+                (wallpaper.id.clone(), matches)
+            })
+            .collect();
+        
+        self.tag_service.apply_rule_matches(&results)
+    }
+    
+    /// 标签库整体变化后重新写入壁纸模型
+    fn refresh_tags(&mut self) {
+        self.tag_service.apply_to(&mut self.wallpapers);
+        self.generation += 1;
+    }
+    
+    fn apply_to_wallpapers<F>(&mut self, ids: &[String], mut operation: F) -> BatchReport
+    where
+        F: FnMut(&mut Wallpaper) -> Result<()>,
+    {
+        let mut report: BatchReport = BatchReport::default();
+        
+        for id in ids {
+            let result: Result<String> = match self.wallpapers.iter_mut().find(|w| &w.id == id) {
+                Some(wallpaper) => operation(wallpaper).map(|_| wallpaper.id.clone()),
+                None => Err(WallpaperError::Service(format!("找不到壁纸: {}", id))),
+            };
+            
+            match result {
+                Ok(new_id) => {
+                    self.generation += 1;
+                    if &new_id != id {
+                        report.renamed.insert(id.clone(), new_id.clone());
+                    }
+                    report.succeeded.push(new_id);
+                }
+                Err(e) => {
+                    log::warn!("批量操作失败 {}: {}", id, e);
+                    report.failed.push((id.clone(), e.to_string()));
+                }
+            }
+        }
+        
+        report
+    }
+    
+    /// 返回满足查询条件的壁纸，与网格一致不包含归组的 RAW
+    pub fn search(&self, query: &Query) -> Vec<&Wallpaper> {
+        query
+            .filter(&self.wallpapers)
+            .into_iter()
+            .filter(|wallpaper| !is_grouped_raw(wallpaper))
+            .collect()
+    }
+    
+    /// 为当前壁纸列表建立模糊搜索索引，壁纸列表变化后需要重新建立
+    pub fn build_fuzzy_index(&self) -> FuzzyIndex {
+        FuzzyIndex::build(self.get_display_wallpapers(), &self.config.wallpaper_directories)
+    }
+    
+    /// 解析搜索栏输入并返回匹配的壁纸，标签别名会解析为对应的标签
+    pub fn search_str(&self, input: &str) -> Result<Vec<&Wallpaper>> {
+        let mut query: Query = Query::parse(input)?;
+        query.resolve_tags(&|tag: &str| self.tag_service.resolve(tag));
+        Ok(self.search(&query))
+    }
+    
+    /// 按颜色搜索，返回调色板在 Lab 空间中最接近目标颜色的壁纸，由近到远排列
+    pub fn search_by_color(&self, rgb: [u8; 3], limit: usize) -> Vec<&Wallpaper> {
+        let mut ranked: Vec<(f32, &Wallpaper)> = self.wallpapers
+            .iter()
+            .filter(|wallpaper| !is_grouped_raw(wallpaper))
+            .filter_map(|wallpaper| {
+                let distance: f32 = wallpaper.colors.as_ref()?.distance_to(rgb)?;
+                Some((distance, wallpaper))
+            })
+            .collect();
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+        
+        ranked.into_iter().take(limit).map(|(_, wallpaper)| wallpaper).collect()
+    }
 } 
\ No newline at end of file
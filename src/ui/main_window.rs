@@ -1,7 +1,38 @@
+use std::path::Path;
+use image::{DynamicImage, RgbaImage};
+use slint::{Image, Rgba8Pixel, SharedPixelBuffer};
 use crate::Result;
+use crate::utils::{AnimationFrame, composite_on_checkerboard, draw_animation_badge, has_transparency};
 
 slint::include_modules!();
 
+/// 透明缩略图背后棋盘格的格子边长（像素）
+const CHECKERBOARD_CELL_SIZE: u32 = 8;
+
+/// 读取网格中显示的缩略图并转换为 Slint 图像，透明区域叠加棋盘格，动图在右下角叠加播放标记
+pub fn load_thumbnail_image(thumbnail_path: &Path, animated: bool) -> Result<Image> {
+    let thumbnail: DynamicImage = image::open(thumbnail_path)?;
+    let mut pixels: RgbaImage = if has_transparency(&thumbnail) {
+        composite_on_checkerboard(&thumbnail, CHECKERBOARD_CELL_SIZE)
+    } else {
+        thumbnail.to_rgba8()
+    };
+    if animated {
+        draw_animation_badge(&mut pixels);
+    }
+    Ok(to_slint_image(&pixels))
+}
+
+/// 将预览窗口中动图播放器的当前帧转换为 Slint 图像
+pub fn get_frame_image(frame: &AnimationFrame) -> Image {
+    to_slint_image(&frame.image)
+}
+
+fn to_slint_image(pixels: &RgbaImage) -> Image {
+    let buffer: SharedPixelBuffer<Rgba8Pixel> = SharedPixelBuffer::clone_from_slice(pixels.as_raw(), pixels.width(), pixels.height());
+    Image::from_rgba8(buffer)
+}
+
 pub struct MainWindowWrapper {
     window: MainWindow,
 }
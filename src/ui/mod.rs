@@ -1,3 +1,3 @@
 pub mod main_window;
 
-pub use main_window::MainWindowWrapper as MainWindow; 
\ No newline at end of file
+pub use main_window::{MainWindowWrapper as MainWindow, WallpaperThumbnail, get_frame_image, load_thumbnail_image}; 
\ No newline at end of file
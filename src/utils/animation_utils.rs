@@ -0,0 +1,333 @@
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::time::Duration;
+use image::{AnimationDecoder, DynamicImage, Frame, Frames, ImageFormat, ImageReader, Rgba, RgbaImage};
+use image::codecs::gif::GifDecoder;
+use image::codecs::webp::WebPDecoder;
+use crate::{Result, WallpaperError};
+use crate::models::AnimationInfo;
+
+/// GIF 帧延迟不超过此值（毫秒）时按默认延迟播放，与浏览器的处理一致
+const MIN_GIF_FRAME_DELAY_MS: u64 = 10;
+const DEFAULT_GIF_FRAME_DELAY_MS: u64 = 100;
+
+/// 挑选代表帧时最多比较的帧数
+const REPRESENTATIVE_FRAME_SAMPLES: u32 = 8;
+
+/// 动图的一帧
+#[derive(Debug, Clone)]
+pub struct AnimationFrame {
+    pub image: RgbaImage,
+    pub delay: Duration,
+}
+
+/// 读取 GIF/WebP 的帧数和总时长，只解析文件结构不解码帧；静态图片返回 `None`
+pub fn read_animation_info(path: &Path) -> Result<Option<AnimationInfo>> {
+    // 先只读文件头，静态格式无需读取整个文件
+    let mut file: File = File::open(path)?;
+    let mut data: Vec<u8> = Vec::new();
+    file.by_ref().take(12).read_to_end(&mut data)?;
+    let is_gif: bool = data.starts_with(b"GIF8");
+    let is_webp: bool = data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP";
+    if !is_gif && !is_webp {
+        return Ok(None);
+    }
+    file.read_to_end(&mut data)?;
+    
+    let info: Option<AnimationInfo> = if is_gif { parse_gif(&data) } else { parse_webp(&data) };
+    Ok(info.filter(|info| info.frame_count > 1))
+}
+
+/// 按需逐帧解码 GIF/WebP，其他格式返回 `None`
+fn open_frames(path: &Path, format: Option<ImageFormat>) -> Result<Option<Frames<'static>>> {
+    Ok(match format {
+        Some(ImageFormat::Gif) => Some(GifDecoder::new(BufReader::new(File::open(path)?))?.into_frames()),
+        Some(ImageFormat::WebP) => Some(WebPDecoder::new(BufReader::new(File::open(path)?))?.into_frames()),
+        _ => None,
+    })
+}
+
+/// 解码所有帧，用于预览窗口播放；静态图片返回单帧
+pub fn decode_frames(path: &Path) -> Result<Vec<AnimationFrame>> {
+    let format: Option<ImageFormat> = ImageReader::open(path)?.with_guessed_format()?.format();
+    let frames: Vec<Frame> = match open_frames(path, format)? {
+        Some(frames) => frames.collect_frames()?,
+        None => {
+            return Ok(vec![AnimationFrame {
+                image: image::open(path)?.to_rgba8(),
+                delay: Duration::ZERO,
+            }]);
+        }
+    };
+    
+    Ok(frames
+        .into_iter()
+        .map(|frame| {
+            let delay: Duration = Duration::from(frame.delay());
+            let delay: Duration = if format == Some(ImageFormat::Gif) {
+                Duration::from_millis(normalize_gif_delay(delay.as_millis() as u64))
+            } else {
+                delay
+            };
+            AnimationFrame {
+                image: frame.into_buffer(),
+                delay,
+            }
+        })
+        .collect())
+}
+
+/// 解码指定的一帧，静态图片只有第 0 帧
+pub fn load_frame(path: &Path, index: usize) -> Result<DynamicImage> {
+    let format: Option<ImageFormat> = ImageReader::open(path)?.with_guessed_format()?.format();
+    let frame: Option<image::ImageResult<Frame>> = match open_frames(path, format)? {
+        Some(mut frames) => frames.nth(index),
+        None if index == 0 => return Ok(image::open(path)?),
+        None => None,
+    };
+    
+    match frame {
+        Some(frame) => Ok(DynamicImage::ImageRgba8(frame?.into_buffer())),
+        None => Err(WallpaperError::Service(format!("帧序号超出范围: {}", index))),
+    }
+}
+
+/// 挑选最能代表动图内容的一帧：在均匀分布的若干帧中选择亮度方差最大的一帧，
+/// 避免选中淡入动画开头的纯色帧
+///
+/// 逐帧解码，未抽到的帧解码后立即丢弃，内存中只保留当前帧和目前最好的一帧
+pub fn load_representative_frame(path: &Path, info: &AnimationInfo) -> Result<DynamicImage> {
+    let format: Option<ImageFormat> = ImageReader::open(path)?.with_guessed_format()?.format();
+    let Some(frames) = open_frames(path, format)? else {
+        return Ok(image::open(path)?);
+    };
+    let step: usize = info.frame_count.div_ceil(REPRESENTATIVE_FRAME_SAMPLES).max(1) as usize;
+    let mut best: Option<(f32, RgbaImage)> = None;
+    
+    for (index, frame) in frames.enumerate().step_by(step) {
+        let image: RgbaImage = frame?.into_buffer();
+        let score: f32 = luminance_variance(&image);
+        log::trace!("{:?} 第 {} 帧亮度方差 {:.1}", path, index, score);
+        if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+            best = Some((score, image));
+        }
+    }
+    
+    best.map(|(_, image)| DynamicImage::ImageRgba8(image))
+        .ok_or_else(|| WallpaperError::Service(format!("动图没有可解码的帧: {:?}", path)))
+}
+
+/// 在右下角绘制表示动图的播放标记，供网格在缩略图上叠加显示
+pub fn draw_animation_badge(image: &mut RgbaImage) {
+    let size: u32 = (image.width().min(image.height()) / 5).max(8);
+    if size > image.width() || size > image.height() {
+        return;
+    }
+    
+    let (left, top) = (image.width() - size, image.height() - size);
+    for y in 0..size {
+        for x in 0..size {
+            // 三角形占据标记中间一半的区域，顶点指向右侧
+            let (u, v) = (x as f32 / size as f32 - 0.3, y as f32 / size as f32 - 0.5);
+            let inside_triangle: bool = (0.0..=0.45).contains(&u) && v.abs() <= 0.25 * (1.0 - u / 0.45);
+            let color: Rgba<u8> = if inside_triangle { Rgba([255, 255, 255, 255]) } else { Rgba([0, 0, 0, 160]) };
+            
+            let pixel: &mut Rgba<u8> = image.get_pixel_mut(left + x, top + y);
+            let alpha: u32 = color[3] as u32;
+            for channel in 0..3 {
+                pixel[channel] = ((color[channel] as u32 * alpha + pixel[channel] as u32 * (255 - alpha)) / 255) as u8;
+            }
+            pixel[3] = pixel[3].max(color[3]);
+        }
+    }
+}
+
+fn normalize_gif_delay(delay_ms: u64) -> u64 {
+    if delay_ms <= MIN_GIF_FRAME_DELAY_MS {
+        DEFAULT_GIF_FRAME_DELAY_MS
+    } else {
+        delay_ms
+    }
+}
+
+fn luminance_variance(image: &RgbaImage) -> f32 {
+    let sample: RgbaImage = image::imageops::thumbnail(image, 32, 32);
+    let values: Vec<f32> = sample
+        .pixels()
+        .map(|pixel| (0.299 * pixel[0] as f32 + 0.587 * pixel[1] as f32 + 0.114 * pixel[2] as f32) * pixel[3] as f32 / 255.0)
+        .collect();
+    let mean: f32 = values.iter().sum::<f32>() / values.len().max(1) as f32;
+    values.iter().map(|value| (value - mean).powi(2)).sum::<f32>() / values.len().max(1) as f32
+}
+
+/// 解析 GIF 块结构：统计图像描述符数量，累加图形控制扩展中的延迟
+fn parse_gif(data: &[u8]) -> Option<AnimationInfo> {
+    let packed: u8 = *data.get(10)?;
+    let mut offset: usize = 13 + color_table_size(packed);
+    let mut frame_count: u32 = 0;
+    let mut duration_ms: u64 = 0;
+    let mut pending_delay_ms: u64 = 0;
+    
+    loop {
+        match *data.get(offset)? {
+            // 扩展块，0xF9 为图形控制扩展
+            0x21 => {
+                if data.get(offset + 1) == Some(&0xF9) && data.get(offset + 2) == Some(&4) {
+                    let delay: u16 = u16::from_le_bytes([*data.get(offset + 4)?, *data.get(offset + 5)?]);
+                    pending_delay_ms = delay as u64 * 10;
+                }
+                offset = skip_sub_blocks(data, offset + 2)?;
+            }
+            // 图像描述符：9 字节字段、可选的局部颜色表、LZW 最小码长和图像数据
+            0x2C => {
+                let packed: u8 = *data.get(offset + 9)?;
+                offset = skip_sub_blocks(data, offset + 10 + color_table_size(packed) + 1)?;
+                frame_count += 1;
+                duration_ms += normalize_gif_delay(pending_delay_ms);
+                pending_delay_ms = 0;
+            }
+            // 结束标记或无法识别的数据
+            _ => break,
+        }
+    }
+    
+    Some(AnimationInfo {
+        frame_count,
+        duration_ms,
+    })
+}
+
+fn color_table_size(packed: u8) -> usize {
+    if packed & 0x80 != 0 {
+        3 << ((packed & 0x07) + 1)
+    } else {
+        0
+    }
+}
+
+fn skip_sub_blocks(data: &[u8], mut offset: usize) -> Option<usize> {
+    loop {
+        let size: usize = *data.get(offset)? as usize;
+        offset += 1 + size;
+        if size == 0 {
+            return Some(offset);
+        }
+    }
+}
+
+/// 解析 WebP 的 RIFF 块：`VP8X` 的动画标志位和每个 `ANMF` 帧的时长
+fn parse_webp(data: &[u8]) -> Option<AnimationInfo> {
+    let mut offset: usize = 12;
+    let mut animated: bool = false;
+    let mut frame_count: u32 = 0;
+    let mut duration_ms: u64 = 0;
+    
+    while offset + 8 <= data.len() {
+        let fourcc: &[u8] = &data[offset..offset + 4];
+        let size: usize = u32::from_le_bytes([data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7]]) as usize;
+        let payload: &[u8] = data.get(offset + 8..offset + 8 + size)?;
+        
+        match fourcc {
+            b"VP8X" => animated = payload.first()? & 0x02 != 0,
+            b"ANMF" => {
+                let duration: &[u8] = payload.get(12..15)?;
+                frame_count += 1;
+                duration_ms += u32::from_le_bytes([duration[0], duration[1], duration[2], 0]) as u64;
+            }
+            _ => {}
+        }
+        // 块按偶数字节对齐
+        offset += 8 + size + (size & 1);
+    }
+    
+    animated.then_some(AnimationInfo {
+        frame_count,
+        duration_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use image::Delay;
+    use image::codecs::gif::{GifEncoder, Repeat};
+
+    /// 写入 GIF 动图，第一帧为纯黑，其余帧为条纹
+    fn create_animated_gif(path: &Path, frame_count: u32, delay_ms: u32) {
+        let mut encoder: GifEncoder<File> = GifEncoder::new(File::create(path).unwrap());
+        encoder.set_repeat(Repeat::Infinite).unwrap();
+        for index in 0..frame_count {
+            let image: RgbaImage = RgbaImage::from_fn(32, 16, |x, _| {
+                if index == 0 || (x / 4) % 2 == 0 { Rgba([0, 0, 0, 255]) } else { Rgba([250, 250, 250, 255]) }
+            });
+            encoder.encode_frame(Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(delay_ms, 1))).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_gif_animation() {
+        let path: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-animation-{}.gif", std::process::id()));
+        create_animated_gif(&path, 5, 40);
+        
+        let info: AnimationInfo = read_animation_info(&path).unwrap().unwrap();
+        assert_eq!(info, AnimationInfo { frame_count: 5, duration_ms: 200 });
+        
+        let frames: Vec<AnimationFrame> = decode_frames(&path).unwrap();
+        assert_eq!(frames.len(), 5);
+        assert_eq!(frames[1].delay, Duration::from_millis(40));
+        assert_eq!(load_frame(&path, 3).unwrap().width(), 32);
+        assert!(load_frame(&path, 5).is_err());
+        
+        // 第一帧为纯黑，代表帧应选择有内容的帧
+        let representative: RgbaImage = load_representative_frame(&path, &info).unwrap().to_rgba8();
+        assert_eq!(representative.get_pixel(5, 5)[0], 250);
+        
+        // 单帧 GIF 不视为动图
+        create_animated_gif(&path, 1, 40);
+        assert_eq!(read_animation_info(&path).unwrap(), None);
+        
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_webp_animation_info() {
+        // 手工构造的 RIFF 结构：VP8X（动画标志）加两个 ANMF 块，帧数据本身不会被解析
+        let mut chunks: Vec<u8> = Vec::new();
+        let mut push_chunk = |fourcc: &[u8], payload: &[u8]| {
+            chunks.extend_from_slice(fourcc);
+            chunks.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            chunks.extend_from_slice(payload);
+            if payload.len() % 2 == 1 {
+                chunks.push(0);
+            }
+        };
+        push_chunk(b"VP8X", &[0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        push_chunk(b"ANIM", &[0, 0, 0, 0, 0, 0]);
+        for duration in [120u32, 80] {
+            let mut frame: Vec<u8> = vec![0; 16];
+            frame[12..15].copy_from_slice(&duration.to_le_bytes()[..3]);
+            push_chunk(b"ANMF", &frame);
+        }
+        
+        let mut data: Vec<u8> = b"RIFF".to_vec();
+        data.extend_from_slice(&((chunks.len() + 4) as u32).to_le_bytes());
+        data.extend_from_slice(b"WEBP");
+        data.extend_from_slice(&chunks);
+        assert_eq!(parse_webp(&data), Some(AnimationInfo { frame_count: 2, duration_ms: 200 }));
+        
+        data[20] = 0;
+        assert_eq!(parse_webp(&data), None);
+    }
+
+    #[test]
+    fn test_animation_badge() {
+        let mut image: RgbaImage = RgbaImage::from_pixel(100, 50, Rgba([0, 200, 0, 255]));
+        draw_animation_badge(&mut image);
+        assert_eq!(image.get_pixel(10, 10).0, [0, 200, 0, 255]);
+        assert_eq!(image.get_pixel(94, 45).0, [255, 255, 255, 255]);
+        assert!(image.get_pixel(99, 49)[1] < 200);
+    }
+}
\ No newline at end of file
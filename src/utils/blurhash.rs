@@ -0,0 +1,168 @@
+use std::f32::consts::PI;
+use image::{DynamicImage, Rgb, RgbImage};
+use image::imageops::FilterType;
+use crate::{Result, WallpaperError};
+use crate::utils::{linear_to_srgb, srgb_to_linear};
+
+/// 占位图使用的 DCT 分量数（横向, 纵向），编码结果为 4 + 2 × 4 × 3 = 28 个字符
+pub const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+
+/// 编码前先缩小到此边长以内，分量数很少，更大的图像不会改变结果
+const ENCODE_MAX_SIDE: u32 = 32;
+
+const BASE83_CHARS: &[u8; 83] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+/// 计算图像的 BlurHash 字符串，网格在真正的缩略图加载前用它绘制模糊占位图
+pub fn encode_blurhash(image: &DynamicImage, components: (u32, u32)) -> String {
+    let (components_x, components_y) = (components.0.clamp(1, 9), components.1.clamp(1, 9));
+    let image: RgbImage = if image.width().max(image.height()) > ENCODE_MAX_SIDE {
+        image.resize(ENCODE_MAX_SIDE, ENCODE_MAX_SIDE, FilterType::Triangle).to_rgb8()
+    } else {
+        image.to_rgb8()
+    };
+    let (width, height) = (image.width().max(1) as f32, image.height().max(1) as f32);
+    let linear: Vec<(u32, u32, [f32; 3])> = image
+        .enumerate_pixels()
+        .map(|(x, y, pixel)| (x, y, pixel.0.map(srgb_to_linear)))
+        .collect();
+    
+    // 每个分量是图像与对应余弦基函数的内积
+    let mut factors: Vec<[f32; 3]> = Vec::with_capacity((components_x * components_y) as usize);
+    for j in 0..components_y {
+        for i in 0..components_x {
+            let normalization: f32 = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+            let mut factor: [f32; 3] = [0.0; 3];
+            for (x, y, color) in &linear {
+                let basis: f32 = (PI * i as f32 * *x as f32 / width).cos() * (PI * j as f32 * *y as f32 / height).cos();
+                for channel in 0..3 {
+                    factor[channel] += basis * color[channel];
+                }
+            }
+            factors.push(factor.map(|value| value * normalization / (width * height)));
+        }
+    }
+    
+    let mut hash: String = String::new();
+    encode_base83((components_x - 1) + (components_y - 1) * 9, 1, &mut hash);
+    
+    let ac: &[[f32; 3]] = &factors[1..];
+    let maximum: f32 = if ac.is_empty() {
+        encode_base83(0, 1, &mut hash);
+        1.0
+    } else {
+        let actual: f32 = ac.iter().flatten().fold(0.0f32, |max, value| max.max(value.abs()));
+        let quantised: u32 = ((actual * 166.0 - 0.5).floor()).clamp(0.0, 82.0) as u32;
+        encode_base83(quantised, 1, &mut hash);
+        (quantised + 1) as f32 / 166.0
+    };
+    
+    let [r, g, b] = factors[0].map(|value| linear_to_srgb(value) as u32);
+    encode_base83((r << 16) + (g << 8) + b, 4, &mut hash);
+    for factor in ac {
+        let [r, g, b] = factor.map(|value| ((sign_pow(value / maximum, 0.5) * 9.0 + 9.5).floor()).clamp(0.0, 18.0) as u32);
+        encode_base83(r * 19 * 19 + g * 19 + b, 2, &mut hash);
+    }
+    hash
+}
+
+/// 将 BlurHash 解码为指定尺寸的图像，`punch` 调整对比度（1.0 为原样）
+pub fn decode_blurhash(hash: &str, width: u32, height: u32, punch: f32) -> Result<RgbImage> {
+    let invalid = || WallpaperError::Service(format!("无效的 BlurHash: {}", hash));
+    // Base83 字符均为 ASCII，提前排除多字节字符，后续按字节切片不会落在字符中间
+    if !hash.is_ascii() {
+        return Err(invalid());
+    }
+    let size_flag: u32 = decode_base83(hash.get(0..1).ok_or_else(invalid)?).ok_or_else(invalid)?;
+    let (components_x, components_y) = (size_flag % 9 + 1, size_flag / 9 + 1);
+    if hash.len() != 4 + 2 * (components_x * components_y) as usize {
+        return Err(invalid());
+    }
+    
+    let quantised: u32 = decode_base83(&hash[1..2]).ok_or_else(invalid)?;
+    let maximum: f32 = (quantised + 1) as f32 / 166.0 * punch;
+    
+    let dc: u32 = decode_base83(&hash[2..6]).ok_or_else(invalid)?;
+    let mut colors: Vec<[f32; 3]> = vec![[dc >> 16, (dc >> 8) & 255, dc & 255].map(|value| srgb_to_linear(value as u8))];
+    for index in 1..(components_x * components_y) as usize {
+        let value: u32 = decode_base83(&hash[4 + index * 2..6 + index * 2]).ok_or_else(invalid)?;
+        colors.push([value / (19 * 19), (value / 19) % 19, value % 19].map(|quantised| {
+            sign_pow((quantised as f32 - 9.0) / 9.0, 2.0) * maximum
+        }));
+    }
+    
+    Ok(RgbImage::from_fn(width, height, |x, y| {
+        let mut pixel: [f32; 3] = [0.0; 3];
+        for j in 0..components_y {
+            for i in 0..components_x {
+                let basis: f32 = (PI * x as f32 * i as f32 / width as f32).cos() * (PI * y as f32 * j as f32 / height as f32).cos();
+                let color: [f32; 3] = colors[(i + j * components_x) as usize];
+                for channel in 0..3 {
+                    pixel[channel] += color[channel] * basis;
+                }
+            }
+        }
+        Rgb(pixel.map(linear_to_srgb))
+    }))
+}
+
+fn sign_pow(value: f32, exponent: f32) -> f32 {
+    value.abs().powf(exponent).copysign(value)
+}
+
+fn encode_base83(value: u32, length: u32, output: &mut String) {
+    for position in (0..length).rev() {
+        let digit: u32 = (value / 83u32.pow(position)) % 83;
+        output.push(BASE83_CHARS[digit as usize] as char);
+    }
+}
+
+fn decode_base83(input: &str) -> Option<u32> {
+    input.bytes().try_fold(0u32, |value, byte| {
+        let digit: usize = BASE83_CHARS.iter().position(|&c| c == byte)?;
+        Some(value * 83 + digit as u32)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blurhash_round_trip() {
+        // 左红右蓝的图像，占位图应保留左右两侧的主色
+        let image: RgbImage = RgbImage::from_fn(64, 32, |x, _| if x < 32 { Rgb([220, 40, 40]) } else { Rgb([40, 40, 220]) });
+        let hash: String = encode_blurhash(&DynamicImage::ImageRgb8(image), BLURHASH_COMPONENTS);
+        assert_eq!(hash.len(), 28);
+        
+        let placeholder: RgbImage = decode_blurhash(&hash, 32, 16, 1.0).unwrap();
+        let (left, right) = (placeholder.get_pixel(2, 8), placeholder.get_pixel(29, 8));
+        assert!(left[0] > left[2] + 80, "左侧应偏红: {:?}", left);
+        assert!(right[2] > right[0] + 80, "右侧应偏蓝: {:?}", right);
+        
+        // 纯色图像的解码结果接近原色（采样点上的余弦基并不完全正交，交流分量不为零）
+        let solid: DynamicImage = DynamicImage::ImageRgb8(RgbImage::from_pixel(32, 32, Rgb([10, 120, 200])));
+        let hash: String = encode_blurhash(&solid, BLURHASH_COMPONENTS);
+        let pixel: Rgb<u8> = *decode_blurhash(&hash, 4, 4, 1.0).unwrap().get_pixel(1, 1);
+        for (decoded, expected) in pixel.0.iter().zip([10u8, 120, 200]) {
+            assert!(decoded.abs_diff(expected) <= 12, "{:?}", pixel);
+        }
+    }
+
+    #[test]
+    fn test_decode_reference_hash() {
+        // BlurHash 官方示例
+        let image: RgbImage = decode_blurhash("LEHV6nWB2yk8pyo0adR*.7kCMdnj", 32, 32, 1.0).unwrap();
+        assert_eq!(image.dimensions(), (32, 32));
+        
+        assert!(decode_blurhash("LEHV6nWB2yk8", 32, 32, 1.0).is_err());
+        assert!(decode_blurhash("LEHV6nWB2yk8pyo0adR*.7kCMdn\"", 32, 32, 1.0).is_err());
+        assert!(decode_blurhash("", 32, 32, 1.0).is_err());
+    }
+
+    #[test]
+    fn test_decode_non_ascii_hash() {
+        // 字节长度恰好符合 1x1 分量的要求，按字节切片会落在 é 的中间
+        assert!(decode_blurhash("0\u{e9}abc", 4, 4, 1.0).is_err());
+        assert!(decode_blurhash("LEHV6nWB2yk8pyo0adR*.7kCMd\u{e9}", 4, 4, 1.0).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,383 @@
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+/// 粗略的颜色类别，用于按主色调分类壁纸
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorName {
+    Red,
+    Orange,
+    Yellow,
+    Green,
+    Cyan,
+    Blue,
+    Purple,
+    Pink,
+    Brown,
+    Black,
+    White,
+    Gray,
+}
+
+impl ColorName {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let color: ColorName = match name.to_lowercase().as_str() {
+            "red" => ColorName::Red,
+            "orange" => ColorName::Orange,
+            "yellow" => ColorName::Yellow,
+            "green" => ColorName::Green,
+            "cyan" => ColorName::Cyan,
+            "blue" => ColorName::Blue,
+            "purple" => ColorName::Purple,
+            "pink" => ColorName::Pink,
+            "brown" => ColorName::Brown,
+            "black" => ColorName::Black,
+            "white" => ColorName::White,
+            "gray" | "grey" => ColorName::Gray,
+            _ => return None,
+        };
+        Some(color)
+    }
+    
+    /// 该类别的代表色，用于按颜色名称搜索
+    pub fn get_rgb(self) -> [u8; 3] {
+        match self {
+            ColorName::Red => [210, 35, 35],
+            ColorName::Orange => [240, 140, 30],
+            ColorName::Yellow => [240, 210, 40],
+            ColorName::Green => [50, 160, 60],
+            ColorName::Cyan => [40, 190, 200],
+            ColorName::Blue => [35, 80, 200],
+            ColorName::Purple => [130, 60, 180],
+            ColorName::Pink => [235, 110, 170],
+            ColorName::Brown => [120, 75, 35],
+            ColorName::Black => [15, 15, 15],
+            ColorName::White => [245, 245, 245],
+            ColorName::Gray => [128, 128, 128],
+        }
+    }
+}
+
+/// 每张壁纸提取的调色板颜色数
+pub const PALETTE_SIZE: usize = 5;
+
+/// 亮度低于该值的壁纸视为深色壁纸
+pub const DARK_BRIGHTNESS_THRESHOLD: f32 = 0.35;
+
+/// 按颜色搜索时，只考虑占比不低于该值的调色板颜色
+const MIN_SEARCH_WEIGHT: f32 = 0.05;
+
+/// 调色板中的一种颜色及其在图像中的占比
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct PaletteColor {
+    pub rgb: [u8; 3],
+    /// 占比，0-1
+    pub weight: f32,
+}
+
+/// 壁纸的调色板和亮度，由缩略图计算
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ColorPalette {
+    /// 按占比从高到低排列
+    pub colors: Vec<PaletteColor>,
+    /// 平均亮度（CIE L*），0 为纯黑，1 为纯白
+    pub brightness: f32,
+}
+
+impl ColorPalette {
+    /// 主色，即占比最高的颜色
+    pub fn get_dominant(&self) -> Option<[u8; 3]> {
+        self.colors.first().map(|color| color.rgb)
+    }
+    
+    pub fn is_dark(&self) -> bool {
+        self.brightness < DARK_BRIGHTNESS_THRESHOLD
+    }
+    
+    /// 主色的色相（0-360），主色接近灰色时返回 `None`
+    pub fn get_hue(&self) -> Option<f32> {
+        self.get_dominant().and_then(color_hue)
+    }
+    
+    /// 调色板与目标颜色在 Lab 空间中的最近距离（CIE76 ΔE）
+    pub fn distance_to(&self, rgb: [u8; 3]) -> Option<f32> {
+        let target: [f32; 3] = rgb_to_lab(rgb);
+        
+        self.colors
+            .iter()
+            .filter(|color| color.weight >= MIN_SEARCH_WEIGHT)
+            .map(|color| delta_e(rgb_to_lab(color.rgb), target))
+            .min_by(|a, b| a.total_cmp(b))
+    }
+}
+
+/// 计算调色板时最多采样的像素数
+const PALETTE_SAMPLE_LIMIT: usize = 16 * 1024;
+
+/// 用中位切分法提取图像的调色板，并计算平均亮度
+///
+/// 应使用缩略图计算，完全透明的像素会被忽略
+pub fn extract_palette(image: &DynamicImage, max_colors: usize) -> ColorPalette {
+    let rgba: image::RgbaImage = image.to_rgba8();
+    let step: usize = (rgba.pixels().len() / PALETTE_SAMPLE_LIMIT).max(1);
+    let pixels: Vec<[u8; 3]> = rgba
+        .pixels()
+        .step_by(step)
+        .filter(|pixel| pixel.0[3] >= 128)
+        .map(|pixel| [pixel.0[0], pixel.0[1], pixel.0[2]])
+        .collect();
+    
+    if pixels.is_empty() || max_colors == 0 {
+        return ColorPalette::default();
+    }
+    
+    let brightness: f32 = pixels.iter().map(|&rgb| rgb_to_lab(rgb)[0]).sum::<f32>()
+        / pixels.len() as f32
+        / 100.0;
+    
+    let total: usize = pixels.len();
+    let mut boxes: Vec<Vec<[u8; 3]>> = vec![pixels];
+    while boxes.len() < max_colors {
+        // 选择颜色跨度最大的区域，沿跨度最大的通道在中位数处切分
+        let Some((index, channel, _)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, pixels)| pixels.len() > 1)
+            .map(|(index, pixels)| {
+                let (channel, range) = widest_channel(pixels);
+                (index, channel, range)
+            })
+            .filter(|(_, _, range)| *range > 0)
+            .max_by_key(|(_, _, range)| *range)
+        else {
+            break;
+        };
+        
+        let mut pixels: Vec<[u8; 3]> = boxes.swap_remove(index);
+        pixels.sort_unstable_by_key(|pixel| pixel[channel]);
+        
+        // 与中位数相同的像素分在同一侧，避免同一种颜色被拆成两个调色板颜色
+        let median: u8 = pixels[pixels.len() / 2][channel];
+        let mut split: usize = pixels.partition_point(|pixel| pixel[channel] <= median);
+        if split == pixels.len() {
+            split = pixels.partition_point(|pixel| pixel[channel] < median);
+        }
+        let upper: Vec<[u8; 3]> = pixels.split_off(split);
+        boxes.push(pixels);
+        boxes.push(upper);
+    }
+    
+    let mut colors: Vec<PaletteColor> = boxes
+        .iter()
+        .map(|pixels| {
+            let mut sums: [u64; 3] = [0; 3];
+            for pixel in pixels {
+                for channel in 0..3 {
+                    sums[channel] += pixel[channel] as u64;
+                }
+            }
+            PaletteColor {
+                rgb: sums.map(|sum| (sum / pixels.len() as u64) as u8),
+                weight: pixels.len() as f32 / total as f32,
+            }
+        })
+        .collect();
+    colors.sort_by(|a, b| b.weight.total_cmp(&a.weight));
+    
+    ColorPalette { colors, brightness }
+}
+
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let (min, max) = pixels.iter().fold((u8::MAX, u8::MIN), |(min, max), pixel| {
+                (min.min(pixel[channel]), max.max(pixel[channel]))
+            });
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap()
+}
+
+/// sRGB 通道值转换为 0..=1 的线性亮度
+pub fn srgb_to_linear(channel: u8) -> f32 {
+    let value: f32 = channel as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// 线性亮度转换回 sRGB 通道值，超出 0..=1 的部分被截断
+pub fn linear_to_srgb(value: f32) -> u8 {
+    let value: f32 = value.clamp(0.0, 1.0);
+    let encoded: f32 = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (encoded * 255.0 + 0.5) as u8
+}
+
+/// sRGB 转换为 CIE Lab（D65 白点）
+pub fn rgb_to_lab(rgb: [u8; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb.map(srgb_to_linear);
+    
+    let x: f32 = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y: f32 = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z: f32 = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    
+    let f = |t: f32| -> f32 {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// 两个 Lab 颜色之间的距离（CIE76 ΔE），约 2.3 为人眼可分辨的最小差异
+pub fn delta_e(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| (a - b).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+/// 颜色的色相（0-360），接近灰色时返回 `None`
+pub fn color_hue(rgb: [u8; 3]) -> Option<f32> {
+    let [r, g, b] = rgb.map(|channel| channel as f32 / 255.0);
+    let max: f32 = r.max(g).max(b);
+    let delta: f32 = max - r.min(g).min(b);
+    
+    if max < 0.2 || delta / max < 0.15 {
+        return None;
+    }
+    
+    Some(if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    })
+}
+
+/// 解析颜色：`#rrggbb`、`rrggbb` 或颜色名称（如 `blue`）
+pub fn parse_color(input: &str) -> Option<[u8; 3]> {
+    let hex: &str = input.strip_prefix('#').unwrap_or(input);
+    if hex.len() == 6 && hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        let channel = |index: usize| u8::from_str_radix(&hex[index..index + 2], 16).ok();
+        return Some([channel(0)?, channel(2)?, channel(4)?]);
+    }
+    
+    ColorName::from_name(input).map(ColorName::get_rgb)
+}
+
+/// 将 RGB 颜色归入粗略的颜色类别
+pub fn classify_color(rgb: [u8; 3]) -> ColorName {
+    let [r, g, b] = rgb.map(|channel| channel as f32 / 255.0);
+    let max: f32 = r.max(g).max(b);
+    let min: f32 = r.min(g).min(b);
+    let delta: f32 = max - min;
+    let saturation: f32 = if max > 0.0 { delta / max } else { 0.0 };
+    
+    if max < 0.2 {
+        return ColorName::Black;
+    }
+    if saturation < 0.15 {
+        return match max {
+            value if value > 0.85 => ColorName::White,
+            _ => ColorName::Gray,
+        };
+    }
+    
+    let hue: f32 = color_hue(rgb).unwrap_or(0.0);
+    
+    match hue {
+        h if !(15.0..345.0).contains(&h) => ColorName::Red,
+        h if h < 45.0 && max < 0.6 => ColorName::Brown,
+        h if h < 45.0 => ColorName::Orange,
+        h if h < 70.0 => ColorName::Yellow,
+        h if h < 160.0 => ColorName::Green,
+        h if h < 200.0 => ColorName::Cyan,
+        h if h < 260.0 => ColorName::Blue,
+        h if h < 290.0 => ColorName::Purple,
+        _ => ColorName::Pink,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_palette() {
+        let image: image::RgbImage = image::RgbImage::from_fn(10, 10, |x, _| {
+            if x < 7 {
+                image::Rgb([20, 60, 200])
+            } else {
+                image::Rgb([250, 250, 250])
+            }
+        });
+        
+        let palette: ColorPalette = extract_palette(&DynamicImage::ImageRgb8(image), 4);
+        assert_eq!(palette.colors.len(), 2);
+        assert_eq!(palette.get_dominant(), Some([20, 60, 200]));
+        assert!((palette.colors[0].weight - 0.7).abs() < 1e-6);
+        assert!(palette.brightness > 0.3 && palette.brightness < 0.6);
+        
+        let black: DynamicImage = DynamicImage::ImageRgb8(image::RgbImage::new(4, 4));
+        assert!(extract_palette(&black, 4).is_dark());
+    }
+
+    #[test]
+    fn test_lab_and_parsing() {
+        let white: [f32; 3] = rgb_to_lab([255, 255, 255]);
+        assert!((white[0] - 100.0).abs() < 0.1 && white[1].abs() < 0.5 && white[2].abs() < 0.5);
+        assert!(delta_e(rgb_to_lab([250, 0, 0]), rgb_to_lab([255, 0, 0])) < 3.0);
+        assert!(delta_e(rgb_to_lab([255, 0, 0]), rgb_to_lab([0, 0, 255])) > 100.0);
+        
+        assert_eq!(parse_color("#FF8800"), Some([255, 136, 0]));
+        assert_eq!(parse_color("grey"), Some([128, 128, 128]));
+        assert_eq!(parse_color("#ff88"), None);
+        assert_eq!(color_hue([128, 128, 128]), None);
+    }
+
+    #[test]
+    fn test_classify_color() {
+        assert_eq!(classify_color([220, 30, 30]), ColorName::Red);
+        assert_eq!(classify_color([240, 140, 20]), ColorName::Orange);
+        assert_eq!(classify_color([120, 70, 20]), ColorName::Brown);
+        assert_eq!(classify_color([30, 180, 60]), ColorName::Green);
+        assert_eq!(classify_color([20, 60, 200]), ColorName::Blue);
+        assert_eq!(classify_color([10, 10, 12]), ColorName::Black);
+        assert_eq!(classify_color([245, 245, 240]), ColorName::White);
+        assert_eq!(classify_color([128, 128, 128]), ColorName::Gray);
+    }
+
+    #[test]
+    fn test_palette_queries() {
+        let palette: ColorPalette = ColorPalette {
+            colors: vec![
+                PaletteColor { rgb: [20, 40, 160], weight: 0.7 },
+                PaletteColor { rgb: [250, 200, 40], weight: 0.28 },
+                PaletteColor { rgb: [255, 0, 0], weight: 0.02 },
+            ],
+            brightness: 0.3,
+        };
+        
+        assert_eq!(palette.get_dominant(), Some([20, 40, 160]));
+        assert!(palette.is_dark());
+        assert!(palette.get_hue().unwrap() > 200.0);
+        assert!(palette.distance_to([255, 210, 50]).unwrap() < 10.0);
+        // 占比过低的颜色不参与搜索
+        assert!(palette.distance_to([255, 0, 0]).unwrap() > 50.0);
+        assert_eq!(ColorPalette::default().distance_to([0, 0, 0]), None);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,335 @@
+use std::path::Path;
+use image::DynamicImage;
+use crate::{Result, WallpaperError};
+use crate::utils::RAW_EXTENSIONS;
+
+/// 需要启用对应 cargo feature 才能读取的格式
+///
+/// AVIF 由 image 的 `avif-native` 解码器（dav1d）处理，只需登记扩展名；
+/// HEIF、JPEG XL、SVG 和相机 RAW 由本模块解码，不经过 `ImageReader`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtraFormat {
+    Avif,
+    Heif,
+    JpegXl,
+    Svg,
+    /// 相机 RAW（CR2、NEF、ARW、DNG）
+    Raw,
+}
+
+impl ExtraFormat {
+    pub const ALL: &'static [ExtraFormat] = &[
+        ExtraFormat::Avif,
+        ExtraFormat::Heif,
+        ExtraFormat::JpegXl,
+        ExtraFormat::Svg,
+        ExtraFormat::Raw,
+    ];
+    
+    pub fn get_extensions(self) -> &'static [&'static str] {
+        match self {
+            ExtraFormat::Avif => &["avif"],
+            ExtraFormat::Heif => &["heic", "heif", "hif"],
+            ExtraFormat::JpegXl => &["jxl"],
+            ExtraFormat::Svg => &["svg", "svgz"],
+            ExtraFormat::Raw => RAW_EXTENSIONS,
+        }
+    }
+    
+    /// 编译时是否启用了该格式的 feature
+    pub fn is_enabled(self) -> bool {
+        match self {
+            ExtraFormat::Avif => cfg!(feature = "avif"),
+            ExtraFormat::Heif => cfg!(feature = "heif"),
+            ExtraFormat::JpegXl => cfg!(feature = "jxl"),
+            ExtraFormat::Svg => cfg!(feature = "svg"),
+            ExtraFormat::Raw => cfg!(feature = "raw"),
+        }
+    }
+    
+    /// 按扩展名识别可选格式，不论是否启用
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|format| format.get_extensions().iter().any(|ext| ext.eq_ignore_ascii_case(extension)))
+    }
+    
+    /// 按扩展名识别已启用的可选格式
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(path.extension()?.to_str()?).filter(|format| format.is_enabled())
+    }
+    
+    /// 识别需要由本模块解码的格式，其余格式（包括 AVIF）交给 `ImageReader`
+    pub fn detect_custom(path: &Path) -> Option<Self> {
+        Self::from_path(path).filter(|format| *format != ExtraFormat::Avif)
+    }
+    
+    /// 图像显示时的尺寸，SVG 为其固有尺寸
+    pub fn read_dimensions(self, path: &Path) -> Result<(u32, u32)> {
+        match self {
+            #[cfg(feature = "heif")]
+            ExtraFormat::Heif => heif::read_dimensions(path),
+            #[cfg(feature = "jxl")]
+            ExtraFormat::JpegXl => jxl::read_dimensions(path),
+            #[cfg(feature = "svg")]
+            ExtraFormat::Svg => svg::read_dimensions(path),
+            // RAW 结构由内置的 TIFF 解析器读取，不需要 rawloader
+            ExtraFormat::Raw => crate::utils::read_raw_dimensions(path),
+            _ => Err(self.not_enabled(path)),
+        }
+    }
+    
+    /// 解码图像，已应用文件中的旋转和翻转；SVG 按 `target` 边界框栅格化，
+    /// 为 `None` 时按固有尺寸栅格化；RAW 在给出 `target` 时使用足够大的内嵌预览，
+    /// 为 `None` 时完整显影；其他格式忽略 `target`
+    #[cfg_attr(not(any(feature = "svg", feature = "raw")), allow(unused_variables))]
+    pub fn decode(self, path: &Path, target: Option<(u32, u32)>) -> Result<DynamicImage> {
+        match self {
+            #[cfg(feature = "heif")]
+            ExtraFormat::Heif => heif::decode(path),
+            #[cfg(feature = "jxl")]
+            ExtraFormat::JpegXl => jxl::decode(path),
+            #[cfg(feature = "svg")]
+            ExtraFormat::Svg => svg::render(path, target),
+            #[cfg(feature = "raw")]
+            ExtraFormat::Raw => {
+                let preview: Option<DynamicImage> = match target {
+                    Some((width, height)) => crate::utils::load_raw_preview(path, width.max(height))?,
+                    None => None,
+                };
+                match preview {
+                    Some(preview) => Ok(preview),
+                    None => crate::utils::develop_raw(path),
+                }
+            }
+            _ => Err(self.not_enabled(path)),
+        }
+    }
+    
+    fn not_enabled(self, path: &Path) -> WallpaperError {
+        WallpaperError::Service(format!("未启用 {:?} 格式支持: {:?}", self, path))
+    }
+}
+
+/// 当前编译启用的可选格式的全部扩展名
+pub fn enabled_extra_extensions() -> Vec<&'static str> {
+    ExtraFormat::ALL
+        .iter()
+        .filter(|format| format.is_enabled())
+        .flat_map(|format| format.get_extensions().iter().copied())
+        .collect()
+}
+
+/// 将第三方解码器的错误包装为图像解码错误，使缩略图服务能记录解码失败
+#[cfg(any(feature = "heif", feature = "jxl", feature = "svg", feature = "raw"))]
+pub(crate) fn decoding_error(format: &str, error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> WallpaperError {
+    use image::error::{DecodingError, ImageError, ImageFormatHint};
+    
+    WallpaperError::Image(ImageError::Decoding(DecodingError::new(ImageFormatHint::Name(format.to_string()), error)))
+}
+
+#[cfg(feature = "heif")]
+mod heif {
+    use std::path::Path;
+    use image::{DynamicImage, RgbaImage};
+    use libheif_rs::{ColorSpace, HeifContext, ImageHandle, LibHeif, RgbChroma};
+    use super::decoding_error;
+    use crate::Result;
+
+    fn open_primary(path: &Path) -> Result<ImageHandle> {
+        let context: HeifContext = HeifContext::read_from_file(&path.to_string_lossy())
+            .map_err(|e| decoding_error("HEIF", e))?;
+        context.primary_image_handle().map_err(|e| decoding_error("HEIF", e))
+    }
+    
+    pub fn read_dimensions(path: &Path) -> Result<(u32, u32)> {
+        let handle: ImageHandle = open_primary(path)?;
+        Ok((handle.width(), handle.height()))
+    }
+    
+    /// libheif 解码时会应用 irot/imir 变换
+    pub fn decode(path: &Path) -> Result<DynamicImage> {
+        let handle: ImageHandle = open_primary(path)?;
+        let image: libheif_rs::Image = LibHeif::new()
+            .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+            .map_err(|e| decoding_error("HEIF", e))?;
+        
+        let planes = image.planes();
+        let plane = planes.interleaved.ok_or_else(|| decoding_error("HEIF", "缺少交错的 RGBA 数据"))?;
+        let row_bytes: usize = plane.width as usize * 4;
+        let pixels: Vec<u8> = plane.data
+            .chunks(plane.stride)
+            .take(plane.height as usize)
+            .flat_map(|row| row[..row_bytes].iter().copied())
+            .collect();
+        
+        RgbaImage::from_raw(plane.width, plane.height, pixels)
+            .map(DynamicImage::ImageRgba8)
+            .ok_or_else(|| decoding_error("HEIF", "像素数据长度与尺寸不符"))
+    }
+}
+
+#[cfg(feature = "jxl")]
+mod jxl {
+    use std::fs::File;
+    use std::io::BufReader;
+    use std::path::Path;
+    use image::{DynamicImage, ImageDecoder};
+    use jxl_oxide::integration::JxlDecoder;
+    use super::decoding_error;
+    use crate::Result;
+
+    fn open(path: &Path) -> Result<JxlDecoder<BufReader<File>>> {
+        JxlDecoder::new(BufReader::new(File::open(path)?)).map_err(|e| decoding_error("JPEG XL", e))
+    }
+    
+    pub fn read_dimensions(path: &Path) -> Result<(u32, u32)> {
+        Ok(open(path)?.dimensions())
+    }
+    
+    /// jxl-oxide 渲染时会应用头部中的方向信息
+    pub fn decode(path: &Path) -> Result<DynamicImage> {
+        Ok(DynamicImage::from_decoder(open(path)?)?)
+    }
+}
+
+#[cfg(feature = "svg")]
+mod svg {
+    use std::path::Path;
+    use std::sync::{Arc, OnceLock};
+    use image::{DynamicImage, RgbaImage};
+    use resvg::{tiny_skia, usvg};
+    use super::decoding_error;
+    use crate::Result;
+
+    /// 栅格化尺寸上限，防止声明了超大画布的 SVG 占用过多内存
+    const MAX_RASTER_SIDE: u32 = 8192;
+    
+    /// 系统字体只加载一次，供所有 SVG 中的文字共用
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    
+    fn load_tree(path: &Path) -> Result<usvg::Tree> {
+        // from_data 会自动解压 svgz
+        let data: Vec<u8> = std::fs::read(path)?;
+        let options: usvg::Options = usvg::Options {
+            resources_dir: path.parent().map(Path::to_path_buf),
+            fontdb: FONTS
+                .get_or_init(|| {
+                    let mut database: usvg::fontdb::Database = usvg::fontdb::Database::new();
+                    database.load_system_fonts();
+                    Arc::new(database)
+                })
+                .clone(),
+            ..usvg::Options::default()
+        };
+        usvg::Tree::from_data(&data, &options).map_err(|e| decoding_error("SVG", e))
+    }
+    
+    pub fn read_dimensions(path: &Path) -> Result<(u32, u32)> {
+        let size: usvg::Size = load_tree(path)?.size();
+        Ok((size.width().ceil() as u32, size.height().ceil() as u32))
+    }
+    
+    /// 按目标边界框缩放后栅格化，矢量图允许放大
+    pub fn render(path: &Path, target: Option<(u32, u32)>) -> Result<DynamicImage> {
+        let tree: usvg::Tree = load_tree(path)?;
+        let size: usvg::Size = tree.size();
+        let (bound_width, bound_height) = target
+            .unwrap_or((size.width().ceil() as u32, size.height().ceil() as u32));
+        let scale: f32 = (bound_width.min(MAX_RASTER_SIDE) as f32 / size.width())
+            .min(bound_height.min(MAX_RASTER_SIDE) as f32 / size.height());
+        let width: u32 = ((size.width() * scale).round() as u32).max(1);
+        let height: u32 = ((size.height() * scale).round() as u32).max(1);
+        
+        let mut pixmap: tiny_skia::Pixmap = tiny_skia::Pixmap::new(width, height)
+            .ok_or_else(|| decoding_error("SVG", format!("无效的栅格化尺寸 {}x{}", width, height)))?;
+        resvg::render(&tree, tiny_skia::Transform::from_scale(scale, scale), &mut pixmap.as_mut());
+        
+        // tiny-skia 使用预乘 alpha
+        let pixels: Vec<u8> = pixmap
+            .pixels()
+            .iter()
+            .flat_map(|pixel| {
+                let color: tiny_skia::ColorU8 = pixel.demultiply();
+                [color.red(), color.green(), color.blue(), color.alpha()]
+            })
+            .collect();
+        RgbaImage::from_raw(width, height, pixels)
+            .map(DynamicImage::ImageRgba8)
+            .ok_or_else(|| decoding_error("SVG", "像素数据长度与尺寸不符"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(any(feature = "avif", feature = "heif", feature = "jxl", feature = "svg"))]
+    use std::path::PathBuf;
+    
+    #[cfg(any(feature = "avif", feature = "heif", feature = "jxl", feature = "svg"))]
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join(name)
+    }
+    
+    /// 夹具图像为 16x8，左半红色、右半蓝色
+    #[cfg(any(feature = "avif", feature = "heif", feature = "jxl", feature = "svg"))]
+    fn assert_fixture_image(image: &DynamicImage) {
+        let image: image::RgbaImage = image.to_rgba8();
+        assert_eq!(image.dimensions(), (16, 8));
+        let (left, right) = (image.get_pixel(2, 4), image.get_pixel(13, 4));
+        assert!(left[0] > 150 && left[2] < 100, "左半部分应为红色: {:?}", left);
+        assert!(right[2] > 150 && right[0] < 100, "右半部分应为蓝色: {:?}", right);
+    }
+
+    #[test]
+    fn test_extra_format_detection() {
+        assert_eq!(ExtraFormat::from_path(Path::new("a.AVIF")).is_some(), cfg!(feature = "avif"));
+        assert_eq!(ExtraFormat::detect_custom(Path::new("a.heic")).is_some(), cfg!(feature = "heif"));
+        assert_eq!(ExtraFormat::detect_custom(Path::new("a.svgz")).is_some(), cfg!(feature = "svg"));
+        assert_eq!(ExtraFormat::detect_custom(Path::new("a.avif")), None);
+        assert_eq!(ExtraFormat::from_path(Path::new("a.jpg")), None);
+        assert_eq!(enabled_extra_extensions().contains(&"jxl"), cfg!(feature = "jxl"));
+        assert_eq!(ExtraFormat::detect_custom(Path::new("IMG_0001.CR2")).is_some(), cfg!(feature = "raw"));
+        assert_eq!(ExtraFormat::from_extension("HIF"), Some(ExtraFormat::Heif));
+        assert_eq!(ExtraFormat::from_extension("png"), None);
+    }
+    
+    #[cfg(feature = "avif")]
+    #[test]
+    fn test_avif_fixture() {
+        let path: PathBuf = fixture("sample.avif");
+        assert_eq!(crate::utils::get_image_dimensions(&path).unwrap(), (16, 8));
+        assert_fixture_image(&crate::utils::open_oriented(&path).unwrap());
+    }
+    
+    #[cfg(feature = "heif")]
+    #[test]
+    fn test_heif_fixture() {
+        let path: PathBuf = fixture("sample.heic");
+        assert_eq!(ExtraFormat::Heif.read_dimensions(&path).unwrap(), (16, 8));
+        assert_fixture_image(&ExtraFormat::Heif.decode(&path, None).unwrap());
+    }
+    
+    #[cfg(feature = "jxl")]
+    #[test]
+    fn test_jxl_fixture() {
+        let path: PathBuf = fixture("sample.jxl");
+        assert_eq!(ExtraFormat::JpegXl.read_dimensions(&path).unwrap(), (16, 8));
+        assert_fixture_image(&ExtraFormat::JpegXl.decode(&path, None).unwrap());
+    }
+    
+    #[cfg(feature = "svg")]
+    #[test]
+    fn test_svg_fixture() {
+        let path: PathBuf = fixture("sample.svg");
+        assert_eq!(ExtraFormat::Svg.read_dimensions(&path).unwrap(), (16, 8));
+        assert_fixture_image(&ExtraFormat::Svg.decode(&path, None).unwrap());
+        
+        // 按目标尺寸栅格化，矢量图可以放大
+        let image: DynamicImage = ExtraFormat::Svg.decode(&path, Some((400, 400))).unwrap();
+        assert_eq!((image.width(), image.height()), (400, 200));
+        assert_eq!(crate::utils::get_image_dimensions(&path).unwrap(), (16, 8));
+        assert_eq!(crate::utils::load_for_thumbnail(&path, 64).unwrap().width(), 64);
+    }
+}
\ No newline at end of file
@@ -1,5 +1,8 @@
-use std::path::Path;
-use crate::Result;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use crate::{Result, WallpaperError};
+use crate::utils::{stable_hash, stable_hash_update};
 
 /// 格式化文件大小为人类可读的字符串
 pub fn format_file_size(bytes: u64) -> String {
@@ -49,6 +52,138 @@ pub fn safe_remove_file(path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// 在目标目录中为文件名生成不冲突的路径，重名时追加 ` (n)` 后缀
+pub fn unique_destination_path(directory: &Path, filename: &str) -> PathBuf {
+    let candidate: PathBuf = directory.join(filename);
+    if !candidate.exists() {
+        return candidate;
+    }
+    
+    let original: &Path = Path::new(filename);
+    let stem: String = original.file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| filename.to_string());
+    let extension: Option<String> = original.extension()
+        .map(|ext| ext.to_string_lossy().to_string());
+    
+    let mut counter: u32 = 1;
+    loop {
+        let name: String = match &extension {
+            Some(ext) => format!("{} ({}).{}", stem, counter, ext),
+            None => format!("{} ({})", stem, counter),
+        };
+        let candidate: PathBuf = directory.join(name);
+        if !candidate.exists() {
+            return candidate;
+        }
+        counter += 1;
+    }
+}
+
+/// 移动文件，跨文件系统时回退为复制后删除
+pub fn move_file(source: &Path, destination: &Path) -> Result<()> {
+    if std::fs::rename(source, destination).is_err() {
+        std::fs::copy(source, destination)?;
+        std::fs::remove_file(source)?;
+    }
+    Ok(())
+}
+
+/// 读取 TOML 数据文件，文件不存在时返回 `None`
+pub fn load_toml_file<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    
+    let content: String = std::fs::read_to_string(path)?;
+    let value: T = toml::from_str(&content)
+        .map_err(|e| WallpaperError::Service(format!("解析数据文件 {:?} 失败: {}", path, e)))?;
+    Ok(Some(value))
+}
+
+/// 写入 TOML 数据文件，先写临时文件再重命名，避免中途退出导致文件损坏
+pub fn save_toml_file<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    
+    let content: String = toml::to_string_pretty(value)
+        .map_err(|e| WallpaperError::Service(format!("序列化数据文件 {:?} 失败: {}", path, e)))?;
+    
+    let temp_path: PathBuf = path.with_extension("toml.tmp");
+    std::fs::write(&temp_path, content)?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(())
+}
+
+/// 计算文件指纹时读取的头部和尾部字节数
+const FINGERPRINT_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// 计算文件内容指纹（文件大小加头尾各 64 KiB 的 FNV-1a 哈希），用于识别被重命名或移动的文件
+///
+/// 指纹会持久化，因此不使用跨版本不稳定的 `DefaultHasher`
+pub fn file_fingerprint(path: &Path) -> Result<String> {
+    use std::io::{Read, Seek, SeekFrom};
+    
+    let mut file: std::fs::File = std::fs::File::open(path)?;
+    let size: u64 = file.metadata()?.len();
+    
+    let mut buffer: Vec<u8> = Vec::new();
+    (&mut file).take(FINGERPRINT_CHUNK_SIZE).read_to_end(&mut buffer)?;
+    if size > FINGERPRINT_CHUNK_SIZE * 2 {
+        file.seek(SeekFrom::End(-(FINGERPRINT_CHUNK_SIZE as i64)))?;
+        file.take(FINGERPRINT_CHUNK_SIZE).read_to_end(&mut buffer)?;
+    } else {
+        file.read_to_end(&mut buffer)?;
+    }
+    
+    Ok(format!("{:x}-{:016x}", size, stable_hash(&buffer)))
+}
+
+/// 计算整个文件内容的哈希，内容相同的文件结果相同
+pub fn content_hash(path: &Path) -> Result<String> {
+    use std::io::Read;
+    
+    let mut file: std::fs::File = std::fs::File::open(path)?;
+    let mut buffer: Vec<u8> = vec![0; FINGERPRINT_CHUNK_SIZE as usize];
+    let mut hash: u64 = stable_hash(&[]);
+    let mut size: u64 = 0;
+    loop {
+        let read: usize = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hash = stable_hash_update(hash, &buffer[..read]);
+        size += read as u64;
+    }
+    
+    Ok(format!("{:x}-{:016x}", size, hash))
+}
+
+/// 将路径转换为 `file://` URI，与 GLib 的 `g_filename_to_uri` 使用相同的转义规则，
+/// 使生成的 URI 与文件管理器一致
+pub fn file_uri(path: &Path) -> Result<String> {
+    let absolute: PathBuf = std::path::absolute(path)?;
+    
+    #[cfg(unix)]
+    let bytes: Vec<u8> = std::os::unix::ffi::OsStrExt::as_bytes(absolute.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    let bytes: Vec<u8> = absolute.to_string_lossy().replace('\\', "/").into_bytes();
+    
+    let mut uri: String = String::from("file://");
+    if !bytes.starts_with(b"/") {
+        uri.push('/');
+    }
+    for byte in bytes {
+        if byte.is_ascii_alphanumeric() || b"!$&'()*+,-./:=@_~".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    Ok(uri)
+}
+
 /// 计算目录大小
 pub fn calculate_directory_size(path: &Path) -> Result<u64> {
     let mut total_size: u64 = 0;
@@ -80,4 +215,39 @@ mod tests {
         assert_eq!(format_file_size(1536), "1.5 KB");
         assert_eq!(format_file_size(1048576), "1.0 MB");
     }
+
+    #[test]
+    fn test_file_fingerprint() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-fingerprint-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        
+        let content: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(directory.join("a.jpg"), &content).unwrap();
+        std::fs::write(directory.join("b.jpg"), &content).unwrap();
+        let mut changed: Vec<u8> = content.clone();
+        *changed.last_mut().unwrap() ^= 1;
+        std::fs::write(directory.join("c.jpg"), &changed).unwrap();
+        
+        let fingerprint: String = file_fingerprint(&directory.join("a.jpg")).unwrap();
+        assert_eq!(fingerprint, file_fingerprint(&directory.join("b.jpg")).unwrap());
+        assert_ne!(fingerprint, file_fingerprint(&directory.join("c.jpg")).unwrap());
+        
+        // 指纹只读取头尾，中间的修改只有完整的内容哈希能发现
+        let mut middle: Vec<u8> = content.clone();
+        middle[100_000] ^= 1;
+        std::fs::write(directory.join("d.jpg"), &middle).unwrap();
+        assert_eq!(fingerprint, file_fingerprint(&directory.join("d.jpg")).unwrap());
+        assert_eq!(content_hash(&directory.join("a.jpg")).unwrap(), content_hash(&directory.join("b.jpg")).unwrap());
+        assert_ne!(content_hash(&directory.join("a.jpg")).unwrap(), content_hash(&directory.join("d.jpg")).unwrap());
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+    
+    #[cfg(unix)]
+    #[test]
+    fn test_file_uri() {
+        assert_eq!(file_uri(Path::new("/home/jens/photos/me.png")).unwrap(), "file:///home/jens/photos/me.png");
+        assert_eq!(file_uri(Path::new("/壁纸/a b#1.jpg")).unwrap(), "file:///%E5%A3%81%E7%BA%B8/a%20b%231.jpg");
+    }
 } 
\ No newline at end of file
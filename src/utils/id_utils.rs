@@ -0,0 +1,52 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use md5::{Digest, Md5};
+
+static ID_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 生成用于收藏夹、智能收藏等用户数据的唯一ID
+pub fn generate_unique_id() -> String {
+    let timestamp: i64 = chrono::Utc::now()
+        .timestamp_nanos_opt()
+        .unwrap_or_default();
+    let counter: u64 = ID_COUNTER.fetch_add(1, Ordering::Relaxed);
+    
+    format!("{:x}{:04x}", timestamp, counter & 0xffff)
+}
+
+/// FNV-1a 64 位哈希，结果在不同版本和平台间保持稳定，可用于持久化
+pub fn stable_hash(bytes: &[u8]) -> u64 {
+    stable_hash_update(0xcbf29ce484222325, bytes)
+}
+
+/// 在已有的 FNV-1a 哈希值上继续哈希更多数据，用于分块计算大文件的哈希
+pub fn stable_hash_update(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |hash: u64, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// splitmix64 混合函数，用于可复现的伪随机数
+pub fn splitmix64(value: u64) -> u64 {
+    let mut z: u64 = value.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// MD5 摘要的十六进制表示，freedesktop.org 缩略图规范用它生成文件名，不可用于安全用途
+pub fn md5_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Md5::digest(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_md5_hex() {
+        assert_eq!(md5_hex(b""), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(md5_hex(b"The quick brown fox jumps over the lazy dog"), "9e107d9d372bb6826bd81d3542a419d6");
+        // freedesktop.org 缩略图规范中的示例
+        assert_eq!(md5_hex(b"file:///home/jens/photos/me.png"), "c6ee772d9e49320e97ec29a7eb5b1697");
+    }
+}
\ No newline at end of file
@@ -1,14 +1,20 @@
 use std::path::Path;
-use image::ImageFormat;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, RgbaImage};
+use image::imageops::FilterType;
+use image::metadata::Orientation;
 use crate::Result;
+use crate::utils::ExtraFormat;
 
 /// 支持的图像格式列表
 pub const SUPPORTED_FORMATS: &[&str] = &[
     "jpg", "jpeg", "png", "bmp", "gif", "webp", "tiff", "tga", "ico"
 ];
 
-/// 检查文件是否为支持的图像格式
+/// 检查文件是否为支持的图像格式，包括编译时启用的可选格式
 pub fn is_supported_image_format(path: &Path) -> bool {
+    if ExtraFormat::from_path(path).is_some() {
+        return true;
+    }
     if let Some(extension) = path.extension() {
         if let Some(ext_str) = extension.to_str() {
             return SUPPORTED_FORMATS.iter()
@@ -18,10 +24,70 @@ pub fn is_supported_image_format(path: &Path) -> bool {
     false
 }
 
-/// 获取图像的尺寸而不完全加载图像
+/// 获取图像按 EXIF 方向显示时的尺寸，不完全加载图像
 pub fn get_image_dimensions(path: &Path) -> Result<(u32, u32)> {
-    let dimensions = image::image_dimensions(path)?;
-    Ok(dimensions)
+    if let Some(format) = ExtraFormat::detect_custom(path) {
+        return format.read_dimensions(path);
+    }
+    
+    let mut decoder = ImageReader::open(path)?.with_guessed_format()?.into_decoder()?;
+    let orientation: Orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let (width, height) = decoder.dimensions();
+    
+    if orientation_swaps_dimensions(orientation) {
+        Ok((height, width))
+    } else {
+        Ok((width, height))
+    }
+}
+
+/// 读取图像的 EXIF 方向，没有方向信息时返回 `NoTransforms`
+pub fn read_orientation(path: &Path) -> Result<Orientation> {
+    // 可选格式的解码器自行应用方向
+    if ExtraFormat::detect_custom(path).is_some() {
+        return Ok(Orientation::NoTransforms);
+    }
+    
+    let mut decoder = ImageReader::open(path)?.with_guessed_format()?.into_decoder()?;
+    Ok(decoder.orientation().unwrap_or(Orientation::NoTransforms))
+}
+
+/// 加载图像并按 EXIF 方向旋转或翻转，得到实际显示的图像
+pub fn open_oriented(path: &Path) -> Result<DynamicImage> {
+    if let Some(format) = ExtraFormat::detect_custom(path) {
+        return format.decode(path, None);
+    }
+    
+    let mut decoder = ImageReader::open(path)?.with_guessed_format()?.into_decoder()?;
+    let orientation: Orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    
+    let mut image: DynamicImage = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+    Ok(image)
+}
+
+/// 加载用于预览的图像：应用 EXIF 方向，并缩小到不超过给定尺寸
+pub fn load_preview(path: &Path, max_width: u32, max_height: u32) -> Result<DynamicImage> {
+    // SVG 直接按预览尺寸栅格化
+    let image: DynamicImage = match ExtraFormat::detect_custom(path) {
+        Some(format) => format.decode(path, Some((max_width, max_height)))?,
+        None => open_oriented(path)?,
+    };
+    
+    if image.width() <= max_width && image.height() <= max_height {
+        return Ok(image);
+    }
+    
+    let (width, height) = calculate_scaled_size(image.width(), image.height(), max_width, max_height);
+    Ok(image.resize(width, height, FilterType::Triangle))
+}
+
+/// 方向变换是否会交换宽高（旋转 90° 或 270°）
+pub fn orientation_swaps_dimensions(orientation: Orientation) -> bool {
+    matches!(
+        orientation,
+        Orientation::Rotate90 | Orientation::Rotate270 | Orientation::Rotate90FlipH | Orientation::Rotate270FlipH
+    )
 }
 
 /// 计算保持宽高比的缩放尺寸
@@ -55,11 +121,29 @@ pub fn format_from_path(path: &Path) -> Option<ImageFormat> {
                 "tiff" | "tif" => Some(ImageFormat::Tiff),
                 "tga" => Some(ImageFormat::Tga),
                 "ico" => Some(ImageFormat::Ico),
+                "avif" => Some(ImageFormat::Avif),
                 _ => None,
             }
         })
 }
 
+/// 宽高比比较的容差，吸收 1920x1080 与 16:9 之类的舍入误差，标签规则与查询语法共用
+pub const ASPECT_RATIO_TOLERANCE: f32 = 0.01;
+
+/// `jpg` 与 `jpeg` 是同一格式的两种扩展名（不区分大小写）
+pub fn is_jpeg_alias(format: &str) -> bool {
+    format.eq_ignore_ascii_case("jpg") || format.eq_ignore_ascii_case("jpeg")
+}
+
+/// 规范化的格式名：小写，`jpeg` 统一为 `jpg`
+pub fn normalize_format(format: &str) -> String {
+    if is_jpeg_alias(format) {
+        "jpg".to_string()
+    } else {
+        format.to_lowercase()
+    }
+}
+
 /// 获取图像的宽高比
 pub fn get_aspect_ratio(width: u32, height: u32) -> f32 {
     if height == 0 {
@@ -84,10 +168,34 @@ pub fn is_square(width: u32, height: u32) -> bool {
     width == height
 }
 
+/// 图像是否含有不完全不透明的像素
+pub fn has_transparency(image: &DynamicImage) -> bool {
+    image.color().has_alpha() && image.to_rgba8().pixels().any(|pixel| pixel[3] < u8::MAX)
+}
+
+/// 将图像叠加在棋盘格背景上，用于显示透明区域，`cell_size` 为格子边长（像素）
+pub fn composite_on_checkerboard(image: &DynamicImage, cell_size: u32) -> RgbaImage {
+    const LIGHT: [u8; 3] = [255, 255, 255];
+    const DARK: [u8; 3] = [204, 204, 204];
+    let cell_size: u32 = cell_size.max(1);
+    
+    let mut output: RgbaImage = image.to_rgba8();
+    for (x, y, pixel) in output.enumerate_pixels_mut() {
+        let background: [u8; 3] = if (x / cell_size + y / cell_size).is_multiple_of(2) { LIGHT } else { DARK };
+        let alpha: u32 = pixel[3] as u32;
+        for (value, background) in pixel.0.iter_mut().zip(background) {
+            *value = ((*value as u32 * alpha + background as u32 * (255 - alpha) + 127) / 255) as u8;
+        }
+        pixel[3] = u8::MAX;
+    }
+    output
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::path::PathBuf;
+    use crate::utils::test_fixtures::{exif_orientation_segment, write_jpeg_with_app1};
 
     #[test]
     fn test_is_supported_image_format() {
@@ -117,4 +225,73 @@ mod tests {
         assert!(is_portrait(1080, 1920));
         assert!(is_square(1080, 1080));
     }
+    
+    /// 构造带有 EXIF 方向的 JPEG：原始像素为 `displayed` 经过方向逆变换后的结果
+    fn create_oriented_jpeg(path: &Path, displayed: &image::RgbImage, orientation: u16) {
+        use image::imageops::{flip_horizontal, flip_vertical, rotate180, rotate270, rotate90};
+        
+        let stored: image::RgbImage = match orientation {
+            2 => flip_horizontal(displayed),
+            3 => rotate180(displayed),
+            4 => flip_vertical(displayed),
+            5 => flip_horizontal(&rotate90(displayed)),
+            6 => rotate270(displayed),
+            7 => flip_horizontal(&rotate270(displayed)),
+            8 => rotate90(displayed),
+            _ => displayed.clone(),
+        };
+        
+        write_jpeg_with_app1(path, &stored, &[exif_orientation_segment(orientation)]);
+    }
+
+    #[test]
+    fn test_exif_orientation() {
+        // 四个象限颜色不同，可以区分所有旋转和翻转
+        let quadrants: [[u8; 3]; 4] = [[220, 30, 30], [30, 200, 30], [30, 30, 220], [240, 240, 240]];
+        let displayed: image::RgbImage = image::RgbImage::from_fn(64, 32, |x, y| {
+            image::Rgb(quadrants[(x / 32 + 2 * (y / 16)) as usize])
+        });
+        
+        for orientation in 1..=8u16 {
+            let path: PathBuf = std::env::temp_dir()
+                .join(format!("wallpaper-explorer-orientation-{}-{}.jpg", orientation, std::process::id()));
+            create_oriented_jpeg(&path, &displayed, orientation);
+            
+            let expected: Orientation = Orientation::from_exif(orientation as u8).unwrap();
+            assert_eq!(read_orientation(&path).unwrap(), expected);
+            assert_eq!(get_image_dimensions(&path).unwrap(), (64, 32), "方向 {}", orientation);
+            
+            let image: image::RgbImage = open_oriented(&path).unwrap().to_rgb8();
+            assert_eq!(image.dimensions(), (64, 32));
+            for (index, color) in quadrants.iter().enumerate() {
+                let (x, y) = (16 + 32 * (index as u32 % 2), 8 + 16 * (index as u32 / 2));
+                let actual: [u8; 3] = image.get_pixel(x, y).0;
+                let close: bool = actual.iter().zip(color).all(|(a, b)| a.abs_diff(*b) < 40);
+                assert!(close, "方向 {} 象限 {}: {:?} != {:?}", orientation, index, actual, color);
+            }
+            
+            let preview: DynamicImage = load_preview(&path, 16, 16).unwrap();
+            assert_eq!((preview.width(), preview.height()), (16, 8));
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+
+    #[test]
+    fn test_transparency() {
+        let opaque: DynamicImage = DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 4, image::Rgba([10, 20, 30, 255])));
+        assert!(!has_transparency(&opaque));
+        assert!(!has_transparency(&DynamicImage::new_rgb8(4, 4)));
+        
+        let mut transparent: RgbaImage = RgbaImage::from_pixel(4, 4, image::Rgba([255, 0, 0, 255]));
+        transparent.put_pixel(0, 0, image::Rgba([0, 0, 0, 0]));
+        transparent.put_pixel(2, 0, image::Rgba([0, 0, 0, 0]));
+        let transparent: DynamicImage = DynamicImage::ImageRgba8(transparent);
+        assert!(has_transparency(&transparent));
+        
+        // 透明像素显示为棋盘格，不透明像素保持不变
+        let composited: RgbaImage = composite_on_checkerboard(&transparent, 2);
+        assert_eq!(composited.get_pixel(0, 0).0, [255, 255, 255, 255]);
+        assert_eq!(composited.get_pixel(2, 0).0, [204, 204, 204, 255]);
+        assert_eq!(composited.get_pixel(1, 1).0, [255, 0, 0, 255]);
+    }
 } 
\ No newline at end of file
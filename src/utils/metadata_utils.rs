@@ -0,0 +1,273 @@
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use exif::{Exif, In, Tag, Value};
+use crate::Result;
+use crate::models::image_metadata::{GpsCoordinates, ImageMetadata};
+
+/// 查找 XMP 数据包时最多读取的字节数，XMP 一般位于文件开头附近
+const XMP_SCAN_LIMIT: u64 = 1 << 20;
+
+/// 读取图片的 EXIF 和 XMP 元数据
+///
+/// 图片不含元数据或元数据损坏时返回空的元数据，只有文件无法读取时返回错误
+pub fn read_image_metadata(path: &Path) -> Result<ImageMetadata> {
+    let mut metadata: ImageMetadata = ImageMetadata::default();
+    let mut reader: BufReader<File> = BufReader::new(File::open(path)?);
+    
+    match exif::Reader::new().read_from_container(&mut reader) {
+        Ok(exif) => apply_exif(&exif, &mut metadata),
+        Err(exif::Error::NotFound(_)) => {}
+        Err(e) => log::debug!("读取 EXIF 失败 {:?}: {}", path, e),
+    }
+    
+    reader.seek(SeekFrom::Start(0))?;
+    let mut buffer: Vec<u8> = Vec::new();
+    reader.take(XMP_SCAN_LIMIT).read_to_end(&mut buffer)?;
+    if let Some(packet) = find_xmp_packet(&buffer) {
+        apply_xmp(&packet, &mut metadata);
+    }
+    
+    Ok(metadata)
+}
+
+/// 从 EXIF 中提取元数据
+pub fn apply_exif(exif: &Exif, metadata: &mut ImageMetadata) {
+    let text = |tag: Tag| -> Option<String> {
+        match &exif.get_field(tag, In::PRIMARY)?.value {
+            Value::Ascii(values) => values
+                .iter()
+                .map(|value| clean_text(&String::from_utf8_lossy(value)))
+                .find(|value| !value.is_empty()),
+            _ => None,
+        }
+    };
+    
+    metadata.camera_make = text(Tag::Make);
+    metadata.camera_model = text(Tag::Model);
+    metadata.lens = text(Tag::LensModel);
+    metadata.artist = text(Tag::Artist);
+    metadata.copyright = text(Tag::Copyright);
+    metadata.orientation = exif
+        .get_field(Tag::Orientation, In::PRIMARY)
+        .and_then(|field| field.value.get_uint(0))
+        .filter(|orientation| (1..=8).contains(orientation))
+        .map(|orientation| orientation as u16);
+    
+    metadata.captured_at = [Tag::DateTimeOriginal, Tag::DateTimeDigitized, Tag::DateTime]
+        .into_iter()
+        .find_map(|tag| match &exif.get_field(tag, In::PRIMARY)?.value {
+            Value::Ascii(values) => {
+                let datetime: exif::DateTime = exif::DateTime::from_ascii(values.first()?).ok()?;
+                chrono::NaiveDate::from_ymd_opt(datetime.year as i32, datetime.month as u32, datetime.day as u32)?
+                    .and_hms_opt(datetime.hour as u32, datetime.minute as u32, datetime.second as u32)
+            }
+            _ => None,
+        });
+    
+    let coordinate = |value_tag: Tag, reference_tag: Tag, negative: &str| -> Option<f64> {
+        let degrees: f64 = match &exif.get_field(value_tag, In::PRIMARY)?.value {
+            Value::Rational(parts) if parts.len() == 3 => {
+                parts[0].to_f64() + parts[1].to_f64() / 60.0 + parts[2].to_f64() / 3600.0
+            }
+            _ => return None,
+        };
+        let reference: String = text(reference_tag).unwrap_or_default();
+        Some(if reference.eq_ignore_ascii_case(negative) { -degrees } else { degrees })
+    };
+    
+    let latitude: Option<f64> = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S");
+    let longitude: Option<f64> = coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W");
+    if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+        let altitude: Option<f64> = match &exif.get_field(Tag::GPSAltitude, In::PRIMARY).map(|field| &field.value) {
+            Some(Value::Rational(parts)) if !parts.is_empty() => {
+                let below_sea_level: bool = exif
+                    .get_field(Tag::GPSAltitudeRef, In::PRIMARY)
+                    .and_then(|field| field.value.get_uint(0))
+                    == Some(1);
+                let altitude: f64 = parts[0].to_f64();
+                Some(if below_sea_level { -altitude } else { altitude })
+            }
+            _ => None,
+        };
+        
+        metadata.gps = Some(GpsCoordinates {
+            latitude,
+            longitude,
+            altitude,
+        });
+    }
+}
+
+/// 在文件内容中查找 XMP 数据包
+pub fn find_xmp_packet(bytes: &[u8]) -> Option<String> {
+    const START: &[u8] = b"<x:xmpmeta";
+    const END: &[u8] = b"</x:xmpmeta>";
+    
+    let start: usize = bytes.windows(START.len()).position(|window| window == START)?;
+    let length: usize = bytes[start..].windows(END.len()).position(|window| window == END)?;
+    
+    Some(String::from_utf8_lossy(&bytes[start..start + length + END.len()]).into_owned())
+}
+
+/// 从 XMP 数据包中提取关键词、作者和版权，EXIF 中已有的作者和版权优先
+pub fn apply_xmp(packet: &str, metadata: &mut ImageMetadata) {
+    let mut keywords: Vec<String> = list_items(packet, "dc:subject");
+    // Lightroom 的层级关键词使用 `|` 分隔，例如 `Places|Japan|Kyoto`
+    keywords.extend(
+        list_items(packet, "lr:hierarchicalSubject")
+            .into_iter()
+            .map(|keyword| keyword.replace('|', "/")),
+    );
+    
+    for keyword in keywords {
+        if !metadata.keywords.contains(&keyword) {
+            metadata.keywords.push(keyword);
+        }
+    }
+    
+    if metadata.artist.is_none() {
+        metadata.artist = list_items(packet, "dc:creator").into_iter().next();
+    }
+    if metadata.copyright.is_none() {
+        metadata.copyright = list_items(packet, "dc:rights").into_iter().next();
+    }
+}
+
+/// 返回 XMP 元素中 `rdf:li` 列表项的文本
+fn list_items(packet: &str, element: &str) -> Vec<String> {
+    let open: String = format!("<{}>", element);
+    let close: String = format!("</{}>", element);
+    
+    let Some(start) = packet.find(&open) else {
+        return Vec::new();
+    };
+    let body: &str = &packet[start + open.len()..];
+    let body: &str = &body[..body.find(&close).unwrap_or(body.len())];
+    
+    let mut items: Vec<String> = Vec::new();
+    let mut rest: &str = body;
+    while let Some(position) = rest.find("<rdf:li") {
+        rest = &rest[position..];
+        let Some(content_start) = rest.find('>') else {
+            break;
+        };
+        // 自闭合的空列表项
+        if rest[..content_start].ends_with('/') {
+            rest = &rest[content_start + 1..];
+            continue;
+        }
+        let content: &str = &rest[content_start + 1..];
+        let Some(content_end) = content.find("</rdf:li>") else {
+            break;
+        };
+        
+        let item: String = clean_text(&unescape_xml(&content[..content_end]));
+        if !item.is_empty() {
+            items.push(item);
+        }
+        rest = &content[content_end..];
+    }
+    
+    items
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#169;", "©")
+        .replace("&amp;", "&")
+}
+
+fn clean_text(text: &str) -> String {
+    text.trim_matches(|ch: char| ch == '\0' || ch.is_whitespace()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::{Field, Rational};
+    use crate::utils::test_fixtures::{exif_segment, write_jpeg_with_app1, xmp_segment};
+
+    /// 构造带有 EXIF APP1 段和 XMP APP1 段的 JPEG 文件
+    fn create_jpeg_with_metadata(path: &Path, fields: &[Field], xmp: Option<&str>) {
+        let mut segments: Vec<Vec<u8>> = vec![exif_segment(fields, None)];
+        segments.extend(xmp.map(xmp_segment));
+        write_jpeg_with_app1(path, &image::RgbImage::from_pixel(8, 8, image::Rgb([10, 20, 30])), &segments);
+    }
+    
+    fn ascii(tag: Tag, value: &str) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+        }
+    }
+    
+    fn rational(tag: Tag, values: &[(u32, u32)]) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(values.iter().map(|&(num, denom)| Rational { num, denom }).collect()),
+        }
+    }
+    
+    const XMP_PACKET: &str = r#"<x:xmpmeta xmlns:x="adobe:ns:meta/"><rdf:RDF><rdf:Description>
+        <dc:subject><rdf:Bag><rdf:li>Mountains</rdf:li><rdf:li>Sunset &amp; Dusk</rdf:li></rdf:Bag></dc:subject>
+        <lr:hierarchicalSubject><rdf:Bag><rdf:li>Places|Japan</rdf:li></rdf:Bag></lr:hierarchicalSubject>
+        <dc:creator><rdf:Seq><rdf:li>XMP Author</rdf:li></rdf:Seq></dc:creator>
+        <dc:rights><rdf:Alt><rdf:li xml:lang="x-default">CC BY 4.0</rdf:li></rdf:Alt></dc:rights>
+        </rdf:Description></rdf:RDF></x:xmpmeta>"#;
+    
+    #[test]
+    fn test_read_exif_and_xmp() {
+        let path: std::path::PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-metadata-{}.jpg", std::process::id()));
+        let fields: Vec<Field> = vec![
+            ascii(Tag::Make, "Canon"),
+            ascii(Tag::Model, "Canon EOS R5"),
+            ascii(Tag::Artist, "Jane Doe"),
+            ascii(Tag::DateTimeOriginal, "2023:08:14 19:42:05"),
+            ascii(Tag::LensModel, "RF24-70mm F2.8 L IS USM"),
+            ascii(Tag::GPSLatitudeRef, "S"),
+            rational(Tag::GPSLatitude, &[(33, 1), (51, 1), (3600, 100)]),
+            ascii(Tag::GPSLongitudeRef, "E"),
+            rational(Tag::GPSLongitude, &[(151, 1), (12, 1), (0, 1)]),
+            Field {
+                tag: Tag::Orientation,
+                ifd_num: In::PRIMARY,
+                value: Value::Short(vec![6]),
+            },
+        ];
+        create_jpeg_with_metadata(&path, &fields, Some(XMP_PACKET));
+        
+        let metadata: ImageMetadata = read_image_metadata(&path).unwrap();
+        assert_eq!(metadata.get_camera().as_deref(), Some("Canon EOS R5"));
+        assert_eq!(metadata.lens.as_deref(), Some("RF24-70mm F2.8 L IS USM"));
+        assert_eq!(metadata.artist.as_deref(), Some("Jane Doe"));
+        assert_eq!(metadata.copyright.as_deref(), Some("CC BY 4.0"));
+        assert_eq!(metadata.orientation, Some(6));
+        assert_eq!(metadata.captured_at.unwrap().to_string(), "2023-08-14 19:42:05");
+        assert_eq!(metadata.keywords, vec!["Mountains", "Sunset & Dusk", "Places/Japan"]);
+        
+        let gps: GpsCoordinates = metadata.gps.unwrap();
+        assert!((gps.latitude + 33.86).abs() < 1e-9);
+        assert!((gps.longitude - 151.2).abs() < 1e-9);
+        
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_image_without_metadata() {
+        let path: std::path::PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-no-metadata-{}.png", std::process::id()));
+        image::RgbImage::from_pixel(4, 4, image::Rgb([0, 0, 0])).save(&path).unwrap();
+        
+        assert!(read_image_metadata(&path).unwrap().is_empty());
+        assert!(read_image_metadata(&path.with_extension("missing")).is_err());
+        
+        let _ = std::fs::remove_file(&path);
+    }
+}
\ No newline at end of file
@@ -1,5 +1,27 @@
+pub mod animation_utils;
+pub mod blurhash;
+pub mod color_utils;
+pub mod extra_formats;
 pub mod file_utils;
+pub mod id_utils;
 pub mod image_utils;
+pub mod metadata_utils;
+pub mod png_utils;
+pub mod quality_utils;
+pub mod raw_utils;
+#[cfg(test)]
+pub(crate) mod test_fixtures;
+pub mod thumbnail_decode;
 
+pub use animation_utils::*;
+pub use blurhash::*;
+pub use color_utils::*;
+pub use extra_formats::*;
 pub use file_utils::*;
-pub use image_utils::*; 
\ No newline at end of file
+pub use id_utils::*;
+pub use image_utils::*;
+pub use metadata_utils::*;
+pub use png_utils::*;
+pub use quality_utils::*;
+pub use raw_utils::*;
+pub use thumbnail_decode::*; 
\ No newline at end of file
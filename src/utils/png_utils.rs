@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use image::DynamicImage;
+use crate::{Result, WallpaperError};
+
+/// 读取 PNG 文件中位于图像数据之前的文本块（`tEXt`、`zTXt` 和 `iTXt`）
+pub fn read_png_text(path: &Path) -> Result<HashMap<String, String>> {
+    let decoder: png::Decoder<BufReader<File>> = png::Decoder::new(BufReader::new(File::open(path)?));
+    let reader = decoder.read_info().map_err(png_error)?;
+    let info: &png::Info = reader.info();
+    
+    let mut text: HashMap<String, String> = HashMap::new();
+    for chunk in &info.uncompressed_latin1_text {
+        text.insert(chunk.keyword.clone(), chunk.text.clone());
+    }
+    for chunk in &info.compressed_latin1_text {
+        if let Ok(value) = chunk.get_text() {
+            text.insert(chunk.keyword.clone(), value);
+        }
+    }
+    for chunk in &info.utf8_text {
+        if let Ok(value) = chunk.get_text() {
+            text.insert(chunk.keyword.clone(), value);
+        }
+    }
+    Ok(text)
+}
+
+/// 将图像编码为 PNG，并在图像数据之前写入 `tEXt` 文本块
+///
+/// 文本块只能包含 Latin-1 字符，超出范围的字符会导致错误
+pub fn encode_png_with_text(image: &DynamicImage, text: &[(&str, String)]) -> Result<Vec<u8>> {
+    let (color, pixels): (png::ColorType, Vec<u8>) = if image.color().has_alpha() {
+        (png::ColorType::Rgba, image.to_rgba8().into_raw())
+    } else {
+        (png::ColorType::Rgb, image.to_rgb8().into_raw())
+    };
+    
+    let mut encoded: Vec<u8> = Vec::new();
+    let mut encoder: png::Encoder<&mut Vec<u8>> = png::Encoder::new(&mut encoded, image.width(), image.height());
+    encoder.set_color(color);
+    encoder.set_depth(png::BitDepth::Eight);
+    for (keyword, value) in text {
+        encoder.add_text_chunk(keyword.to_string(), value.clone()).map_err(png_error)?;
+    }
+    
+    let mut writer = encoder.write_header().map_err(png_error)?;
+    writer.write_image_data(&pixels).map_err(png_error)?;
+    writer.finish().map_err(png_error)?;
+    Ok(encoded)
+}
+
+fn png_error(error: impl std::fmt::Display) -> WallpaperError {
+    WallpaperError::Service(format!("PNG 编解码失败: {}", error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png_text_round_trip() {
+        let directory: std::path::PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-png-text-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path: std::path::PathBuf = directory.join("thumb.png");
+        
+        let image: DynamicImage = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(4, 3, image::Rgb([10, 20, 30])));
+        let encoded: Vec<u8> = encode_png_with_text(&image, &[
+            ("Thumb::URI", "file:///tmp/a.png".to_string()),
+            ("Thumb::MTime", "1700000000".to_string()),
+        ]).unwrap();
+        std::fs::write(&path, encoded).unwrap();
+        
+        // 写入的文本块不影响图像解码
+        assert_eq!(image::open(&path).unwrap().to_rgb8(), image.to_rgb8());
+        let text: HashMap<String, String> = read_png_text(&path).unwrap();
+        assert_eq!(text.get("Thumb::URI").map(String::as_str), Some("file:///tmp/a.png"));
+        assert_eq!(text.get("Thumb::MTime").map(String::as_str), Some("1700000000"));
+        
+        assert!(encode_png_with_text(&image, &[("Thumb::URI", "壁纸".to_string())]).is_err());
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
\ No newline at end of file
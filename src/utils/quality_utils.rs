@@ -0,0 +1,389 @@
+use std::f32::consts::PI;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use image::{DynamicImage, GrayImage};
+use crate::Result;
+use crate::models::ImageQuality;
+use crate::utils::open_oriented;
+
+/// 计算频谱时每条采样线的长度，图片任一边小于该值时不检测放大
+const SPECTRUM_LENGTH: usize = 256;
+
+/// 横向和纵向各取的采样线数量
+const SPECTRUM_LINES: u32 = 32;
+
+/// 截止频率两侧的平均功率相差超过该倍数时视为放大图片，自然图像的频谱平滑衰减，通常在 2 倍以内
+const UPSCALE_DROP_RATIO: f32 = 8.0;
+
+/// 拉普拉斯方差达到该值时清晰度得分为 0.5
+const SHARPNESS_REFERENCE: f32 = 100.0;
+
+/// 有效像素达到该值（4K）时分辨率得分为满分
+const REFERENCE_PIXELS: f32 = 3840.0 * 2160.0;
+
+/// JPEG 标准（ITU-T T.81 附录 K）亮度量化表，按自然顺序排列
+const STANDARD_LUMINANCE_TABLE: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61,
+    12, 12, 14, 19, 26, 58, 60, 55,
+    14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62,
+    18, 22, 37, 56, 68, 109, 103, 77,
+    24, 35, 55, 64, 81, 104, 113, 92,
+    49, 64, 78, 87, 103, 121, 120, 101,
+    72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+/// 之字形扫描顺序中第 i 个系数在自然顺序中的位置，DQT 段按之字形顺序存储
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5,
+    12, 19, 26, 33, 40, 48, 41, 34, 27, 20, 13, 6, 7, 14, 21, 28,
+    35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51,
+    58, 59, 52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/// 分析图片质量：清晰度、JPEG 压缩质量、块效应和放大检测，需要完整解码原图
+pub fn analyze_image_quality(path: &Path) -> Result<ImageQuality> {
+    let image: DynamicImage = open_oriented(path)?;
+    let luma: GrayImage = image.to_luma8();
+    
+    let jpeg_quality: Option<u8> = match estimate_jpeg_quality(path) {
+        Ok(quality) => quality,
+        Err(e) => {
+            log::debug!("读取 JPEG 量化表失败 {:?}: {}", path, e);
+            None
+        }
+    };
+    let sharpness: f32 = laplacian_variance(&luma);
+    let blockiness: f32 = measure_blockiness(&luma);
+    let scale: f32 = estimate_upscale_factor(&luma);
+    let effective_size: (u32, u32) = (
+        ((luma.width() as f32 * scale).round() as u32).max(1),
+        ((luma.height() as f32 * scale).round() as u32).max(1),
+    );
+    
+    Ok(ImageQuality {
+        sharpness,
+        jpeg_quality,
+        blockiness,
+        effective_size,
+        score: quality_score(effective_size, sharpness, jpeg_quality, blockiness),
+    })
+}
+
+/// 综合质量分（0-100）：有效分辨率占 40%，清晰度和压缩质量各占 30%
+pub fn quality_score(effective_size: (u32, u32), sharpness: f32, jpeg_quality: Option<u8>, blockiness: f32) -> u8 {
+    let pixels: f32 = effective_size.0 as f32 * effective_size.1 as f32;
+    let resolution: f32 = (pixels / REFERENCE_PIXELS).sqrt().min(1.0);
+    let clarity: f32 = sharpness / (sharpness + SHARPNESS_REFERENCE);
+    // 质量 95 以上的 JPEG 与无损格式看不出差别
+    let compression: f32 = jpeg_quality.map_or(1.0, |quality| (quality as f32 / 95.0).min(1.0))
+        * (1.0 - blockiness.clamp(0.0, 1.0));
+    
+    (100.0 * (0.4 * resolution + 0.3 * clarity + 0.3 * compression)).round().clamp(0.0, 100.0) as u8
+}
+
+/// 灰度图拉普拉斯响应（4 邻域）的方差，模糊或放大的图片边缘平缓，方差较小
+pub fn laplacian_variance(luma: &GrayImage) -> f32 {
+    let (width, height) = luma.dimensions();
+    if width < 3 || height < 3 {
+        return 0.0;
+    }
+    
+    let pixel = |x: u32, y: u32| -> f64 { luma.get_pixel(x, y)[0] as f64 };
+    let (mut sum, mut sum_squares) = (0.0f64, 0.0f64);
+    for y in 1..height - 1 {
+        for x in 1..width - 1 {
+            let response: f64 = pixel(x - 1, y) + pixel(x + 1, y) + pixel(x, y - 1) + pixel(x, y + 1) - 4.0 * pixel(x, y);
+            sum += response;
+            sum_squares += response * response;
+        }
+    }
+    let count: f64 = ((width - 2) * (height - 2)) as f64;
+    let mean: f64 = sum / count;
+    (sum_squares / count - mean * mean) as f32
+}
+
+/// 块效应强度（0-1）：按坐标模 8 统计相邻像素差，最大一组与其余各组平均值之差相对两者之和的比例
+///
+/// 取最大一组而不是固定的块边界，图片旋转或裁剪后块网格不再与坐标 0 对齐
+pub fn measure_blockiness(luma: &GrayImage) -> f32 {
+    let (width, height) = luma.dimensions();
+    if width < 16 || height < 16 {
+        return 0.0;
+    }
+    
+    let mut columns: [f64; 8] = [0.0; 8];
+    let mut rows: [f64; 8] = [0.0; 8];
+    for y in 0..height {
+        for x in 0..width {
+            let value: i32 = luma.get_pixel(x, y)[0] as i32;
+            if x > 0 {
+                columns[(x % 8) as usize] += (value - luma.get_pixel(x - 1, y)[0] as i32).unsigned_abs() as f64;
+            }
+            if y > 0 {
+                rows[(y % 8) as usize] += (value - luma.get_pixel(x, y - 1)[0] as i32).unsigned_abs() as f64;
+            }
+        }
+    }
+    
+    let excess = |bins: &[f64; 8]| -> f64 {
+        let (index, maximum) = bins
+            .iter()
+            .copied()
+            .enumerate()
+            .fold((0, f64::MIN), |best, (index, value)| if value > best.1 { (index, value) } else { best });
+        let others: f64 = bins.iter().enumerate().filter(|(i, _)| *i != index).map(|(_, value)| value).sum::<f64>() / 7.0;
+        if maximum <= f64::EPSILON {
+            0.0
+        } else {
+            (maximum - others) / (maximum + others)
+        }
+    };
+    ((excess(&columns) + excess(&rows)) / 2.0) as f32
+}
+
+/// 估计图片相对原始分辨率的放大倍数的倒数，未检测到放大时返回 1.0
+///
+/// 放大（插值）不会产生新的高频成分，频谱在原始分辨率对应的截止频率处陡降；
+/// 自然图像的频谱则平滑衰减。在横纵采样线的平均功率谱中寻找两侧功率比最大的位置。
+/// Lanczos 等锐利的插值估计较准，双线性等平缓的插值估计值偏高，放大倍数很小时可能检测不到
+pub fn estimate_upscale_factor(luma: &GrayImage) -> f32 {
+    let Some(spectrum) = average_power_spectrum(luma) else {
+        return 1.0;
+    };
+    let nyquist: usize = SPECTRUM_LENGTH / 2;
+    let mean = |from: usize, to: usize| -> f32 {
+        let band: &[f32] = &spectrum[from.max(1)..=to.min(nyquist)];
+        band.iter().sum::<f32>() / band.len() as f32
+    };
+    
+    // 截止频率以奈奎斯特频率的比例表示，从 0.2 到 0.9
+    let mut best: (f32, f32) = (1.0, 0.0);
+    for step in 8..=36 {
+        let cutoff: f32 = step as f32 * 0.025;
+        let bin: usize = (cutoff * nyquist as f32).round() as usize;
+        let below: f32 = mean((bin as f32 * 0.8) as usize, bin - 1);
+        let above: f32 = mean(bin + 1, (bin as f32 * 1.25).ceil() as usize);
+        let ratio: f32 = below / above.max(f32::MIN_POSITIVE);
+        if ratio > best.1 {
+            best = (cutoff, ratio);
+        }
+    }
+    
+    if best.1 < UPSCALE_DROP_RATIO {
+        return 1.0;
+    }
+    
+    // 插值滤波器的过渡带较宽，功率比最大处位于过渡带末端；以过渡带之前的功率为参考，
+    // 取功率降到其 1/4（幅度减半）处作为截止频率
+    let end: usize = (best.0 * nyquist as f32).round() as usize;
+    let reference: f32 = mean(end / 2, end * 7 / 10);
+    (end * 7 / 10..end)
+        .find(|bin| mean(bin - 1, bin + 1) < reference / 4.0)
+        .map_or(best.0, |bin| bin as f32 / nyquist as f32)
+}
+
+/// 横向和纵向均匀分布的采样线（加 Hann 窗、去均值）的平均功率谱，下标为频率 0 到奈奎斯特频率
+fn average_power_spectrum(luma: &GrayImage) -> Option<Vec<f32>> {
+    let (width, height) = luma.dimensions();
+    let length: u32 = SPECTRUM_LENGTH as u32;
+    if width < length || height < length {
+        return None;
+    }
+    
+    let window: Vec<f32> = (0..SPECTRUM_LENGTH)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / SPECTRUM_LENGTH as f32).cos())
+        .collect();
+    let (cosines, sines): (Vec<f32>, Vec<f32>) = (0..SPECTRUM_LENGTH)
+        .map(|i| {
+            let angle: f32 = 2.0 * PI * i as f32 / SPECTRUM_LENGTH as f32;
+            (angle.cos(), angle.sin())
+        })
+        .unzip();
+    
+    let mut spectrum: Vec<f32> = vec![0.0; SPECTRUM_LENGTH / 2 + 1];
+    let mut accumulate = |samples: Vec<f32>| {
+        let mean: f32 = samples.iter().sum::<f32>() / samples.len() as f32;
+        let windowed: Vec<f32> = samples.iter().zip(&window).map(|(value, weight)| (value - mean) * weight).collect();
+        for (frequency, power) in spectrum.iter_mut().enumerate() {
+            let (mut real, mut imaginary) = (0.0f32, 0.0f32);
+            for (i, value) in windowed.iter().enumerate() {
+                let index: usize = (frequency * i) % SPECTRUM_LENGTH;
+                real += value * cosines[index];
+                imaginary -= value * sines[index];
+            }
+            *power += real * real + imaginary * imaginary;
+        }
+    };
+    
+    let (left, top) = ((width - length) / 2, (height - length) / 2);
+    for line in 0..SPECTRUM_LINES {
+        let y: u32 = (2 * line + 1) * height / (2 * SPECTRUM_LINES);
+        accumulate((left..left + length).map(|x| luma.get_pixel(x, y)[0] as f32).collect());
+        let x: u32 = (2 * line + 1) * width / (2 * SPECTRUM_LINES);
+        accumulate((top..top + length).map(|y| luma.get_pixel(x, y)[0] as f32).collect());
+    }
+    Some(spectrum)
+}
+
+/// 由亮度量化表估计 JPEG 压缩质量（按 IJG 的质量缩放公式），不是 JPEG 或没有量化表时返回 `None`
+///
+/// 只读取到扫描数据开始之前的文件头
+pub fn estimate_jpeg_quality(path: &Path) -> Result<Option<u8>> {
+    let mut reader: BufReader<File> = BufReader::new(File::open(path)?);
+    let mut marker: [u8; 2] = [0; 2];
+    reader.read_exact(&mut marker)?;
+    if marker != [0xFF, 0xD8] {
+        return Ok(None);
+    }
+    
+    loop {
+        // 标记前可以有任意个 0xFF 填充字节
+        let mut byte: [u8; 1] = [0];
+        reader.read_exact(&mut byte)?;
+        if byte[0] != 0xFF {
+            return Ok(None);
+        }
+        while byte[0] == 0xFF {
+            reader.read_exact(&mut byte)?;
+        }
+        match byte[0] {
+            // 扫描数据开始或图像结束之前仍未找到亮度量化表
+            0xDA | 0xD9 => return Ok(None),
+            // 没有长度字段的独立标记
+            0x01 | 0xD0..=0xD7 => continue,
+            _ => {}
+        }
+        
+        let mut length: [u8; 2] = [0; 2];
+        reader.read_exact(&mut length)?;
+        let length: usize = (u16::from_be_bytes(length) as usize).saturating_sub(2);
+        if byte[0] != 0xDB {
+            reader.seek(SeekFrom::Current(length as i64))?;
+            continue;
+        }
+        
+        let mut segment: Vec<u8> = vec![0; length];
+        reader.read_exact(&mut segment)?;
+        if let Some(table) = find_luminance_table(&segment) {
+            return Ok(Some(quality_from_table(&table)));
+        }
+    }
+}
+
+/// 在 DQT 段中查找 0 号（亮度）量化表，按之字形顺序返回
+fn find_luminance_table(segment: &[u8]) -> Option<[u16; 64]> {
+    let mut offset: usize = 0;
+    while offset < segment.len() {
+        let precision: u8 = segment[offset] >> 4;
+        let id: u8 = segment[offset] & 0x0F;
+        let entry_size: usize = if precision == 0 { 1 } else { 2 };
+        let data: &[u8] = segment.get(offset + 1..offset + 1 + 64 * entry_size)?;
+        if id == 0 {
+            let mut table: [u16; 64] = [0; 64];
+            for (i, value) in table.iter_mut().enumerate() {
+                *value = if precision == 0 {
+                    data[i] as u16
+                } else {
+                    u16::from_be_bytes([data[2 * i], data[2 * i + 1]])
+                };
+            }
+            return Some(table);
+        }
+        offset += 1 + 64 * entry_size;
+    }
+    None
+}
+
+/// 与按各质量缩放后的标准量化表逐项比较，返回差异最小的质量
+fn quality_from_table(table: &[u16; 64]) -> u8 {
+    (1..=100u32)
+        .min_by_key(|quality| {
+            let scale: u32 = if *quality < 50 { 5000 / quality } else { 200 - 2 * quality };
+            table
+                .iter()
+                .zip(ZIGZAG)
+                .map(|(value, natural)| {
+                    let expected: u32 = ((STANDARD_LUMINANCE_TABLE[natural] as u32 * scale + 50) / 100).clamp(1, 255);
+                    expected.abs_diff(*value as u32)
+                })
+                .sum::<u32>()
+        })
+        .unwrap_or(100) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use image::{Luma, RgbImage};
+    use image::codecs::jpeg::JpegEncoder;
+    use image::imageops::FilterType;
+    use crate::models::image_quality::UPSCALE_THRESHOLD;
+    use crate::utils::splitmix64;
+
+    /// 可复现的伪随机噪声图像，频谱在各频率上大致均匀
+    fn noise_image(width: u32, height: u32, seed: u64) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, y| {
+            let hash: u64 = splitmix64(seed ^ ((y as u64) << 32 | x as u64));
+            Luma([(hash >> 56) as u8])
+        })
+    }
+
+    #[test]
+    fn test_estimate_jpeg_quality() {
+        let directory: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-quality-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        
+        let image: RgbImage = RgbImage::from_fn(64, 64, |x, y| image::Rgb([(x * 4) as u8, (y * 4) as u8, 128]));
+        for quality in [30u8, 75, 92] {
+            let path: PathBuf = directory.join(format!("q{}.jpg", quality));
+            let file: File = File::create(&path).unwrap();
+            image.write_with_encoder(JpegEncoder::new_with_quality(file, quality)).unwrap();
+            let estimated: u8 = estimate_jpeg_quality(&path).unwrap().unwrap();
+            assert!(estimated.abs_diff(quality) <= 2, "{} -> {}", quality, estimated);
+        }
+        
+        let png: PathBuf = directory.join("a.png");
+        image.save(&png).unwrap();
+        assert_eq!(estimate_jpeg_quality(&png).unwrap(), None);
+        
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn test_sharpness_and_blockiness() {
+        let sharp: GrayImage = noise_image(128, 128, 1);
+        let blurred: GrayImage = image::imageops::blur(&sharp, 2.0);
+        assert!(laplacian_variance(&sharp) > laplacian_variance(&blurred) * 10.0);
+        
+        // 每个 8×8 块内为纯色，块边界处的梯度远大于块内
+        let blocky: GrayImage = GrayImage::from_fn(128, 128, |x, y| *sharp.get_pixel(x / 8 * 8, y / 8 * 8));
+        assert!(measure_blockiness(&blocky) > 0.9);
+        assert!(measure_blockiness(&sharp) < 0.1);
+    }
+
+    #[test]
+    fn test_detect_upscale() {
+        // 原生分辨率的噪声图像没有截止频率
+        let native: GrayImage = noise_image(384, 384, 2);
+        assert_eq!(estimate_upscale_factor(&native), 1.0);
+        
+        // 由 128 像素放大 3 倍，截止频率约为奈奎斯特频率的 1/3
+        let upscaled: GrayImage = image::imageops::resize(&noise_image(128, 128, 3), 384, 384, FilterType::Lanczos3);
+        let factor: f32 = estimate_upscale_factor(&upscaled);
+        assert!((factor - 1.0 / 3.0).abs() < 0.05, "{}", factor);
+        let bilinear: GrayImage = image::imageops::resize(&noise_image(192, 192, 3), 384, 384, FilterType::Triangle);
+        assert!(estimate_upscale_factor(&bilinear) < UPSCALE_THRESHOLD);
+        
+        // 小于采样线长度的图片不检测
+        assert_eq!(estimate_upscale_factor(&noise_image(100, 100, 4)), 1.0);
+        
+        let score_native: u8 = quality_score((3840, 2160), 500.0, None, 0.0);
+        let score_upscaled: u8 = quality_score((1280, 720), 20.0, Some(70), 0.5);
+        assert!(score_native > 90 && score_upscaled < 50, "{} {}", score_native, score_upscaled);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,468 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use image::{DynamicImage, ImageFormat, RgbImage};
+use image::metadata::Orientation;
+use jpeg_decoder::CodingProcess;
+use crate::Result;
+use crate::utils::orientation_swaps_dimensions;
+
+/// 支持的相机 RAW 扩展名，均为基于 TIFF 结构的格式
+pub const RAW_EXTENSIONS: &[&str] = &["cr2", "nef", "arw", "dng"];
+
+/// 遍历 IFD 的数量上限，防止损坏文件中的循环链表
+const MAX_IFDS: usize = 32;
+
+const TAG_NEW_SUBFILE_TYPE: u16 = 0x00FE;
+const TAG_IMAGE_WIDTH: u16 = 0x0100;
+const TAG_IMAGE_LENGTH: u16 = 0x0101;
+const TAG_COMPRESSION: u16 = 0x0103;
+const TAG_PHOTOMETRIC: u16 = 0x0106;
+const TAG_STRIP_OFFSETS: u16 = 0x0111;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_STRIP_BYTE_COUNTS: u16 = 0x0117;
+const TAG_SUB_IFDS: u16 = 0x014A;
+const TAG_JPEG_OFFSET: u16 = 0x0201;
+const TAG_JPEG_LENGTH: u16 = 0x0202;
+
+/// 传感器原始数据的 PhotometricInterpretation：CFA 和 LinearRaw
+const PHOTOMETRIC_RAW: &[u32] = &[32803, 34892];
+
+/// RAW 文件的结构信息：内嵌 JPEG 预览、原始数据尺寸和方向
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawLayout {
+    /// 内嵌 JPEG 预览的 (偏移, 长度, 宽, 高)，按面积从小到大排列
+    previews: Vec<(usize, usize, u32, u32)>,
+    /// 原始传感器数据的尺寸
+    sensor_size: Option<(u32, u32)>,
+    orientation: Orientation,
+}
+
+impl RawLayout {
+    /// 解析 TIFF 结构（包括 SubIFD），只读取 IFD 和预览的 JPEG 头部，不解码任何图像数据
+    pub fn parse<R: Read + Seek>(reader: R) -> Option<Self> {
+        let mut tiff: Tiff<R> = Tiff::new(reader)?;
+        let mut layout: RawLayout = RawLayout {
+            previews: Vec::new(),
+            sensor_size: None,
+            orientation: Orientation::NoTransforms,
+        };
+        
+        let ifd0: usize = tiff.read_u32(4)? as usize;
+        let mut pending: Vec<usize> = vec![ifd0];
+        let mut visited: HashSet<usize> = HashSet::new();
+        while let Some(offset) = pending.pop() {
+            if offset == 0 || visited.len() >= MAX_IFDS || !visited.insert(offset) {
+                continue;
+            }
+            let Some((entries, next)) = tiff.read_ifd(offset) else {
+                continue;
+            };
+            pending.push(next);
+            pending.extend(entries.get(&TAG_SUB_IFDS).into_iter().flatten().map(|offset| *offset as usize));
+            
+            // 只有 IFD0 的方向适用于整张图像
+            if offset == ifd0 {
+                if let Some(orientation) = first(&entries, TAG_ORIENTATION).and_then(|value| Orientation::from_exif(value as u8)) {
+                    layout.orientation = orientation;
+                }
+            }
+            layout.collect_images(&mut tiff, &entries);
+        }
+        
+        layout.previews.sort_by_key(|(_, _, width, height)| *width as u64 * *height as u64);
+        layout.previews.dedup_by_key(|(offset, _, _, _)| *offset);
+        Some(layout)
+    }
+    
+    fn collect_images<R: Read + Seek>(&mut self, tiff: &mut Tiff<R>, entries: &HashMap<u16, Vec<u32>>) {
+        let photometric: Option<u32> = first(entries, TAG_PHOTOMETRIC);
+        if photometric.is_some_and(|value| PHOTOMETRIC_RAW.contains(&value)) {
+            // 主图像（NewSubfileType 为 0）才是完整的传感器数据
+            if first(entries, TAG_NEW_SUBFILE_TYPE).unwrap_or(0) == 0 {
+                if let (Some(width), Some(height)) = (first(entries, TAG_IMAGE_WIDTH), first(entries, TAG_IMAGE_LENGTH)) {
+                    self.sensor_size = Some((width, height));
+                }
+            }
+            return;
+        }
+        
+        let mut candidates: Vec<(usize, usize)> = Vec::new();
+        if let (Some(offset), Some(length)) = (first(entries, TAG_JPEG_OFFSET), first(entries, TAG_JPEG_LENGTH)) {
+            candidates.push((offset as usize, length as usize));
+        }
+        // 以单个 JPEG 条带保存的预览（CR2 的 IFD0、DNG 的预览 SubIFD）
+        if matches!(first(entries, TAG_COMPRESSION), Some(6) | Some(7)) {
+            let offsets: Option<&Vec<u32>> = entries.get(&TAG_STRIP_OFFSETS);
+            let lengths: Option<&Vec<u32>> = entries.get(&TAG_STRIP_BYTE_COUNTS);
+            if let (Some([offset]), Some([length])) = (offsets.map(Vec::as_slice), lengths.map(Vec::as_slice)) {
+                candidates.push((*offset as usize, *length as usize));
+            }
+        }
+        
+        for (offset, length) in candidates {
+            if let Some((width, height)) = tiff.read_jpeg_dimensions(offset, length) {
+                self.previews.push((offset, length, width, height));
+            }
+        }
+    }
+    
+    /// 图像按方向显示时的尺寸，取最大的预览和传感器数据中面积较大者
+    pub fn get_dimensions(&self) -> Option<(u32, u32)> {
+        let (width, height) = self.previews
+            .last()
+            .map(|(_, _, width, height)| (*width, *height))
+            .into_iter()
+            .chain(self.sensor_size)
+            .max_by_key(|(width, height)| *width as u64 * *height as u64)?;
+        
+        if orientation_swaps_dimensions(self.orientation) {
+            Some((height, width))
+        } else {
+            Some((width, height))
+        }
+    }
+    
+    pub fn get_orientation(&self) -> Orientation {
+        self.orientation
+    }
+    
+    /// 长边不小于 `min_side` 的最小预览，都不够大时返回最大的预览
+    pub fn select_preview(&self, min_side: u32) -> Option<(usize, usize)> {
+        self.previews
+            .iter()
+            .find(|(_, _, width, height)| (*width).max(*height) >= min_side)
+            .or(self.previews.last())
+            .map(|(offset, length, _, _)| (*offset, *length))
+    }
+}
+
+/// 读取 RAW 文件按方向显示时的尺寸
+pub fn read_raw_dimensions(path: &Path) -> Result<(u32, u32)> {
+    RawLayout::parse(BufReader::new(File::open(path)?))
+        .and_then(|layout| layout.get_dimensions())
+        .ok_or_else(|| crate::WallpaperError::Service(format!("无法解析 RAW 文件结构: {:?}", path)))
+}
+
+/// 解码内嵌的 JPEG 预览并按方向旋转，用于快速生成缩略图和预览；没有可用预览时返回 `None`
+pub fn load_raw_preview(path: &Path, min_side: u32) -> Result<Option<DynamicImage>> {
+    let mut reader: BufReader<File> = BufReader::new(File::open(path)?);
+    let Some(layout) = RawLayout::parse(&mut reader) else {
+        return Ok(None);
+    };
+    let Some((offset, length)) = layout.select_preview(min_side) else {
+        return Ok(None);
+    };
+    
+    // 只读取选中的预览
+    let mut jpeg: Vec<u8> = vec![0; length];
+    reader.seek(SeekFrom::Start(offset as u64))?;
+    reader.read_exact(&mut jpeg)?;
+    let mut image: DynamicImage = image::load_from_memory_with_format(&jpeg, ImageFormat::Jpeg)?;
+    image.apply_orientation(layout.get_orientation());
+    Ok(Some(image))
+}
+
+/// 基本的 RAW 显影：拜耳阵列双线性插值、白平衡和 sRGB 伽马
+///
+/// `data` 为已减去黑电平并归一化到 0..=1 的传感器数据，`color_at(row, col)` 返回该位置的
+/// 滤色片颜色（0 红、1 绿、2 蓝，3 视为绿），`white_balance` 为以绿色为 1 的 RGB 增益
+pub fn demosaic_bilinear(
+    data: &[f32],
+    width: usize,
+    height: usize,
+    color_at: impl Fn(usize, usize) -> usize,
+    white_balance: [f32; 3],
+) -> RgbImage {
+    let color = |row: usize, col: usize| -> usize {
+        match color_at(row, col) {
+            3 => 1,
+            channel => channel.min(2),
+        }
+    };
+    
+    RgbImage::from_fn(width as u32, height as u32, |x, y| {
+        let (col, row) = (x as usize, y as usize);
+        let mut sums: [f32; 3] = [0.0; 3];
+        let mut counts: [u32; 3] = [0; 3];
+        
+        // 本像素的颜色直接使用，其余颜色取 3x3 邻域内同色像素的平均值
+        let own: usize = color(row, col);
+        sums[own] = data[row * width + col];
+        counts[own] = 1;
+        for neighbor_row in row.saturating_sub(1)..(row + 2).min(height) {
+            for neighbor_col in col.saturating_sub(1)..(col + 2).min(width) {
+                let channel: usize = color(neighbor_row, neighbor_col);
+                if channel != own {
+                    sums[channel] += data[neighbor_row * width + neighbor_col];
+                    counts[channel] += 1;
+                }
+            }
+        }
+        
+        let mut pixel: [u8; 3] = [0; 3];
+        for channel in 0..3 {
+            let value: f32 = sums[channel] / counts[channel].max(1) as f32 * white_balance[channel];
+            pixel[channel] = (value.clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0).round() as u8;
+        }
+        image::Rgb(pixel)
+    })
+}
+
+/// 完整解码并显影 RAW 文件，用于将 RAW 设置为壁纸
+#[cfg(feature = "raw")]
+pub fn develop_raw(path: &Path) -> Result<DynamicImage> {
+    use rawloader::RawImageData;
+    use crate::utils::extra_formats::decoding_error;
+    
+    let raw: rawloader::RawImage = rawloader::decode_file(path).map_err(|e| decoding_error("RAW", e.to_string()))?;
+    if raw.cpp != 1 {
+        return Err(decoding_error("RAW", format!("不支持每像素 {} 个分量的 RAW 数据", raw.cpp)));
+    }
+    
+    // 减去黑电平并按白电平归一化，浮点数据（如浮点 DNG）的电平与数据使用同一刻度
+    let black: f32 = raw.blacklevels[0] as f32;
+    let range: f32 = (raw.whitelevels[0] as f32 - black).max(1.0);
+    let values: Vec<f32> = match &raw.data {
+        RawImageData::Integer(data) => data.iter().map(|value| (*value as f32 - black) / range).collect(),
+        RawImageData::Float(data) => data.iter().map(|value| (*value - black) / range).collect(),
+    };
+    
+    // 白平衡系数以绿色为基准，缺失时不做调整
+    let green: f32 = raw.wb_coeffs[1];
+    let white_balance: [f32; 3] = [0, 1, 2].map(|channel| {
+        let gain: f32 = raw.wb_coeffs[channel] / green;
+        if gain.is_finite() && gain > 0.0 { gain } else { 1.0 }
+    });
+    
+    let developed: RgbImage = demosaic_bilinear(&values, raw.width, raw.height, |row, col| raw.cfa.color_at(row, col), white_balance);
+    
+    // 裁掉传感器边缘的遮光区域，crops 依次为上、右、下、左
+    let [top, right, bottom, left] = raw.crops;
+    let mut image: DynamicImage = DynamicImage::ImageRgb8(developed).crop_imm(
+        left as u32,
+        top as u32,
+        raw.width.saturating_sub(left + right) as u32,
+        raw.height.saturating_sub(top + bottom) as u32,
+    );
+    if let Some(orientation) = Orientation::from_exif(raw.orientation.to_u16() as u8) {
+        image.apply_orientation(orientation);
+    }
+    Ok(image)
+}
+
+fn first(entries: &HashMap<u16, Vec<u32>>, tag: u16) -> Option<u32> {
+    entries.get(&tag).and_then(|values| values.first().copied())
+}
+
+/// 只读取 SHORT、LONG 和 IFD 类型字段的最小 TIFF 解析器，按偏移定位读取，不载入整个文件
+struct Tiff<R> {
+    reader: R,
+    little_endian: bool,
+}
+
+impl<R: Read + Seek> Tiff<R> {
+    fn new(mut reader: R) -> Option<Self> {
+        let mut header: [u8; 4] = [0; 4];
+        reader.seek(SeekFrom::Start(0)).ok()?;
+        reader.read_exact(&mut header).ok()?;
+        let little_endian: bool = match header {
+            [b'I', b'I', 42, 0] => true,
+            [b'M', b'M', 0, 42] => false,
+            _ => return None,
+        };
+        Some(Self { reader, little_endian })
+    }
+    
+    fn read_at(&mut self, offset: usize, buffer: &mut [u8]) -> Option<()> {
+        self.reader.seek(SeekFrom::Start(offset as u64)).ok()?;
+        self.reader.read_exact(buffer).ok()
+    }
+    
+    fn get_u16(&self, bytes: &[u8]) -> u16 {
+        let bytes: [u8; 2] = [bytes[0], bytes[1]];
+        if self.little_endian { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) }
+    }
+    
+    fn get_u32(&self, bytes: &[u8]) -> u32 {
+        let bytes: [u8; 4] = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.little_endian { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) }
+    }
+    
+    fn read_u16(&mut self, offset: usize) -> Option<u16> {
+        let mut bytes: [u8; 2] = [0; 2];
+        self.read_at(offset, &mut bytes)?;
+        Some(self.get_u16(&bytes))
+    }
+    
+    fn read_u32(&mut self, offset: usize) -> Option<u32> {
+        let mut bytes: [u8; 4] = [0; 4];
+        self.read_at(offset, &mut bytes)?;
+        Some(self.get_u32(&bytes))
+    }
+    
+    /// 读取一个 IFD 的字段和下一个 IFD 的偏移
+    fn read_ifd(&mut self, offset: usize) -> Option<(HashMap<u16, Vec<u32>>, usize)> {
+        let count: usize = self.read_u16(offset)? as usize;
+        let mut table: Vec<u8> = vec![0; count * 12 + 4];
+        self.read_at(offset + 2, &mut table)?;
+        let mut entries: HashMap<u16, Vec<u32>> = HashMap::new();
+        
+        for entry in table[..count * 12].chunks_exact(12) {
+            let tag: u16 = self.get_u16(&entry[0..2]);
+            let field_type: u16 = self.get_u16(&entry[2..4]);
+            let value_count: usize = (self.get_u32(&entry[4..8]) as usize).min(4096);
+            let size: usize = match field_type {
+                3 => 2,
+                4 | 13 => 4,
+                _ => continue,
+            };
+            // 不超过 4 字节的值直接存放在字段中
+            let byte_count: usize = size * value_count;
+            let mut values: Vec<u8> = vec![0; byte_count];
+            if byte_count <= 4 {
+                values.copy_from_slice(&entry[8..8 + byte_count]);
+            } else {
+                let values_offset: usize = self.get_u32(&entry[8..12]) as usize;
+                if self.read_at(values_offset, &mut values).is_none() {
+                    continue;
+                }
+            }
+            let values: Vec<u32> = values
+                .chunks_exact(size)
+                .map(|value| if size == 2 { u32::from(self.get_u16(value)) } else { self.get_u32(value) })
+                .collect();
+            entries.insert(tag, values);
+        }
+        
+        let next: usize = self.get_u32(&table[count * 12..]) as usize;
+        Some((entries, next))
+    }
+    
+    /// 读取 JPEG 头部中的尺寸；无损 JPEG（CR2 的传感器数据）等无法作为预览的数据返回 `None`
+    fn read_jpeg_dimensions(&mut self, offset: usize, length: usize) -> Option<(u32, u32)> {
+        self.reader.seek(SeekFrom::Start(offset as u64)).ok()?;
+        let mut decoder: jpeg_decoder::Decoder<std::io::Take<&mut R>> = jpeg_decoder::Decoder::new((&mut self.reader).take(length as u64));
+        decoder.read_info().ok()?;
+        let info: jpeg_decoder::ImageInfo = decoder.info()?;
+        if info.coding_process == CodingProcess::Lossless {
+            return None;
+        }
+        Some((info.width as u32, info.height as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    fn encode_jpeg(width: u32, height: u32) -> Vec<u8> {
+        let mut encoded: Vec<u8> = Vec::new();
+        RgbImage::from_pixel(width, height, image::Rgb([40, 120, 200]))
+            .write_to(&mut Cursor::new(&mut encoded), ImageFormat::Jpeg)
+            .unwrap();
+        encoded
+    }
+    
+    /// 构造类似 NEF 的小端 TIFF：IFD0 带小预览和方向，SubIFD 为大预览和 CFA 原始数据
+    fn create_raw(orientation: u16) -> Vec<u8> {
+        let small: Vec<u8> = encode_jpeg(160, 120);
+        let large: Vec<u8> = encode_jpeg(640, 480);
+        
+        // 布局：头部(8) + IFD0 + SubIFD 偏移数组 + 两个 SubIFD + JPEG 数据
+        let ifd0: usize = 8;
+        let ifd0_entries: usize = 4;
+        let sub_offsets: usize = ifd0 + 2 + ifd0_entries * 12 + 4;
+        let sub1: usize = sub_offsets + 8;
+        let sub1_entries: usize = 4;
+        let sub2: usize = sub1 + 2 + sub1_entries * 12 + 4;
+        let sub2_entries: usize = 4;
+        let small_offset: usize = sub2 + 2 + sub2_entries * 12 + 4;
+        let large_offset: usize = small_offset + small.len();
+        
+        let mut data: Vec<u8> = b"II*\0".to_vec();
+        data.extend_from_slice(&(ifd0 as u32).to_le_bytes());
+        fn write_ifd(data: &mut Vec<u8>, entries: &[(u16, u16, u32, u32)]) {
+            data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+            for (tag, field_type, count, value) in entries {
+                data.extend_from_slice(&tag.to_le_bytes());
+                data.extend_from_slice(&field_type.to_le_bytes());
+                data.extend_from_slice(&count.to_le_bytes());
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+            data.extend_from_slice(&0u32.to_le_bytes());
+        }
+        
+        write_ifd(&mut data, &[
+            (TAG_ORIENTATION, 3, 1, orientation as u32),
+            (TAG_SUB_IFDS, 4, 2, sub_offsets as u32),
+            (TAG_JPEG_OFFSET, 4, 1, small_offset as u32),
+            (TAG_JPEG_LENGTH, 4, 1, small.len() as u32),
+        ]);
+        data.extend_from_slice(&(sub1 as u32).to_le_bytes());
+        data.extend_from_slice(&(sub2 as u32).to_le_bytes());
+        write_ifd(&mut data, &[
+            (TAG_NEW_SUBFILE_TYPE, 4, 1, 1),
+            (TAG_COMPRESSION, 3, 1, 6),
+            (TAG_STRIP_OFFSETS, 4, 1, large_offset as u32),
+            (TAG_STRIP_BYTE_COUNTS, 4, 1, large.len() as u32),
+        ]);
+        write_ifd(&mut data, &[
+            (TAG_NEW_SUBFILE_TYPE, 4, 1, 0),
+            (TAG_IMAGE_WIDTH, 4, 1, 648),
+            (TAG_IMAGE_LENGTH, 4, 1, 486),
+            (TAG_PHOTOMETRIC, 3, 1, 32803),
+        ]);
+        assert_eq!(data.len(), small_offset);
+        data.extend_from_slice(&small);
+        data.extend_from_slice(&large);
+        data
+    }
+
+    #[test]
+    fn test_raw_layout() {
+        let layout: RawLayout = RawLayout::parse(Cursor::new(create_raw(1))).unwrap();
+        assert_eq!(layout.previews.len(), 2);
+        assert_eq!(layout.get_dimensions(), Some((648, 486)));
+        
+        // 选择足够大的最小预览
+        let (small_offset, _) = layout.select_preview(100).unwrap();
+        let (large_offset, _) = layout.select_preview(300).unwrap();
+        assert!(small_offset < large_offset);
+        assert_eq!(layout.select_preview(4000), Some((large_offset, layout.previews[1].1)));
+        
+        // 竖拍的 RAW 交换宽高，预览也按方向旋转
+        let path: PathBuf = std::env::temp_dir()
+            .join(format!("wallpaper-explorer-raw-{}.nef", std::process::id()));
+        std::fs::write(&path, create_raw(6)).unwrap();
+        assert_eq!(read_raw_dimensions(&path).unwrap(), (486, 648));
+        let preview: DynamicImage = load_raw_preview(&path, 300).unwrap().unwrap();
+        assert_eq!((preview.width(), preview.height()), (480, 640));
+        
+        assert!(RawLayout::parse(Cursor::new(b"not a tiff")).is_none());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_demosaic_bilinear() {
+        // RGGB 拜耳阵列拍摄的均匀灰色，红、蓝通道较弱，需要白平衡补偿
+        let rggb = |row: usize, col: usize| [[0, 1], [1, 2]][row % 2][col % 2];
+        let data: Vec<f32> = (0..16)
+            .map(|index| match rggb(index / 4, index % 4) {
+                0 => 0.1,
+                2 => 0.125,
+                _ => 0.25,
+            })
+            .collect();
+        
+        let image: RgbImage = demosaic_bilinear(&data, 4, 4, rggb, [2.5, 1.0, 2.0]);
+        for pixel in image.pixels() {
+            assert_eq!(pixel[0], pixel[1]);
+            assert_eq!(pixel[1], pixel[2]);
+        }
+        assert_eq!(image.get_pixel(1, 1)[1], 136);
+    }
+}
\ No newline at end of file
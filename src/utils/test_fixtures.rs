@@ -0,0 +1,60 @@
+//! 构造测试用 JPEG 文件的工具，单元测试和集成测试共用
+//!
+//! 集成测试无法访问 `#[cfg(test)]` 模块，通过 `#[path]` 直接引入本文件，因此只依赖外部 crate
+#![allow(dead_code)]
+
+use std::path::Path;
+use exif::{Field, In, Tag, Value};
+use image::{ImageFormat, RgbImage};
+
+/// XMP APP1 段的标识头
+const XMP_IDENTIFIER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+
+pub fn encode_jpeg(image: &RgbImage) -> Vec<u8> {
+    let mut encoded: Vec<u8> = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut encoded), ImageFormat::Jpeg).unwrap();
+    encoded
+}
+
+/// 由 EXIF 字段生成 APP1 段，`thumbnail` 为写入 IFD1 的 JPEG 缩略图
+pub fn exif_segment(fields: &[Field], thumbnail: Option<&[u8]>) -> Vec<u8> {
+    let mut writer = exif::experimental::Writer::new();
+    for field in fields {
+        writer.push_field(field);
+    }
+    if let Some(thumbnail) = thumbnail {
+        writer.set_jpeg(thumbnail, In::THUMBNAIL);
+    }
+    let mut tiff: std::io::Cursor<Vec<u8>> = std::io::Cursor::new(Vec::new());
+    writer.write(&mut tiff, true).unwrap();
+    
+    [b"Exif\0\0".as_slice(), tiff.get_ref()].concat()
+}
+
+/// 只包含 Orientation 的 EXIF APP1 段
+pub fn exif_orientation_segment(orientation: u16) -> Vec<u8> {
+    let field: Field = Field {
+        tag: Tag::Orientation,
+        ifd_num: In::PRIMARY,
+        value: Value::Short(vec![orientation]),
+    };
+    exif_segment(&[field], None)
+}
+
+pub fn xmp_segment(xmp: &str) -> Vec<u8> {
+    [XMP_IDENTIFIER, xmp.as_bytes()].concat()
+}
+
+/// 将图像编码为 JPEG，在 SOI 之后依次插入 APP1 段（EXIF 或 XMP，含标识头）并写入 `path`
+pub fn write_jpeg_with_app1(path: &Path, image: &RgbImage, segments: &[Vec<u8>]) {
+    let encoded: Vec<u8> = encode_jpeg(image);
+    
+    let mut bytes: Vec<u8> = vec![0xFF, 0xD8];
+    for segment in segments {
+        bytes.extend_from_slice(&[0xFF, 0xE1]);
+        bytes.extend_from_slice(&((segment.len() + 2) as u16).to_be_bytes());
+        bytes.extend_from_slice(segment);
+    }
+    bytes.extend_from_slice(&encoded[2..]);
+    std::fs::write(path, bytes).unwrap();
+}
\ No newline at end of file
@@ -0,0 +1,207 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use exif::{In, Tag};
+use fast_image_resize::{self as fir, ResizeAlg, ResizeOptions, Resizer};
+use image::{DynamicImage, GrayImage, ImageDecoder, ImageFormat, ImageReader, RgbImage};
+use image::imageops::FilterType;
+use image::metadata::Orientation;
+use jpeg_decoder::PixelFormat;
+use crate::Result;
+use crate::utils::{ExtraFormat, get_aspect_ratio, open_oriented};
+
+/// DCT 域缩放解码时保留目标尺寸的倍数，给最后的 Lanczos3 留下足够的细节
+const OVERSAMPLING: u32 = 2;
+
+/// 内嵌缩略图与原图宽高比允许的相对误差，超出时说明内嵌缩略图带有黑边
+const EMBEDDED_ASPECT_TOLERANCE: f32 = 0.02;
+
+/// 缩略图尺寸档位（正方形边界框的边长），最大档位用于高缩放级别下的 2 倍 HiDPI 显示
+pub const THUMBNAIL_TIERS: &[u32] = &[128, 256, 512, 1024];
+
+/// 选择能覆盖所需像素边长的最小档位，超出所有档位时使用最大档位
+pub fn select_thumbnail_tier(required: u32) -> u32 {
+    THUMBNAIL_TIERS
+        .iter()
+        .copied()
+        .find(|tier| *tier >= required)
+        .unwrap_or(THUMBNAIL_TIERS[THUMBNAIL_TIERS.len() - 1])
+}
+
+/// 加载用于生成缩略图的图像，已按 EXIF 方向旋转，长边不小于 `max_side`（原图更小时除外）
+///
+/// 依次尝试足够大的 EXIF 内嵌缩略图、JPEG 的 DCT 域缩放解码（1/2、1/4、1/8），
+/// 都不可用时才完整解码原图
+pub fn load_for_thumbnail(path: &Path, max_side: u32) -> Result<DynamicImage> {
+    // 可选格式自行解码，SVG 直接按缩略图尺寸栅格化
+    if let Some(format) = ExtraFormat::detect_custom(path) {
+        return format.decode(path, Some((max_side, max_side)));
+    }
+    
+    let reader: ImageReader<BufReader<File>> = ImageReader::open(path)?.with_guessed_format()?;
+    let is_jpeg: bool = reader.format() == Some(ImageFormat::Jpeg);
+    let mut decoder = reader.into_decoder()?;
+    let orientation: Orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+    let size: (u32, u32) = decoder.dimensions();
+    drop(decoder);
+    
+    let reduced: Option<DynamicImage> = load_embedded_thumbnail(path, size, max_side).or_else(|| {
+        if is_jpeg {
+            decode_scaled_jpeg(path, size, max_side * OVERSAMPLING)
+        } else {
+            None
+        }
+    });
+    
+    match reduced {
+        Some(mut image) => {
+            image.apply_orientation(orientation);
+            Ok(image)
+        }
+        None => open_oriented(path),
+    }
+}
+
+/// 用 fast_image_resize 的 SIMD 卷积（Lanczos3）缩放到 `width`×`height`，透明图像按预乘 alpha 处理
+///
+/// fast_image_resize 不支持的像素格式退回 image 的实现
+pub fn resize_thumbnail(image: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    let mut output: DynamicImage = DynamicImage::new(width.max(1), height.max(1), image.color());
+    let options: ResizeOptions = ResizeOptions::new().resize_alg(ResizeAlg::Convolution(fir::FilterType::Lanczos3));
+    match Resizer::new().resize(image, &mut output, &options) {
+        Ok(()) => output,
+        Err(e) => {
+            log::debug!("fast_image_resize 无法缩放 {:?} 图像: {}", image.color(), e);
+            image.resize_exact(width, height, FilterType::Lanczos3)
+        }
+    }
+}
+
+/// 读取 EXIF 内嵌的 JPEG 缩略图，尺寸不足或宽高比与原图不符时返回 `None`
+fn load_embedded_thumbnail(path: &Path, size: (u32, u32), max_side: u32) -> Option<DynamicImage> {
+    let mut reader: BufReader<File> = BufReader::new(File::open(path).ok()?);
+    let exif: exif::Exif = exif::Reader::new().read_from_container(&mut reader).ok()?;
+    let offset: usize = exif.get_field(Tag::JPEGInterchangeFormat, In::THUMBNAIL)?.value.get_uint(0)? as usize;
+    let length: usize = exif.get_field(Tag::JPEGInterchangeFormatLength, In::THUMBNAIL)?.value.get_uint(0)? as usize;
+    let data: &[u8] = exif.buf().get(offset..offset.checked_add(length)?)?;
+    
+    let thumbnail: DynamicImage = image::load_from_memory_with_format(data, ImageFormat::Jpeg).ok()?;
+    let large_enough: bool = thumbnail.width().max(thumbnail.height()) >= max_side;
+    let ratio: f32 = get_aspect_ratio(thumbnail.width(), thumbnail.height()) / get_aspect_ratio(size.0, size.1);
+    
+    (large_enough && (ratio - 1.0).abs() <= EMBEDDED_ASPECT_TOLERANCE).then_some(thumbnail)
+}
+
+/// 在 DCT 域按 1/2、1/4 或 1/8 缩小解码 JPEG，结果的长边不小于 `target_side`
+///
+/// 无法至少缩小一半，或为 CMYK、16 位灰度等格式时返回 `None`，交给 image 完整解码
+fn decode_scaled_jpeg(path: &Path, size: (u32, u32), target_side: u32) -> Option<DynamicImage> {
+    let long_side: u32 = size.0.max(size.1);
+    if long_side < target_side * 2 {
+        return None;
+    }
+    
+    let ratio: f32 = target_side as f32 / long_side as f32;
+    let requested_width: u16 = ((size.0 as f32 * ratio).ceil() as u16).max(1);
+    let requested_height: u16 = ((size.1 as f32 * ratio).ceil() as u16).max(1);
+    
+    let mut decoder: jpeg_decoder::Decoder<BufReader<File>> = jpeg_decoder::Decoder::new(BufReader::new(File::open(path).ok()?));
+    let (width, height) = decoder.scale(requested_width, requested_height).ok()?;
+    let pixels: Vec<u8> = match decoder.decode() {
+        Ok(pixels) => pixels,
+        Err(e) => {
+            log::debug!("缩放解码 {:?} 失败，改为完整解码: {}", path, e);
+            return None;
+        }
+    };
+    
+    match decoder.info()?.pixel_format {
+        PixelFormat::RGB24 => RgbImage::from_raw(width as u32, height as u32, pixels).map(DynamicImage::ImageRgb8),
+        PixelFormat::L8 => GrayImage::from_raw(width as u32, height as u32, pixels).map(DynamicImage::ImageLuma8),
+        PixelFormat::L16 | PixelFormat::CMYK32 => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use crate::utils::test_fixtures::{encode_jpeg, exif_segment, write_jpeg_with_app1};
+
+    /// 写入带有 EXIF 内嵌缩略图的 JPEG
+    fn create_jpeg_with_thumbnail(path: &Path, image: &RgbImage, thumbnail: &RgbImage) {
+        let make: exif::Field = exif::Field {
+            tag: Tag::Make,
+            ifd_num: In::PRIMARY,
+            value: exif::Value::Ascii(vec![b"Test".to_vec()]),
+        };
+        write_jpeg_with_app1(path, image, &[exif_segment(&[make], Some(&encode_jpeg(thumbnail)))]);
+    }
+    
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("wallpaper-explorer-decode-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn test_embedded_thumbnail() {
+        let path: PathBuf = temp_path("embedded.jpg");
+        let red: RgbImage = RgbImage::from_pixel(640, 480, image::Rgb([220, 0, 0]));
+        let blue: RgbImage = RgbImage::from_pixel(320, 240, image::Rgb([0, 0, 220]));
+        create_jpeg_with_thumbnail(&path, &red, &blue);
+        
+        let image: DynamicImage = load_for_thumbnail(&path, 200).unwrap();
+        assert_eq!((image.width(), image.height()), (320, 240));
+        assert!(image.to_rgb8().get_pixel(10, 10)[2] > 200);
+        
+        // 内嵌缩略图不够大时解码原图
+        let image: DynamicImage = load_for_thumbnail(&path, 400).unwrap();
+        assert_eq!((image.width(), image.height()), (640, 480));
+        
+        // 宽高比不符（带黑边）时不使用
+        let letterboxed: RgbImage = RgbImage::from_pixel(320, 320, image::Rgb([0, 0, 220]));
+        create_jpeg_with_thumbnail(&path, &red, &letterboxed);
+        assert_eq!(load_for_thumbnail(&path, 200).unwrap().width(), 640);
+        
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_scaled_jpeg_decode() {
+        let path: PathBuf = temp_path("scaled.jpg");
+        std::fs::write(&path, encode_jpeg(&RgbImage::from_pixel(1600, 1200, image::Rgb([0, 160, 0])))).unwrap();
+        
+        // 需要 200 像素（目标的两倍），按 1/8 解码
+        let image: DynamicImage = load_for_thumbnail(&path, 100).unwrap();
+        assert_eq!((image.width(), image.height()), (200, 150));
+        assert!(image.to_rgb8().get_pixel(100, 75)[1] > 140);
+        
+        let image: DynamicImage = load_for_thumbnail(&path, 300).unwrap();
+        assert_eq!((image.width(), image.height()), (800, 600));
+        
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_resize_thumbnail() {
+        let opaque: DynamicImage = DynamicImage::ImageRgb8(RgbImage::from_pixel(1000, 800, image::Rgb([0, 160, 0])));
+        let resized: DynamicImage = resize_thumbnail(&opaque, 100, 80);
+        assert_eq!((resized.width(), resized.height()), (100, 80));
+        assert_eq!(resized.color(), opaque.color());
+        assert!(resized.to_rgb8().get_pixel(50, 40)[1] > 150);
+        
+        // 完全透明的像素不能把颜色渗进相邻的不透明像素
+        let mut pixels: image::RgbaImage = image::RgbaImage::from_pixel(400, 400, image::Rgba([255, 0, 0, 255]));
+        for x in 0..200 {
+            for y in 0..400 {
+                pixels.put_pixel(x, y, image::Rgba([0, 0, 0, 0]));
+            }
+        }
+        let resized: image::RgbaImage = resize_thumbnail(&DynamicImage::ImageRgba8(pixels), 40, 40).to_rgba8();
+        assert_eq!(resized.get_pixel(5, 20)[3], 0);
+        assert!(resized.get_pixel(22, 20)[0] > 240);
+        
+        // 不支持的像素格式退回 image 的实现
+        let float: DynamicImage = DynamicImage::ImageRgb32F(image::Rgb32FImage::new(300, 200));
+        assert_eq!(resize_thumbnail(&float, 30, 20).width(), 30);
+    }
+}
\ No newline at end of file
@@ -1,5 +1,8 @@
 use Wallpaper_Explorer::Result;
 
+#[path = "../src/utils/test_fixtures.rs"]
+mod test_fixtures;
+
 #[test]
 fn test_app_creation() -> Result<()> {
     // 这里可以添加集成测试
@@ -9,12 +12,420 @@ fn test_app_creation() -> Result<()> {
 
 #[test]
 fn test_config_loading() -> Result<()> {
-    use Wallpaper_Explorer::config::Config;
+    use Wallpaper_Explorer::config::{Config, SharedThumbnailMode};
     
     let config: Config = Config::default();
     assert!(!config.supported_formats.is_empty());
     assert!(config.thumbnail_size.0 > 0);
     assert!(config.thumbnail_size.1 > 0);
     
+    // 共享缩略图缓存只在 Linux 上默认启用，旧配置中的 disabled 仍可读取
+    let expected: SharedThumbnailMode = if cfg!(target_os = "linux") {
+        SharedThumbnailMode::ReadOnly
+    } else {
+        SharedThumbnailMode::Off
+    };
+    assert_eq!(config.shared_thumbnails, expected);
+    let config: Config = toml::from_str("shared_thumbnails = \"disabled\"").unwrap();
+    assert_eq!(config.shared_thumbnails, SharedThumbnailMode::Off);
+    
+    // 只扫描配置中列出的格式，可选格式还需要启用对应的 feature
+    let config: Config = Config {
+        supported_formats: vec!["PNG".to_string(), "svg".to_string()],
+        ..Config::default()
+    };
+    assert!(config.is_supported_format(std::path::Path::new("a.png")));
+    assert!(!config.is_supported_format(std::path::Path::new("a.jpg")));
+    assert!(!config.is_supported_format(std::path::Path::new("a.avif")));
+    assert_eq!(config.is_supported_format(std::path::Path::new("a.svg")), cfg!(feature = "svg"));
+    
+    // 旧配置文件在加载时加入已启用的可选格式，用户删除后不会再次加入
+    let mut config: Config = toml::from_str("supported_formats = [\"jpg\"]").unwrap();
+    assert_eq!(config.merge_extra_formats(), !Wallpaper_Explorer::utils::enabled_extra_extensions().is_empty());
+    assert_eq!(config.is_supported_format(std::path::Path::new("a.svg")), cfg!(feature = "svg"));
+    config.supported_formats.retain(|format| format != "svg");
+    assert!(!config.merge_extra_formats());
+    assert!(!config.is_supported_format(std::path::Path::new("a.svg")));
+    
+    Ok(())
+} 
+
+fn create_test_directory(name: &str) -> std::path::PathBuf {
+    let directory: std::path::PathBuf = std::env::temp_dir()
+        .join(format!("wallpaper-explorer-test-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+fn create_test_config(root: &std::path::Path) -> Wallpaper_Explorer::config::Config {
+    let wallpaper_directory: std::path::PathBuf = root.join("wallpapers");
+    std::fs::create_dir_all(&wallpaper_directory).unwrap();
+    
+    Wallpaper_Explorer::config::Config {
+        wallpaper_directories: vec![wallpaper_directory],
+        cache_directory: root.join("cache"),
+        data_directory: root.join("data"),
+        ..Wallpaper_Explorer::config::Config::default()
+    }
+}
+
+fn create_test_image(path: &std::path::Path, width: u32, height: u32) {
+    image::RgbImage::from_pixel(width, height, image::Rgb([200, 120, 40]))
+        .save(path)
+        .unwrap();
+}
+
+/// 写入带有额外 APP1 段（EXIF 或 XMP）的 JPEG
+fn create_test_jpeg_with_app1(path: &std::path::Path, width: u32, height: u32, segments: &[Vec<u8>]) {
+    let image: image::RgbImage = image::RgbImage::from_pixel(width, height, image::Rgb([10, 20, 30]));
+    test_fixtures::write_jpeg_with_app1(path, &image, segments);
+}
+
+#[test]
+fn test_batch_operations_on_selection() -> Result<()> {
+    use Wallpaper_Explorer::components::WallpaperGrid;
+    use Wallpaper_Explorer::services::{CollectionService, WallpaperService};
+    
+    let root: std::path::PathBuf = create_test_directory("batch");
+    let config = create_test_config(&root);
+    for name in ["a.png", "b.png", "c.png"] {
+        create_test_image(&config.wallpaper_directories[0].join(name), 32, 18);
+    }
+    
+    let mut service: WallpaperService = WallpaperService::new(&config)?;
+    service.scan_wallpapers()?;
+    
+    let mut grid: WallpaperGrid = WallpaperGrid::new(3);
+    grid.set_wallpapers(service.get_wallpapers().to_vec());
+    grid.sort_by_name(true);
+    grid.select_wallpaper(0);
+    grid.toggle_selection(2);
+    let selection: Vec<String> = grid.get_selected_ids();
+    
+    let report = service.add_tag_to_wallpapers(&selection, " Sky / Sunset ")?;
+    assert!(report.is_success());
+    assert_eq!(service.search_str("tag:sky")?.len(), 2);
+    
+    let report = service.export_wallpapers(&selection, &root.join("export"))?;
+    assert_eq!(report.succeeded.len(), 2);
+    assert!(root.join("export").join("a.png").exists());
+    assert!(root.join("export").join("c.png").exists());
+    
+    let mut collections: CollectionService = CollectionService::new(&config)?;
+    let album_id: String = collections.create_album("日落", "")?.id.clone();
+    assert_eq!(collections.add_selection(&album_id, &grid)?, 2);
+    
+    let report = service.move_wallpapers(&selection, &root.join("moved"))?;
+    assert!(report.is_success());
+    collections.replace_ids(&report.renamed)?;
+    assert_eq!(collections.get_album(&album_id).unwrap().wallpaper_ids, report.succeeded);
+    assert!(root.join("moved").join("a.png").exists());
+    assert!(!config.wallpaper_directories[0].join("a.png").exists());
+    assert_eq!(service.get_tag_service().get_tags(&report.succeeded[0]), ["sky/sunset"]);
+    
+    let report = service.delete_wallpapers(&report.succeeded);
+    assert!(report.is_success());
+    assert!(!root.join("moved").join("c.png").exists());
+    assert_eq!(service.get_wallpapers().len(), 1);
+    
+    grid.set_wallpapers(service.get_wallpapers().to_vec());
+    assert_eq!(grid.get_selection_count(), 0);
+    
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn test_smart_collections_persist_in_config() -> Result<()> {
+    use Wallpaper_Explorer::config::Config;
+    use Wallpaper_Explorer::models::WallpaperSource;
+    use Wallpaper_Explorer::services::{CollectionService, SmartCollectionService, WallpaperService};
+
+    // 旧版本的配置文件没有 smart_collections 字段
+    let legacy: Config = toml::from_str("max_cache_size_mb = 100").unwrap();
+    assert!(legacy.smart_collections.is_empty());
+    assert_eq!(legacy.max_cache_size_mb, 100);
+    
+    let root: std::path::PathBuf = create_test_directory("smart");
+    let mut config: Config = create_test_config(&root);
+    create_test_image(&config.wallpaper_directories[0].join("wide.png"), 64, 27);
+    create_test_image(&config.wallpaper_directories[0].join("tall.png"), 27, 64);
+    
+    let mut smart_collections: SmartCollectionService = SmartCollectionService::new(&config);
+    let id: String = smart_collections.create("竖屏", "is:portrait")?.id.clone();
+    config.smart_collections = smart_collections.get_collections().to_vec();
+    
+    let content: String = toml::to_string_pretty(&config).unwrap();
+    let reloaded: Config = toml::from_str(&content).unwrap();
+    assert_eq!(reloaded.smart_collections, config.smart_collections);
+    
+    let mut service: WallpaperService = WallpaperService::new(&reloaded)?;
+    service.scan_wallpapers()?;
+    
+    let mut smart_collections: SmartCollectionService = SmartCollectionService::new(&reloaded);
+    service.refresh_smart_collections(&mut smart_collections);
+    
+    let collections: CollectionService = CollectionService::new(&reloaded)?;
+    let source: WallpaperSource = WallpaperSource::SmartCollection(id);
+    let resolved: Vec<String> = service.resolve_source(&source, &smart_collections, &collections)
+        .into_iter()
+        .map(|wallpaper| wallpaper.filename.clone())
+        .collect();
+    assert_eq!(resolved, vec!["tall.png"]);
+    
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn test_tag_rules_applied_at_scan() -> Result<()> {
+    use Wallpaper_Explorer::config::Config;
+    use Wallpaper_Explorer::models::tag_rule::Orientation;
+    use Wallpaper_Explorer::models::{RuleCondition, TagRule};
+    use Wallpaper_Explorer::services::WallpaperService;
+    use Wallpaper_Explorer::utils::ColorName;
+    
+    let root: std::path::PathBuf = create_test_directory("rules");
+    let mut config: Config = create_test_config(&root);
+    let anime_directory: std::path::PathBuf = config.wallpaper_directories[0].join("Anime");
+    std::fs::create_dir_all(&anime_directory).unwrap();
+    create_test_image(&anime_directory.join("sky.png"), 64, 36);
+    create_test_image(&config.wallpaper_directories[0].join("tall.png"), 27, 64);
+    
+    config.tag_rules = vec![
+        TagRule::new("动漫目录".to_string(), "anime".to_string(), vec![
+            RuleCondition::PathPattern { pattern: "**/anime/**".to_string() },
+        ]),
+        TagRule::new("竖屏".to_string(), "portrait".to_string(), vec![
+            RuleCondition::Orientation { orientation: Orientation::Portrait },
+        ]),
+    ];
+    
+    let mut service: WallpaperService = WallpaperService::new(&config)?;
+    service.scan_wallpapers()?;
+    
+    let sky: String = service.search_str("name:sky")?[0].id.clone();
+    assert_eq!(service.get_wallpaper_by_id(&sky).unwrap().tags, vec!["anime"]);
+    assert_eq!(service.get_tag_rule(&sky, "anime").unwrap().name, "动漫目录");
+    assert_eq!(service.search_str("tag:portrait")?.len(), 1);
+    
+    // 修改规则后重新应用：不再匹配的规则标签被移除
+    let mut rules: Vec<TagRule> = vec![
+        TagRule::new("橙色".to_string(), "orange".to_string(), vec![
+            RuleCondition::DominantColor { color: ColorName::Orange },
+        ]),
+    ];
+    service.set_tag_rules(rules.clone())?;
+    let report = service.reapply_tag_rules()?;
+    assert_eq!((report.added, report.removed), (2, 2));
+    assert_eq!(service.get_wallpaper_by_id(&sky).unwrap().tags, vec!["orange"]);
+    
+    rules.push(TagRule::new("无效".to_string(), "bad".to_string(), Vec::new()));
+    assert!(service.set_tag_rules(rules).is_err());
+    assert_eq!(service.get_tag_rules().len(), 1);
+    
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn test_xmp_keywords_imported_as_tags() -> Result<()> {
+    use Wallpaper_Explorer::services::WallpaperService;
+    
+    let root: std::path::PathBuf = create_test_directory("xmp");
+    let config = create_test_config(&root);
+    
+    let xmp: Vec<u8> = test_fixtures::xmp_segment(
+        "<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF><rdf:Description>\
+         <dc:subject><rdf:Bag><rdf:li>Travel/Japan</rdf:li></rdf:Bag></dc:subject>\
+         <dc:creator><rdf:Seq><rdf:li>Jane Doe</rdf:li></rdf:Seq></dc:creator>\
+         </rdf:Description></rdf:RDF></x:xmpmeta>"
+    );
+    create_test_jpeg_with_app1(&config.wallpaper_directories[0].join("kyoto.jpg"), 16, 9, &[xmp]);
+    
+    let mut service: WallpaperService = WallpaperService::new(&config)?;
+    service.scan_wallpapers()?;
+    
+    let found = service.search_str("tag:travel artist:\"jane doe\"")?;
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].tags, vec!["travel/japan"]);
+    assert_eq!(found[0].metadata.get_attribution().as_deref(), Some("Jane Doe"));
+    
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn test_exif_orientation_applied() -> Result<()> {
+    use Wallpaper_Explorer::services::WallpaperService;
+    
+    let root: std::path::PathBuf = create_test_directory("orientation");
+    let config = create_test_config(&root);
+    let directory: &std::path::Path = &config.wallpaper_directories[0];
+    // 传感器方向为横向，EXIF 方向 6 表示需要顺时针旋转 90°
+    create_test_jpeg_with_app1(&directory.join("phone.jpg"), 32, 18, &[test_fixtures::exif_orientation_segment(6)]);
+    create_test_jpeg_with_app1(&directory.join("plain.jpg"), 32, 18, &[test_fixtures::exif_orientation_segment(1)]);
+    
+    let mut service: WallpaperService = WallpaperService::new(&config)?;
+    service.scan_wallpapers()?;
+    
+    let phone = service.search_str("name:phone")?[0].clone();
+    assert_eq!(phone.size, (18, 32));
+    assert_eq!(phone.metadata.orientation, Some(6));
+    assert_eq!(service.search_str("is:portrait")?.len(), 1);
+    
+    let thumbnail = image::open(phone.thumbnail_path.as_ref().unwrap()).unwrap();
+    assert!(thumbnail.height() > thumbnail.width());
+    
+    let applied: std::path::PathBuf = service.prepare_wallpaper_file(&phone.id)?;
+    assert_ne!(applied, phone.path);
+    assert_eq!(image::image_dimensions(&applied).unwrap(), (18, 32));
+    assert_eq!(service.prepare_wallpaper_file(&phone.id)?, applied);
+    
+    let plain = service.search_str("name:plain")?[0].clone();
+    assert_eq!(service.prepare_wallpaper_file(&plain.id)?, plain.path);
+    
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn test_palette_and_color_search() -> Result<()> {
+    use Wallpaper_Explorer::config::Config;
+    use Wallpaper_Explorer::services::WallpaperService;
+    
+    let root: std::path::PathBuf = create_test_directory("palette");
+    let config: Config = create_test_config(&root);
+    image::RgbImage::from_pixel(64, 36, image::Rgb([15, 20, 60]))
+        .save(config.wallpaper_directories[0].join("night.png"))
+        .unwrap();
+    create_test_image(&config.wallpaper_directories[0].join("desert.png"), 64, 36);
+    
+    let mut service: WallpaperService = WallpaperService::new(&config)?;
+    service.scan_wallpapers()?;
+    
+    let names: Vec<&str> = service.search_str("is:dark")?
+        .into_iter()
+        .map(|wallpaper| wallpaper.filename.as_str())
+        .collect();
+    assert_eq!(names, vec!["night.png"]);
+    assert_eq!(service.search_str("color:#c87828")?[0].filename, "desert.png");
+    
+    let ranked: Vec<&str> = service.search_by_color([20, 30, 80], 10)
+        .into_iter()
+        .map(|wallpaper| wallpaper.filename.as_str())
+        .collect();
+    assert_eq!(ranked, vec!["night.png", "desert.png"]);
+    
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn test_usage_survives_external_rename() -> Result<()> {
+    use Wallpaper_Explorer::components::RotationScheduler;
+    use Wallpaper_Explorer::config::Config;
+    use Wallpaper_Explorer::models::Wallpaper;
+    use Wallpaper_Explorer::services::{CollectionService, WallpaperService};
+    
+    let root: std::path::PathBuf = create_test_directory("usage");
+    let config: Config = create_test_config(&root);
+    let directory: std::path::PathBuf = config.wallpaper_directories[0].clone();
+    create_test_image(&directory.join("a.png"), 32, 18);
+    image::RgbImage::from_pixel(32, 18, image::Rgb([10, 90, 30]))
+        .save(directory.join("b.png"))
+        .unwrap();
+    
+    let mut service: WallpaperService = WallpaperService::new(&config)?;
+    service.scan_wallpapers()?;
+    let a: String = service.search_str("name:a")?[0].id.clone();
+    service.set_rating(std::slice::from_ref(&a), 5)?;
+    service.set_favorite(std::slice::from_ref(&a), true)?;
+    service.add_tag_to_wallpapers(std::slice::from_ref(&a), "keep")?;
+    service.record_wallpaper_applied(&a)?;
+    assert_eq!(service.get_never_used().len(), 1);
+    let mut collections: CollectionService = CollectionService::new(&config)?;
+    let album_id: String = collections.create_album("保留", "")?.id.clone();
+    collections.add_wallpapers(&album_id, std::slice::from_ref(&a))?;
+    
+    // 在程序外重命名文件，重新扫描后评分、收藏、标签和历史都跟随新 ID
+    std::fs::rename(directory.join("a.png"), directory.join("renamed.png"))?;
+    let mut service: WallpaperService = WallpaperService::new(&config)?;
+    service.scan_wallpapers()?;
+    
+    let renamed: &Wallpaper = service.search_str("name:renamed")?[0];
+    assert!(renamed.rating == 5 && renamed.favorite);
+    assert_eq!(renamed.tags, vec!["keep"]);
+    assert_eq!(service.get_scan_renames().get(&a), Some(&renamed.id));
+    let mut collections: CollectionService = CollectionService::new(&config)?;
+    collections.replace_ids(service.get_scan_renames())?;
+    assert_eq!(collections.get_album(&album_id).unwrap().wallpaper_ids, vec![renamed.id.clone()]);
+    assert_eq!(service.get_recently_applied(5)[0].id, renamed.id);
+    assert_eq!(service.get_most_used(5).len(), 1);
+    assert_eq!(service.search_str("rating>=4 is:favorite")?.len(), 1);
+    
+    // 轮换时跳过刚使用过的壁纸
+    let candidates: Vec<&Wallpaper> = service.get_wallpapers().iter().collect();
+    let mut scheduler: RotationScheduler = RotationScheduler::new(1);
+    let next: &Wallpaper = service.pick_rotation_wallpaper(&mut scheduler, &candidates).unwrap();
+    assert_eq!(next.filename, "b.png");
+    
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn test_quality_analyzed_in_background() -> Result<()> {
+    use Wallpaper_Explorer::config::Config;
+    use Wallpaper_Explorer::services::WallpaperService;
+    
+    let root: std::path::PathBuf = create_test_directory("quality");
+    let config: Config = create_test_config(&root);
+    let directory: std::path::PathBuf = config.wallpaper_directories[0].clone();
+    create_test_image(&directory.join("a.png"), 64, 36);
+    create_test_image(&directory.join("b.png"), 48, 27);
+    
+    // 扫描不等待质量分析，结果在后台完成后应用
+    let mut service: WallpaperService = WallpaperService::new(&config)?;
+    service.scan_wallpapers()?;
+    let generation: u64 = service.get_generation();
+    assert_eq!(service.wait_quality_analysis()?, 2);
+    assert!(service.get_generation() > generation);
+    assert!(service.get_wallpapers().iter().all(|wallpaper| wallpaper.quality.is_some()));
+    assert_eq!(service.search_str("quality>=0")?.len(), 2);
+    
+    // 内容未变化的文件在扫描时直接沿用索引中的结果
+    let mut service: WallpaperService = WallpaperService::new(&config)?;
+    service.scan_wallpapers()?;
+    assert!(service.get_wallpapers().iter().all(|wallpaper| wallpaper.quality.is_some()));
+    assert_eq!(service.wait_quality_analysis()?, 0);
+    
+    let _ = std::fs::remove_dir_all(&root);
+    Ok(())
+}
+
+#[test]
+fn test_cache_rebuild_without_scan() -> Result<()> {
+    use Wallpaper_Explorer::config::Config;
+    use Wallpaper_Explorer::services::{RebuildProgress, WallpaperService};
+    
+    let root: std::path::PathBuf = create_test_directory("cache_rebuild");
+    let config: Config = create_test_config(&root);
+    let directory: std::path::PathBuf = config.wallpaper_directories[0].clone();
+    create_test_image(&directory.join("a.png"), 64, 36);
+    std::fs::create_dir_all(directory.join("nested")).unwrap();
+    create_test_image(&directory.join("nested/b.png"), 48, 27);
+    std::fs::write(directory.join("notes.txt"), "不是图片").unwrap();
+    
+    // 命令行重建直接遍历壁纸目录，不扫描壁纸库，也不会启动随进程退出而丢弃的后台质量分析
+    let mut service: WallpaperService = WallpaperService::new(&config)?;
+    let progress: RebuildProgress = service.rebuild_thumbnail_cache(|_| true)?;
+    assert_eq!((progress.total, progress.completed, progress.failed), (2, 2, 0));
+    assert!(service.get_wallpapers().is_empty());
+    assert_eq!(service.wait_quality_analysis()?, 0);
+    assert_eq!(service.verify_thumbnail_cache()?.count, 2);
+    
+    let _ = std::fs::remove_dir_all(&root);
     Ok(())
-} 
\ No newline at end of file
+}
\ No newline at end of file